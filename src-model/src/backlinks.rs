@@ -0,0 +1,272 @@
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, NoteInfo, PageNodeType, FindOptions, Transaction, note_title, resolve_link};
+
+// which notes link to which. the backend keeps every note's links indexed
+// (see `links.rs` there), w/ each block's text so it can also find where a
+// note's title is mentioned w/o a link. a link is a `[[note]]`, or a
+// markdown link to the note's file from the folder the linking note is in
+// (e.g. `[see](../ideas.md)`)
+
+/// a link in a block's text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkRef {
+    /// the `[[link]]`, or the url if `url`
+    pub link: String,
+    pub url: bool,
+    /// byte offsets into the block's text
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedBlock {
+    pub text: String,
+    pub links: Vec<LinkRef>,
+}
+
+/// a note as the link index has it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedNote {
+    pub path: String,
+    pub title: String,
+    /// the leaf blocks, in order
+    pub blocks: Vec<IndexedBlock>,
+}
+impl IndexedNote {
+    pub fn new(path: &str, doc: &Doc) -> Self {
+        Self { path: path.into(), title: note_title(path), blocks: doc.index_links() }
+    }
+}
+
+/// a link to the note from another one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backlink {
+    /// the note the link's in
+    pub path: String,
+    pub title: String,
+    /// the sentence the link's in
+    pub context: String,
+}
+
+/// the note's title in another note's text, but not linked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mention {
+    pub path: String,
+    pub title: String,
+    /// which leaf block it's in, and where in the block's text
+    pub block: usize,
+    pub start: usize,
+    pub end: usize,
+    /// the title as it's written there, so it can be checked it's still
+    /// there before it's turned into a link
+    pub text: String,
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backlinks {
+    pub links: Vec<Backlink>,
+    pub mentions: Vec<Mention>,
+}
+
+/// `\` -> `/`, so paths from windows compare the same
+fn normal_path(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+pub fn same_path(a: &str, b: &str) -> bool {
+    normal_path(a) == normal_path(b)
+}
+
+/// the file a markdown link in the note at `from` goes to, if it's a link to
+/// a markdown file at all (and not e.g. a website)
+pub fn resolve_url(from: &str, url: &str) -> Option<String> {
+    if url.contains("://") || url.starts_with("mailto:") || url.starts_with('/') { return None }
+    let url = url.split(['#', '?']).next()?.replace("%20", " ");
+    if !url.ends_with(".md") { return None }
+    let from = normal_path(from);
+    let mut parts: Vec<&str> = from.split('/').collect();
+    // the note's own file name
+    parts.pop();
+    for part in url.split(['/', '\\']) {
+        match part {
+            "" | "." => {},
+            ".." => { parts.pop()?; },
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// the sentence in `text` that `start..end` is in
+fn sentence_around(text: &str, start: usize, end: usize) -> String {
+    // a `.` etc followed by a space (or the end), so `v1.2` isn't 2 sentences
+    let ends_sentence = |i: usize, c: char| {
+        matches!(c, '.' | '!' | '?') && !text[i + 1..].starts_with(|c: char| !c.is_whitespace())
+    };
+    let from = text[..start].char_indices().rev()
+        .find(|(i, c)| ends_sentence(*i, *c))
+        .map_or(0, |(i, _)| i + 1);
+    let to = text[end..].char_indices()
+        .find(|(i, c)| ends_sentence(end + i, *c))
+        .map_or(text.len(), |(i, _)| end + i + 1);
+    text[from..to].trim().to_string()
+}
+
+/// the links to the note at `target`, and where its title is mentioned w/o
+/// one, in all the other `notes`
+pub fn find_backlinks(notes: &[IndexedNote], target: &str) -> Backlinks {
+    let infos: Vec<NoteInfo> = notes.iter().map(|n| {
+        NoteInfo { title: n.title.clone(), path: n.path.clone(), headings: Vec::new() }
+    }).collect();
+    let title = note_title(target);
+    let finder = (!title.trim().is_empty())
+        .then(|| FindOptions { whole_word: true, ..Default::default() }.compile(&title).ok())
+        .flatten();
+    let mut backlinks = Backlinks::default();
+    for note in notes {
+        if same_path(&note.path, target) { continue }
+        for (i, block) in note.blocks.iter().enumerate() {
+            for link in &block.links {
+                let goes_to = match link.url {
+                    true => resolve_url(&note.path, &link.link),
+                    false => resolve_link(&infos, &link.link).map(|n| n.path.clone()),
+                };
+                if goes_to.is_some_and(|p| same_path(&p, target)) {
+                    backlinks.links.push(Backlink {
+                        path: note.path.clone(),
+                        title: note.title.clone(),
+                        context: sentence_around(&block.text, link.start, link.end),
+                    });
+                }
+            }
+            let Some(finder) = &finder else { continue };
+            for m in finder.find_iter(&block.text) {
+                let in_link = block.links.iter().any(|l| m.start() < l.end && l.start < m.end());
+                if in_link { continue }
+                backlinks.mentions.push(Mention {
+                    path: note.path.clone(),
+                    title: note.title.clone(),
+                    block: i,
+                    start: m.start(),
+                    end: m.end(),
+                    text: m.as_str().to_string(),
+                    context: sentence_around(&block.text, m.start(), m.end()),
+                });
+            }
+        }
+    }
+    backlinks
+}
+
+impl Doc {
+    /// each leaf block's text and the links in it, for the link index
+    pub fn index_links(&self) -> Vec<IndexedBlock> {
+        self.leaf_blocks().into_iter().map(|block| {
+            let mut text = String::new();
+            let mut links: Vec<LinkRef> = Vec::new();
+            // the link the last text node was in, so the text nodes in the
+            // same link (e.g. w/ bold in it) make one `LinkRef`
+            let mut last = None;
+            for hash in self.texts(&block) {
+                let start = text.len();
+                text.push_str(self.text(&hash));
+                let link = self.link_around(&hash, &block);
+                match (&link, &last) {
+                    (Some(l), Some(prev)) if l == prev => links.last_mut().unwrap().end = text.len(),
+                    (Some(l), _) => {
+                        let url = *self.kind(l) == PageNodeType::UrlLink;
+                        let target = match url {
+                            true => self.content(l, "url").unwrap_or("").to_string(),
+                            // no link means it goes to whatever the label says
+                            false => self.content(l, "link").filter(|t| !t.is_empty())
+                                .map(String::from).unwrap_or_else(|| self.plain_text(l)),
+                        };
+                        links.push(LinkRef { link: target, url, start, end: text.len() });
+                    },
+                    (None, _) => {},
+                }
+                last = link;
+            }
+            links.retain(|l| !l.link.is_empty());
+            IndexedBlock { text, links }
+        }).collect()
+    }
+    /// the link the text node is in, if any, w/in `block`
+    fn link_around(&self, hash: &str, block: &str) -> Option<String> {
+        let mut node = self.parent(hash)?;
+        while node != block {
+            if matches!(self.kind(node), PageNodeType::FileLink | PageNodeType::UrlLink) {
+                return Some(node.to_string())
+            }
+            node = self.parent(node)?;
+        }
+        None
+    }
+    /// turn the mention into a link to `link`. `None` if it isn't there
+    /// anymore, or it's split across text nodes (e.g. half of it is bold)
+    pub fn link_mention(&self, mention: &Mention, link: &str) -> Option<Transaction> {
+        let block = self.leaf_blocks().get(mention.block)?.clone();
+        if self.plain_text(&block).get(mention.start..mention.end) != Some(mention.text.as_str()) {
+            return None
+        }
+        let mut offset = 0;
+        for hash in self.texts(&block) {
+            let len = self.text(&hash).len();
+            if offset <= mention.start && mention.end <= offset + len {
+                return self.wrap_in_file_link(&hash, mention.start - offset, mention.end - offset, link,
+                    &mention.text);
+            }
+            offset += len;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(path: &str, md: &str) -> IndexedNote {
+        IndexedNote::new(path, &Doc::from_markdown(md, 1))
+    }
+
+    #[test]
+    fn finds_backlinks() {
+        let notes = vec![
+            index("/w/Ideas.md", "# Ideas\nsee [[Ideas]] here\n"),
+            index("/w/a.md", "First one. Go to [[ideas#Later|the later ones]]! Last one.\n"),
+            index("/w/sub/b.md", "[old](../Ideas.md) and [site](https://ideas.md). More ideas, not Ideasx.\n"),
+        ];
+        assert_eq!(notes[1].blocks[0].links, vec![LinkRef {
+            link: "ideas#Later".into(), url: false, start: 17, end: 31,
+        }]);
+        let found = find_backlinks(&notes, "/w/Ideas.md");
+        let contexts: Vec<_> = found.links.iter().map(|l| (l.title.as_str(), l.context.as_str())).collect();
+        assert_eq!(contexts, vec![
+            ("a", "Go to the later ones!"),
+            ("b", "old and site."),
+        ]);
+        // not in a link, not part of a word, and not in the note itself
+        assert_eq!(found.mentions.len(), 1);
+        let mention = &found.mentions[0];
+        assert_eq!((mention.path.as_str(), mention.text.as_str(), mention.context.as_str()),
+            ("/w/sub/b.md", "ideas", "More ideas, not Ideasx."));
+
+        assert_eq!(resolve_url("C:\\w\\a.md", "sub/b%20c.md#top").as_deref(), Some("C:/w/sub/b c.md"));
+        assert!(resolve_url("/w/a.md", "b.png").is_none());
+    }
+
+    #[test]
+    fn links_mention() {
+        let md = "More ideas, not Ideasx.\n";
+        let mut doc = Doc::from_markdown(md, 1);
+        let notes = vec![IndexedNote::new("/w/b.md", &doc), index("/w/Ideas.md", "")];
+        let mention = find_backlinks(&notes, "/w/Ideas.md").mentions.remove(0);
+        doc.apply(&doc.link_mention(&mention, "Ideas").unwrap()).unwrap();
+        assert_eq!(doc.to_markdown(), "More [[Ideas|ideas]], not Ideasx.\n");
+        // it's not there anymore
+        assert!(doc.link_mention(&mention, "Ideas").is_none());
+    }
+}
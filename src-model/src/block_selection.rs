@@ -0,0 +1,132 @@
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, NodeTree, PageNodeType, Op, Transaction, SyntaxProfile, tree_to_markdown_with};
+
+// block selection mode: whole blocks selected as units rather than text, so
+// they can be changed all at once. what's selected is always a run of
+// siblings (see `Doc::block_range`), so every edit here is on siblings too
+
+/// whole blocks selected, from `anchor` (where it started) to `head` (the
+/// end the arrow keys move)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSelection {
+    pub anchor: String,
+    pub head: String,
+}
+impl BlockSelection {
+    pub fn new(block: &str) -> Self {
+        Self { anchor: block.to_string(), head: block.to_string() }
+    }
+}
+
+impl Doc {
+    /// the blocks that are selected, as siblings
+    pub fn block_selection_blocks(&self, sel: &BlockSelection) -> Vec<String> {
+        if !self.contains(&sel.anchor) || !self.contains(&sel.head) { return Vec::new() }
+        self.block_range(&sel.anchor, &sel.head)
+    }
+    /// the arrow keys. the head moves a leaf block up/down (out of a branch
+    /// block if it was on one). stays put at the top/bottom of the page
+    pub fn extend_block_selection(&self, sel: &BlockSelection, forward: bool) -> BlockSelection {
+        let next = match forward {
+            true => self.last_leaf_block(&sel.head).and_then(|b| self.next_leaf_block(&b)),
+            false => self.first_leaf_block(&sel.head).and_then(|b| self.prev_leaf_block(&b)),
+        };
+        BlockSelection { anchor: sel.anchor.clone(), head: next.unwrap_or_else(|| sel.head.clone()) }
+    }
+    /// turn all the leaf blocks in the blocks into `kind`. a quote wraps them
+    /// instead. tables and dividers have no text to keep, so they're left
+    pub fn set_blocks_kind(&self, hashes: &[String], kind: PageNodeType) -> Transaction {
+        if kind == PageNodeType::Quote {
+            return Op::WrapIn { hashes: hashes.to_vec(), kind }.into();
+        }
+        let mut tx = Transaction::new();
+        for block in hashes.iter().flat_map(|h| self.leaf_blocks_in(h)) {
+            let old = self.kind(&block).clone();
+            if old == kind || old == PageNodeType::Table || old == PageNodeType::Divider { continue }
+            tx.push(Op::SetBlockKind { hash: block.clone(), kind: kind.clone() });
+            if kind == PageNodeType::Check || old == PageNodeType::Check {
+                tx.push(Op::SetContent { hash: block, key: "checked".into(),
+                    val: (kind == PageNodeType::Check).then(|| "false".into()) });
+            }
+        }
+        tx
+    }
+    /// Tab. the blocks go into the indent above them if there is one, so
+    /// indenting blocks one after another doesn't make an indent for each
+    pub fn indent_blocks(&self, hashes: &[String]) -> Option<Transaction> {
+        let prev = self.prev_sibling(hashes.first()?);
+        Some(match prev {
+            Some(prev) if *self.kind(&prev) == PageNodeType::Indent => {
+                Op::MoveNodes { hashes: hashes.to_vec(), parent: prev, index: usize::MAX }.into()
+            },
+            _ => Op::WrapIn { hashes: hashes.to_vec(), kind: PageNodeType::Indent }.into(),
+        })
+    }
+    /// Shift + Tab. takes the blocks out of the branch block they're in.
+    /// `None` if they're already at the top level
+    pub fn outdent_blocks(&self, hashes: &[String]) -> Option<Transaction> {
+        let parent = self.parent(hashes.first()?)?;
+        if !self.kind(parent).is_block() || self.parent(parent).is_none() { return None }
+        // last first, so each goes in right below the branch, above the ones
+        // already lifted out
+        let ops = hashes.iter().rev().map(|h| Op::LiftBlock { hash: h.clone() }).collect::<Vec<_>>();
+        Some(ops.into())
+    }
+    /// the blocks as markdown, e.g. for copying them
+    pub fn blocks_markdown(&self, hashes: &[String], profile: &SyntaxProfile) -> String {
+        let trees = hashes.iter().map(|h| self.tree(h)).collect();
+        tree_to_markdown_with(&NodeTree::new(PageNodeType::Page, trees), profile)
+    }
+    /// the leaf blocks at or under `hash`
+    fn leaf_blocks_in(&self, hash: &str) -> Vec<String> {
+        let kind = self.kind(hash);
+        if kind.is_leaf_block() { return vec![hash.to_string()] }
+        self.children(hash).iter().flat_map(|c| self.leaf_blocks_in(c)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extends_over_blocks() {
+        let doc = Doc::from_markdown("a\n> b\n> c\n\nd\n", 1);
+        let blocks = doc.leaf_blocks();
+        let quote = doc.parent(&blocks[1]).unwrap().to_string();
+        let sel = BlockSelection::new(&blocks[0]);
+        let sel = doc.extend_block_selection(&sel, true);
+        assert_eq!(sel.head, blocks[1]);
+        assert_eq!(doc.block_selection_blocks(&sel), vec![blocks[0].clone(), quote.clone()]);
+        let sel = doc.extend_block_selection(&doc.extend_block_selection(&sel, true), true);
+        assert_eq!(doc.block_selection_blocks(&sel), vec![blocks[0].clone(), quote.clone(), blocks[3].clone()]);
+        // can't go past the end
+        assert_eq!(doc.extend_block_selection(&sel, true), sel);
+        // backwards from inside the quote
+        let sel = BlockSelection::new(&blocks[2]);
+        let sel = doc.extend_block_selection(&doc.extend_block_selection(&sel, false), false);
+        assert_eq!(doc.block_selection_blocks(&sel), vec![blocks[0].clone(), quote]);
+    }
+
+    #[test]
+    fn bulk_edits() {
+        let mut doc = Doc::from_markdown("a\n\n- [x] b\n\nc\n", 1);
+        let blocks = doc.leaf_blocks();
+        doc.apply(&doc.set_blocks_kind(&blocks[..2], PageNodeType::H2)).unwrap();
+        assert_eq!(doc.to_markdown(), "## a\n## b\nc\n");
+        assert_eq!(doc.blocks_markdown(&blocks[1..], &SyntaxProfile::standard()), "## b\nc\n");
+
+        doc.apply(&doc.indent_blocks(&blocks[..1]).unwrap()).unwrap();
+        doc.apply(&doc.indent_blocks(&blocks[1..2]).unwrap()).unwrap();
+        let indent = doc.parent(&blocks[0]).unwrap().to_string();
+        assert_eq!(doc.children(&indent), &blocks[..2]);
+        assert!(doc.outdent_blocks(std::slice::from_ref(&indent)).is_none());
+        doc.apply(&doc.outdent_blocks(&blocks[..2]).unwrap()).unwrap();
+        assert!(!doc.contains(&indent));
+        assert_eq!(doc.leaf_blocks(), blocks);
+
+        doc.apply(&doc.set_blocks_kind(&blocks[1..], PageNodeType::Quote)).unwrap();
+        assert_eq!(doc.to_markdown(), "## a\n> ## b\n> c\n");
+    }
+}
@@ -0,0 +1,568 @@
+use std::{collections::HashMap, fmt};
+use serde::{Serialize, Deserialize};
+
+use super::{PageNodeType, HashAllocator, Op, Transaction, PINNED, is_valid_hash};
+
+/// hash of the `Page` node at the root of every doc
+pub const ROOT_HASH: &str = "";
+
+/// a node in the doc. unlike the UI's `PageNode`, parent and children are
+/// referenced by hash so the whole tree can live in one flat map with no DOM
+/// or signals attached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocNode {
+    pub hash: String,
+    pub kind: PageNodeType,
+    pub content: HashMap<String, String>,
+    pub children: Vec<String>,
+    pub parent: Option<String>,
+}
+
+/// an owned copy of a node and all its children. used to move subtrees in and
+/// out of the doc (and across the tauri bridge)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeTree {
+    /// leave empty to have the doc generate one on insert
+    pub hash: String,
+    pub kind: PageNodeType,
+    pub content: HashMap<String, String>,
+    pub children: Vec<NodeTree>,
+}
+impl NodeTree {
+    pub fn new(kind: PageNodeType, children: Vec<NodeTree>) -> Self {
+        Self { hash: String::new(), kind, content: HashMap::new(), children }
+    }
+    /// a `RawText` node
+    pub fn text(text: &str) -> Self {
+        let mut node = Self::new(PageNodeType::RawText, Vec::new());
+        node.content.insert("text".into(), text.into());
+        node
+    }
+    /// a leaf block holding a single `RawText` node
+    pub fn block(kind: PageNodeType, text: &str) -> Self {
+        Self::new(kind, vec![Self::text(text)])
+    }
+    /// whether there's a `RawText` node anywhere in here, i.e. somewhere for
+    /// the caret to go
+    pub fn has_text(&self) -> bool {
+        self.kind == PageNodeType::RawText || self.children.iter().any(|c| c.has_text())
+    }
+    /// all the text in this node and its children, without any markdown
+    pub fn plain_text(&self) -> String {
+        let mut text = self.content.get("text").cloned().unwrap_or_default();
+        for child in &self.children {
+            text.push_str(&child.plain_text());
+        }
+        text
+    }
+}
+
+/// a position in the doc: a `RawText` node and a byte offset into its text
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Pos {
+    pub hash: String,
+    pub offset: usize,
+}
+impl Pos {
+    pub fn new(hash: &str, offset: usize) -> Self {
+        Self { hash: hash.to_string(), offset }
+    }
+}
+
+/// sent to subscribers every time the doc changes, so the UI can patch the DOM
+/// to match
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Change {
+    /// `node` was inserted as child number `index` of `parent`
+    Inserted { parent: String, index: usize, node: NodeTree },
+    Removed { hash: String },
+    /// `index` is the position in `parent` after the node was taken out of
+    /// its old parent
+    Moved { hash: String, parent: String, index: usize },
+    KindChanged { hash: String, kind: PageNodeType },
+    /// `text` is the full new text of the node
+    TextChanged { hash: String, text: String },
+    /// a content entry other than `"text"` was set (`None` = removed)
+    ContentChanged { hash: String, key: String, val: Option<String> },
+}
+
+type Listener = Box<dyn FnMut(&Change)>;
+
+pub struct Doc {
+    nodes: HashMap<String, DocNode>,
+    hashes: HashAllocator,
+    listeners: Vec<Listener>,
+    /// while `apply` is running, the ops that undo each change made
+    pub(crate) undo_log: Option<Vec<Op>>,
+    /// the inverse of each transaction applied since `start_journal`
+    pub(crate) journal: Option<Vec<Transaction>>,
+}
+impl fmt::Debug for Doc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Doc")
+            .field("nodes", &self.nodes)
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+/// two docs are equal if they have the same nodes, regardless of subscribers
+impl PartialEq for Doc {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+    }
+}
+impl Eq for Doc {}
+impl Doc {
+    /// an empty doc (just the `Page` root). `seed` seeds the hash generator
+    pub fn new(seed: u64) -> Self {
+        let root = DocNode {
+            hash: ROOT_HASH.to_string(), kind: PageNodeType::Page,
+            content: HashMap::new(), children: Vec::new(), parent: None,
+        };
+        Self {
+            nodes: HashMap::from([(ROOT_HASH.to_string(), root)]),
+            hashes: HashAllocator::new(seed),
+            listeners: Vec::new(),
+            undo_log: None,
+            journal: None,
+        }
+    }
+    /// build a doc from a `Page` tree, keeping any hashes already in the tree
+    pub fn from_tree(tree: NodeTree, seed: u64) -> Self {
+        let mut doc = Self::new(seed);
+        for child in tree.children {
+            doc.insert_tree(ROOT_HASH, usize::MAX, child);
+        }
+        doc
+    }
+    /// `listener` gets called with every change made to the doc from now on
+    pub fn subscribe(&mut self, listener: impl FnMut(&Change) + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+    fn emit(&mut self, change: Change) {
+        for listener in self.listeners.iter_mut() {
+            listener(&change);
+        }
+    }
+    pub fn new_hash(&mut self) -> String {
+        self.hashes.new_hash()
+    }
+}
+
+// ===QUERIES===
+
+impl Doc {
+    pub fn node(&self, hash: &str) -> Option<&DocNode> {
+        self.nodes.get(hash)
+    }
+    pub fn contains(&self, hash: &str) -> bool {
+        self.nodes.contains_key(hash)
+    }
+    fn get(&self, hash: &str) -> &DocNode {
+        match self.nodes.get(hash) {
+            Some(node) => node,
+            None => panic!("no node with hash {:?}", hash),
+        }
+    }
+    fn get_mut(&mut self, hash: &str) -> &mut DocNode {
+        match self.nodes.get_mut(hash) {
+            Some(node) => node,
+            None => panic!("no node with hash {:?}", hash),
+        }
+    }
+    pub fn kind(&self, hash: &str) -> &PageNodeType {
+        &self.get(hash).kind
+    }
+    /// text of a `RawText` node (`""` for anything else)
+    pub fn text(&self, hash: &str) -> &str {
+        self.get(hash).content.get("text").map(|t| t.as_str()).unwrap_or("")
+    }
+    pub fn content(&self, hash: &str, key: &str) -> Option<&str> {
+        self.get(hash).content.get(key).map(|v| v.as_str())
+    }
+    pub fn children(&self, hash: &str) -> &[String] {
+        &self.get(hash).children
+    }
+    pub fn parent(&self, hash: &str) -> Option<&str> {
+        self.get(hash).parent.as_deref()
+    }
+    pub fn index_in_parent(&self, hash: &str) -> Option<usize> {
+        let parent = self.parent(hash)?;
+        self.children(parent).iter().position(|c| c == hash)
+    }
+    pub fn prev_sibling(&self, hash: &str) -> Option<String> {
+        let idx = self.index_in_parent(hash)?;
+        if idx == 0 { return None }
+        Some(self.children(self.parent(hash)?)[idx - 1].clone())
+    }
+    pub fn next_sibling(&self, hash: &str) -> Option<String> {
+        let idx = self.index_in_parent(hash)?;
+        self.children(self.parent(hash)?).get(idx + 1).cloned()
+    }
+    /// true if `ancestor` is a parent, grandparent, etc of `hash`
+    pub fn is_ancestor(&self, ancestor: &str, hash: &str) -> bool {
+        let mut hash = hash;
+        while let Some(parent) = self.parent(hash) {
+            if parent == ancestor { return true }
+            hash = parent;
+        }
+        false
+    }
+    /// the leaf block `hash` is in (or `hash` itself if it is a leaf block)
+    pub fn block_of(&self, hash: &str) -> Option<String> {
+        let mut hash = hash;
+        loop {
+            if self.kind(hash).is_leaf_block() { return Some(hash.to_string()) }
+            hash = self.parent(hash)?;
+        }
+    }
+    /// all leaf blocks in the doc, top to bottom
+    pub fn leaf_blocks(&self) -> Vec<String> {
+        let mut blocks = Vec::new();
+        self.collect_leaf_blocks(ROOT_HASH, &mut blocks);
+        blocks
+    }
+    fn collect_leaf_blocks(&self, hash: &str, blocks: &mut Vec<String>) {
+        let kind = self.kind(hash);
+        if kind.is_leaf_block() {
+            blocks.push(hash.to_string());
+        } else if kind.is_block() {
+            for child in self.children(hash) {
+                self.collect_leaf_blocks(child, blocks);
+            }
+        }
+    }
+    /// the top-most leaf block at or under `hash`
+    pub fn first_leaf_block(&self, hash: &str) -> Option<String> {
+        let kind = self.kind(hash);
+        if kind.is_leaf_block() { return Some(hash.to_string()) }
+        if !kind.is_block() { return None }
+        self.children(hash).iter().find_map(|c| self.first_leaf_block(c))
+    }
+    /// the bottom-most leaf block at or under `hash`
+    pub fn last_leaf_block(&self, hash: &str) -> Option<String> {
+        let kind = self.kind(hash);
+        if kind.is_leaf_block() { return Some(hash.to_string()) }
+        if !kind.is_block() { return None }
+        self.children(hash).iter().rev().find_map(|c| self.last_leaf_block(c))
+    }
+    /// the leaf block below this one, regardless of nesting
+    pub fn next_leaf_block(&self, hash: &str) -> Option<String> {
+        let mut hash = hash.to_string();
+        loop {
+            let parent = self.parent(&hash)?;
+            let idx = self.index_in_parent(&hash)?;
+            let next = self.children(parent)[idx + 1..].iter()
+                .find_map(|c| self.first_leaf_block(c));
+            if next.is_some() { return next }
+            hash = parent.to_string();
+        }
+    }
+    /// the leaf block above this one, regardless of nesting
+    pub fn prev_leaf_block(&self, hash: &str) -> Option<String> {
+        let mut hash = hash.to_string();
+        loop {
+            let parent = self.parent(&hash)?;
+            let idx = self.index_in_parent(&hash)?;
+            let prev = self.children(parent)[..idx].iter().rev()
+                .find_map(|c| self.last_leaf_block(c));
+            if prev.is_some() { return prev }
+            hash = parent.to_string();
+        }
+    }
+    /// all `RawText` nodes at or under `hash`, in order
+    pub fn texts(&self, hash: &str) -> Vec<String> {
+        let mut texts = Vec::new();
+        self.collect_texts(hash, &mut texts);
+        texts
+    }
+    fn collect_texts(&self, hash: &str, texts: &mut Vec<String>) {
+        if *self.kind(hash) == PageNodeType::RawText {
+            texts.push(hash.to_string());
+        }
+        for child in self.children(hash) {
+            self.collect_texts(child, texts);
+        }
+    }
+    /// the leaf blocks that have text (so not dividers), top to bottom. the
+    /// caret can only go in these
+    pub fn text_blocks(&self) -> Vec<String> {
+        self.leaf_blocks().into_iter().filter(|b| self.first_text(b).is_some()).collect()
+    }
+    /// the leaf block below this one that has text
+    pub fn next_text_block(&self, hash: &str) -> Option<String> {
+        let mut next = self.next_leaf_block(hash);
+        while let Some(hash) = next {
+            if self.first_text(&hash).is_some() { return Some(hash) }
+            next = self.next_leaf_block(&hash);
+        }
+        None
+    }
+    /// the leaf block above this one that has text
+    pub fn prev_text_block(&self, hash: &str) -> Option<String> {
+        let mut prev = self.prev_leaf_block(hash);
+        while let Some(hash) = prev {
+            if self.first_text(&hash).is_some() { return Some(hash) }
+            prev = self.prev_leaf_block(&hash);
+        }
+        None
+    }
+    pub fn first_text(&self, hash: &str) -> Option<String> {
+        self.texts(hash).into_iter().next()
+    }
+    pub fn last_text(&self, hash: &str) -> Option<String> {
+        self.texts(hash).pop()
+    }
+    /// the `RawText` node before this one in the same block
+    pub fn prev_text(&self, hash: &str) -> Option<String> {
+        let texts = self.texts(&self.block_of(hash)?);
+        let idx = texts.iter().position(|t| t == hash)?;
+        if idx == 0 { return None }
+        Some(texts[idx - 1].clone())
+    }
+    /// the `RawText` node after this one in the same block
+    pub fn next_text(&self, hash: &str) -> Option<String> {
+        let texts = self.texts(&self.block_of(hash)?);
+        let idx = texts.iter().position(|t| t == hash)?;
+        texts.get(idx + 1).cloned()
+    }
+    /// all the text at or under `hash`, without any markdown
+    pub fn plain_text(&self, hash: &str) -> String {
+        self.texts(hash).iter().map(|t| self.text(t)).collect()
+    }
+    /// an owned copy of the node and its children
+    pub fn tree(&self, hash: &str) -> NodeTree {
+        let node = self.get(hash);
+        NodeTree {
+            hash: node.hash.clone(),
+            kind: node.kind.clone(),
+            content: node.content.clone(),
+            children: node.children.iter().map(|c| self.tree(c)).collect(),
+        }
+    }
+    pub fn root_tree(&self) -> NodeTree {
+        self.tree(ROOT_HASH)
+    }
+}
+
+// ===MUTATIONS===
+// every change to the doc goes through one of these so subscribers never miss
+// a change. outside of the crate, edits are made with `Doc::apply` instead, so
+// each one also records the op that undoes it
+
+impl Doc {
+    /// keep the op that undoes the change being made, if `apply` is recording
+    fn record(&mut self, undo: Op) {
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.push(undo);
+        }
+    }
+    /// insert `tree` as child number `index` of `parent` (clamped to the end).
+    /// returns the hash of the inserted node, which will be new if the tree
+    /// had no hash or its hash is already in use
+    pub(crate) fn insert_tree(&mut self, parent: &str, index: usize, mut tree: NodeTree) -> String {
+        self.add_tree(&mut tree, parent);
+        let siblings = &mut self.get_mut(parent).children;
+        let index = index.min(siblings.len());
+        siblings.insert(index, tree.hash.clone());
+        let hash = tree.hash.clone();
+        self.record(Op::RemoveNodes { hashes: vec![hash.clone()] });
+        self.emit(Change::Inserted { parent: parent.to_string(), index, node: tree });
+        hash
+    }
+    fn add_tree(&mut self, tree: &mut NodeTree, parent: &str) {
+        if !is_valid_hash(&tree.hash) || self.nodes.contains_key(&tree.hash) {
+            tree.hash = self.hashes.new_hash();
+            // it'd be saved w/ a different `^block-id` than it was read w/
+            tree.content.remove(PINNED);
+        } else {
+            // if this node was removed and is being put back this fails, which
+            // is fine bc nothing else can have the hash
+            self.hashes.reserve(&tree.hash);
+        }
+        self.nodes.insert(tree.hash.clone(), DocNode {
+            hash: tree.hash.clone(),
+            kind: tree.kind.clone(),
+            content: tree.content.clone(),
+            children: Vec::new(),
+            parent: Some(parent.to_string()),
+        });
+        for child in tree.children.iter_mut() {
+            self.add_tree(child, &tree.hash);
+        }
+        self.get_mut(&tree.hash).children = tree.children.iter()
+            .map(|c| c.hash.clone()).collect();
+    }
+    /// remove the node and all its children, returning them
+    pub(crate) fn remove(&mut self, hash: &str) -> NodeTree {
+        let tree = self.tree(hash);
+        let parent = match self.parent(hash) {
+            Some(parent) => parent.to_string(),
+            None => panic!("can't remove the root node"),
+        };
+        let index = self.index_in_parent(hash).unwrap();
+        self.get_mut(&parent).children.remove(index);
+        self.drop_nodes(hash);
+        self.record(Op::InsertNodes { parent, index, nodes: vec![tree.clone()] });
+        self.emit(Change::Removed { hash: hash.to_string() });
+        tree
+    }
+    fn drop_nodes(&mut self, hash: &str) {
+        if let Some(node) = self.nodes.remove(hash) {
+            for child in node.children {
+                self.drop_nodes(&child);
+            }
+        }
+    }
+    /// move the node (and its children) to be child number `index` of
+    /// `parent`, counting the children once the node has been taken out.
+    /// returns where it was moved from, as `(parent, index)`
+    pub(crate) fn move_node(&mut self, hash: &str, parent: &str, index: usize) -> (String, usize) {
+        if hash == parent || self.is_ancestor(hash, parent) {
+            panic!("can't move node {:?} inside itself", hash);
+        }
+        let old_parent = self.parent(hash).unwrap().to_string();
+        let old_index = self.index_in_parent(hash).unwrap();
+        self.get_mut(&old_parent).children.remove(old_index);
+        let siblings = &mut self.get_mut(parent).children;
+        let index = index.min(siblings.len());
+        siblings.insert(index, hash.to_string());
+        self.get_mut(hash).parent = Some(parent.to_string());
+        self.record(Op::MoveNodes {
+            hashes: vec![hash.to_string()], parent: old_parent.clone(), index: old_index,
+        });
+        self.emit(Change::Moved { hash: hash.to_string(), parent: parent.to_string(), index });
+        (old_parent, old_index)
+    }
+    /// returns the old kind
+    pub(crate) fn set_kind(&mut self, hash: &str, kind: PageNodeType) -> PageNodeType {
+        let old = std::mem::replace(&mut self.get_mut(hash).kind, kind.clone());
+        self.record(Op::SetBlockKind { hash: hash.to_string(), kind: old.clone() });
+        self.emit(Change::KindChanged { hash: hash.to_string(), kind });
+        old
+    }
+    /// set (or remove with `None`) a content entry other than `"text"`.
+    /// returns the old value
+    pub(crate) fn set_content(&mut self, hash: &str, key: &str, val: Option<String>) -> Option<String> {
+        let content = &mut self.get_mut(hash).content;
+        let old = match val.clone() {
+            Some(val) => content.insert(key.to_string(), val),
+            None => content.remove(key),
+        };
+        self.record(Op::SetContent { hash: hash.to_string(), key: key.to_string(), val: old.clone() });
+        self.emit(Change::ContentChanged { hash: hash.to_string(), key: key.to_string(), val });
+        old
+    }
+    /// `offset` is a byte offset into the text
+    pub(crate) fn insert_text(&mut self, hash: &str, offset: usize, text: &str) {
+        let txt = self.get_mut(hash).content.entry("text".into()).or_default();
+        txt.insert_str(offset, text);
+        let new_text = txt.clone();
+        self.record(Op::DeleteRange {
+            start: Pos::new(hash, offset), end: Pos::new(hash, offset + text.len()),
+        });
+        self.emit(Change::TextChanged { hash: hash.to_string(), text: new_text });
+    }
+    /// delete the bytes `start..end` of the text. returns the deleted text
+    pub(crate) fn delete_text(&mut self, hash: &str, start: usize, end: usize) -> String {
+        let txt = self.get_mut(hash).content.entry("text".into()).or_default();
+        let removed: String = txt.drain(start..end).collect();
+        let text = txt.clone();
+        self.record(Op::InsertText { pos: Pos::new(hash, start), text: removed.clone() });
+        self.emit(Change::TextChanged { hash: hash.to_string(), text });
+        removed
+    }
+    /// replace the whole text. returns the old text
+    pub(crate) fn set_text(&mut self, hash: &str, text: &str) -> String {
+        let old = self.get_mut(hash).content.insert("text".into(), text.into())
+            .unwrap_or_default();
+        self.record(Op::SetText { hash: hash.to_string(), text: old.clone() });
+        self.emit(Change::TextChanged { hash: hash.to_string(), text: text.into() });
+        old
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{rc::Rc, cell::RefCell};
+    use PageNodeType::*;
+
+    /// h1, quote[tb, tb], dot
+    fn demo_doc() -> Doc {
+        Doc::from_tree(NodeTree::new(Page, vec![
+            NodeTree::block(H1, "title"),
+            NodeTree::new(Quote, vec![
+                NodeTree::block(TextBlock, "quote 1"),
+                NodeTree::block(TextBlock, "quote 2"),
+            ]),
+            NodeTree::block(Dot, "point"),
+        ]), 1)
+    }
+
+    #[test]
+    fn insert_assigns_unique_hashes() {
+        let doc = demo_doc();
+        let blocks = doc.leaf_blocks();
+        assert_eq!(blocks.len(), 4);
+        for block in &blocks {
+            assert!(is_valid_hash(block));
+            assert_eq!(doc.block_of(&doc.first_text(block).unwrap()).as_ref(), Some(block));
+        }
+        // reusing a live hash gets a new one
+        let mut doc = doc;
+        let mut copy = doc.tree(&blocks[0]);
+        copy.children.clear();
+        let hash = doc.insert_tree(ROOT_HASH, 0, copy);
+        assert_ne!(hash, blocks[0]);
+    }
+
+    #[test]
+    fn leaf_block_navigation_crosses_nesting() {
+        let doc = demo_doc();
+        let blocks = doc.leaf_blocks();
+        assert_eq!(doc.next_leaf_block(&blocks[0]), Some(blocks[1].clone()));
+        assert_eq!(doc.next_leaf_block(&blocks[2]), Some(blocks[3].clone()));
+        assert_eq!(doc.next_leaf_block(&blocks[3]), None);
+        assert_eq!(doc.prev_leaf_block(&blocks[3]), Some(blocks[2].clone()));
+        assert_eq!(doc.prev_leaf_block(&blocks[1]), Some(blocks[0].clone()));
+        assert_eq!(doc.prev_leaf_block(&blocks[0]), None);
+    }
+
+    #[test]
+    fn remove_and_move() {
+        let mut doc = demo_doc();
+        let blocks = doc.leaf_blocks();
+        let quote = doc.parent(&blocks[1]).unwrap().to_string();
+        let (from, idx) = doc.move_node(&blocks[3], &quote, 0);
+        assert_eq!((from.as_str(), idx), (ROOT_HASH, 2));
+        assert_eq!(doc.children(&quote)[0], blocks[3]);
+        let removed = doc.remove(&quote);
+        assert_eq!(removed.children.len(), 3);
+        assert!(!doc.contains(&blocks[1]));
+        assert_eq!(doc.leaf_blocks(), vec![blocks[0].clone()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cant_move_into_self() {
+        let mut doc = demo_doc();
+        let blocks = doc.leaf_blocks();
+        let quote = doc.parent(&blocks[1]).unwrap().to_string();
+        doc.move_node(&quote, &blocks[1], 0);
+    }
+
+    #[test]
+    fn subscribers_see_every_change() {
+        let mut doc = demo_doc();
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let log = changes.clone();
+        doc.subscribe(move |change| log.borrow_mut().push(change.clone()));
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        doc.insert_text(&text, 5, "!");
+        assert_eq!(doc.delete_text(&text, 0, 2), "ti");
+        doc.set_kind(&doc.leaf_blocks()[0], H2);
+        assert_eq!(changes.borrow().len(), 3);
+        assert_eq!(changes.borrow()[1], Change::TextChanged { hash: text.clone(), text: "tle!".into() });
+    }
+}
@@ -0,0 +1,1178 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Doc, NodeTree, PageNodeType, Pos, Op, OpError, Transaction, SyntaxProfile, ROOT_HASH, 
+    prev_grapheme_boundary, next_grapheme_boundary, parse_markdown_with};
+use super::markdown::{check_prefix, parse_span};
+
+// higher level edits built out of the doc's mutation primitives. these back
+// the ops in `transaction.rs`
+
+impl Doc {
+    /// Return key. splits the block at `pos`, moving everything after the
+    /// caret (incl. any spans it's inside of) into a new block of the same
+    /// kind below. returns the start of the new block
+    pub(crate) fn split_block(&mut self, pos: &Pos) -> Pos {
+        let block = self.block_of(&pos.hash).expect("text node not in a block");
+        // the text node and every span between it and the block
+        let mut chain = vec![pos.hash.clone()];
+        while let Some(parent) = self.parent(chain.last().unwrap()) {
+            if parent == block { break }
+            chain.push(parent.to_string());
+        }
+        let len = self.text(&pos.hash).len();
+        let right_text = self.delete_text(&pos.hash, pos.offset, len);
+        // rebuild the chain bottom up for the new block
+        let mut new_tree = NodeTree::text(&right_text);
+        for hash in &chain[1..] {
+            let mut span = NodeTree::new(self.kind(hash).clone(), vec![new_tree]);
+            span.content = self.node(hash).unwrap().content.clone();
+            new_tree = span;
+        }
+        new_tree = NodeTree::new(self.kind(&block).clone(), vec![new_tree]);
+        let parent = self.parent(&block).unwrap().to_string();
+        let idx = self.index_in_parent(&block).unwrap();
+        let new_block = self.insert_tree(&parent, idx + 1, new_tree);
+        // same chain in the new block, bottom up, with the block on the end
+        let mut new_chain = vec![new_block];
+        while new_chain.len() <= chain.len() {
+            let first_child = self.children(new_chain.last().unwrap())[0].clone();
+            new_chain.push(first_child);
+        }
+        new_chain.reverse();
+        // anything after the chain at each level goes to the new block too
+        for (i, hash) in chain.iter().enumerate() {
+            let old_parent = self.parent(hash).unwrap().to_string();
+            let idx = self.index_in_parent(hash).unwrap();
+            let trailing = self.children(&old_parent)[idx + 1..].to_vec();
+            for node in trailing {
+                self.move_node(&node, &new_chain[i + 1], usize::MAX);
+            }
+        }
+        Pos::new(&new_chain[0], 0)
+    }
+    /// merge the block onto the end of the leaf block above it. returns where
+    /// the two joined, or `None` if there's no block above
+    pub(crate) fn merge_into_prev(&mut self, block: &str) -> Option<Pos> {
+        let prev = self.prev_leaf_block(block)?;
+        let caret = self.last_text(&prev).map(|t| Pos::new(&t, self.text(&t).len()));
+        self.merge_children(&prev, block);
+        // (the block could be the only thing in a quote etc)
+        self.remove_pruning(block);
+        caret.or_else(|| self.first_text(&prev).map(|t| Pos::new(&t, 0)))
+    }
+    /// move the children of `from` onto the end of `into`, joining the nodes
+    /// at the seam if they're the same kind (e.g. two bits of bold text)
+    fn merge_children(&mut self, into: &str, from: &str) {
+        let children = self.children(from).to_vec();
+        let Some(first) = children.first() else { return };
+        let mut rest = &children[..];
+        if let Some(last) = self.children(into).last().cloned() {
+            let same = self.kind(&last) == self.kind(first)
+                && self.node(&last).unwrap().content.iter().filter(|(k, _)| *k != "text")
+                    .eq(self.node(first).unwrap().content.iter().filter(|(k, _)| *k != "text"));
+            if same {
+                if *self.kind(first) == PageNodeType::RawText {
+                    let text = self.text(first).to_string();
+                    let len = self.text(&last).len();
+                    self.insert_text(&last, len, &text);
+                } else {
+                    self.merge_children(&last, first);
+                }
+                self.remove(first);
+                rest = &children[1..];
+            }
+        }
+        for child in rest {
+            self.move_node(child, into, usize::MAX);
+        }
+    }
+    /// move the block out of the branch block it's in (e.g. out of a quote).
+    /// blocks after it stay in the branch, in a new branch below
+    pub(crate) fn lift_block(&mut self, block: &str) {
+        let branch = self.parent(block).expect("block has no parent").to_string();
+        let grandparent = self.parent(&branch).expect("can't lift out of the page").to_string();
+        let idx = self.index_in_parent(&branch).unwrap();
+        let block_idx = self.index_in_parent(block).unwrap();
+        let trailing = self.children(&branch)[block_idx + 1..].to_vec();
+        self.move_node(block, &grandparent, idx + 1);
+        if !trailing.is_empty() {
+            let mut new_branch = NodeTree::new(self.kind(&branch).clone(), Vec::new());
+            new_branch.content = self.node(&branch).unwrap().content.clone();
+            let new_branch = self.insert_tree(&grandparent, idx + 2, new_branch);
+            for node in trailing {
+                self.move_node(&node, &new_branch, usize::MAX);
+            }
+        }
+        if self.children(&branch).is_empty() {
+            self.remove(&branch);
+        }
+    }
+    /// replace the branch block with its children
+    pub(crate) fn unwrap_block(&mut self, branch: &str) {
+        let parent = self.parent(branch).expect("can't unwrap the page").to_string();
+        let idx = self.index_in_parent(branch).unwrap();
+        for (i, child) in self.children(branch).to_vec().iter().enumerate() {
+            self.move_node(child, &parent, idx + 1 + i);
+        }
+        self.remove(branch);
+    }
+    /// wrap sibling blocks in a new branch block of `kind`. returns the hash
+    /// of the new branch
+    pub(crate) fn wrap_in(&mut self, hashes: &[String], kind: PageNodeType) -> String {
+        let first = &hashes[0];
+        let parent = self.parent(first).expect("can't wrap the page").to_string();
+        let idx = self.index_in_parent(first).unwrap();
+        let branch = self.insert_tree(&parent, idx, NodeTree::new(kind, Vec::new()));
+        for hash in hashes {
+            self.move_node(hash, &branch, usize::MAX);
+        }
+        branch
+    }
+    /// delete everything between `start` and `end`. if they're in different
+    /// blocks, the blocks in between are removed and the two ends are joined.
+    /// `start` must come before `end`
+    pub(crate) fn delete_range(&mut self, start: &Pos, end: &Pos) -> Pos {
+        if start.hash == end.hash {
+            self.delete_text(&start.hash, start.offset, end.offset);
+            return start.clone();
+        }
+        let start_block = self.block_of(&start.hash).expect("text node not in a block");
+        let end_block = self.block_of(&end.hash).expect("text node not in a block");
+        // everything between the two text nodes, in order
+        let mut blocks = vec![start_block.clone()];
+        while blocks.last() != Some(&end_block) {
+            let next = self.next_leaf_block(blocks.last().unwrap()).expect("`end` is before `start`");
+            blocks.push(next);
+        }
+        let mut texts = self.texts(&start_block);
+        if end_block != start_block {
+            texts.extend(self.texts(&end_block));
+        }
+        let start_idx = texts.iter().position(|t| *t == start.hash).unwrap();
+        let end_idx = texts.iter().position(|t| *t == end.hash).unwrap();
+
+        let len = self.text(&start.hash).len();
+        self.delete_text(&start.hash, start.offset, len);
+        self.delete_text(&end.hash, 0, end.offset);
+        for text in &texts[start_idx + 1..end_idx] {
+            self.remove_pruning(text);
+        }
+        for block in blocks.iter().take(blocks.len() - 1).skip(1) {
+            self.remove_pruning(block);
+        }
+        if start_block != end_block {
+            self.merge_children(&start_block, &end_block);
+            self.remove_pruning(&end_block);
+        }
+        start.clone()
+    }
+    /// remove the node, and its parent if that leaves the parent empty, etc.
+    /// stops at leaf blocks so there's always somewhere for the caret to go
+    fn remove_pruning(&mut self, hash: &str) {
+        let parent = self.parent(hash).unwrap().to_string();
+        self.remove(hash);
+        if parent != ROOT_HASH && self.children(&parent).is_empty()
+            && !self.kind(&parent).is_leaf_block() {
+            self.remove_pruning(&parent);
+        }
+    }
+    /// Backspace key
+    pub fn backspace(&self, pos: &Pos) -> Transaction {
+        // delete the grapheme before the caret
+        if pos.offset > 0 {
+            let start = prev_grapheme_boundary(self.text(&pos.hash), pos.offset);
+            let start = Pos::new(&pos.hash, start);
+            return Op::DeleteRange { start, end: pos.clone() }.into();
+        }
+        // caret is at the start of a text node, but there's text before it
+        // in the same block (e.g. at the start of some bold text)
+        let mut prev = self.prev_text(&pos.hash);
+        while let Some(hash) = prev {
+            let len = self.text(&hash).len();
+            if len > 0 { return self.backspace(&Pos::new(&hash, len)) }
+            prev = self.prev_text(&hash);
+        }
+        // caret is at the start of the block
+        let block = self.block_of(&pos.hash).expect("text node not in a block");
+        if *self.kind(&block) != PageNodeType::TextBlock {
+            return Op::SetBlockKind { hash: block, kind: PageNodeType::TextBlock }.into();
+        }
+        let parent = self.parent(&block).unwrap().to_string();
+        if parent != ROOT_HASH {
+            if self.index_in_parent(&block) == Some(0) {
+                return Op::Unwrap { hash: parent }.into();
+            }
+            return Op::LiftBlock { hash: block }.into();
+        }
+        match self.prev_leaf_block(&block) {
+            // tables aren't editable as text yet, so don't merge into them
+            Some(prev) if *self.kind(&prev) == PageNodeType::Table => Transaction::new(),
+            // there's no text to merge into, so it just goes
+            Some(prev) if *self.kind(&prev) == PageNodeType::Divider => {
+                Op::RemoveNodes { hashes: vec![prev] }.into()
+            },
+            Some(_) => Op::MergeBlocks { hash: block }.into(),
+            None => Transaction::new(),
+        }
+    }
+    /// Delete key (forward delete). mirrors `backspace`: at the end of the
+    /// block the next block gets merged onto the end of this one
+    pub fn forward_delete(&self, pos: &Pos) -> Transaction {
+        // delete the grapheme after the caret
+        let text = self.text(&pos.hash);
+        if pos.offset < text.len() {
+            let end = Pos::new(&pos.hash, next_grapheme_boundary(text, pos.offset));
+            return Op::DeleteRange { start: pos.clone(), end }.into();
+        }
+        // caret is at the end of a text node, but there's text after it in
+        // the same block
+        let mut next = self.next_text(&pos.hash);
+        while let Some(hash) = next {
+            if !self.text(&hash).is_empty() { return self.forward_delete(&Pos::new(&hash, 0)) }
+            next = self.next_text(&hash);
+        }
+        // caret is at the end of the block
+        let block = self.block_of(&pos.hash).expect("text node not in a block");
+        match self.next_leaf_block(&block) {
+            // tables aren't editable as text yet, so don't merge them
+            Some(next) if *self.kind(&next) == PageNodeType::Table => Transaction::new(),
+            Some(next) if *self.kind(&next) == PageNodeType::Divider => {
+                Op::RemoveNodes { hashes: vec![next] }.into()
+            },
+            Some(next) => Op::MergeBlocks { hash: next }.into(),
+            None => Transaction::new(),
+        }
+    }
+    /// Alt/Ctrl + Backspace. deletes back to the start of the word before the
+    /// caret (incl. any spaces/punctuation between). at the start of the
+    /// block it's just a backspace
+    pub fn delete_word_backward(&self, pos: &Pos) -> Transaction {
+        let (block, text, offset) = self.block_text(pos);
+        if offset == 0 { return self.backspace(pos) }
+        let start = text.split_word_bound_indices()
+            .rfind(|(i, word)| *i < offset && is_word(word))
+            .map_or(0, |(i, _)| i);
+        let start = self.pos_in_block(&block, start).unwrap_or_else(|| pos.clone());
+        Op::DeleteRange { start, end: pos.clone() }.into()
+    }
+    /// Alt/Ctrl + Delete. deletes up to the end of the word after the caret.
+    /// at the end of the block it's just a forward delete
+    pub fn delete_word_forward(&self, pos: &Pos) -> Transaction {
+        let (block, text, offset) = self.block_text(pos);
+        if offset == text.len() { return self.forward_delete(pos) }
+        let end = text.split_word_bound_indices()
+            .find(|(i, word)| i + word.len() > offset && is_word(word))
+            .map_or(text.len(), |(i, word)| i + word.len());
+        let end = self.pos_in_block(&block, end).unwrap_or_else(|| pos.clone());
+        Op::DeleteRange { start: pos.clone(), end }.into()
+    }
+    /// Cmd + Backspace. deletes back to the start of the block
+    pub fn delete_to_block_start(&self, pos: &Pos) -> Transaction {
+        let (block, _, offset) = self.block_text(pos);
+        if offset == 0 { return self.backspace(pos) }
+        let start = self.pos_in_block(&block, 0).unwrap_or_else(|| pos.clone());
+        Op::DeleteRange { start, end: pos.clone() }.into()
+    }
+    /// Cmd + Delete. deletes up to the end of the block
+    pub fn delete_to_block_end(&self, pos: &Pos) -> Transaction {
+        let (block, text, offset) = self.block_text(pos);
+        if offset == text.len() { return self.forward_delete(pos) }
+        let end = self.pos_in_block(&block, text.len()).unwrap_or_else(|| pos.clone());
+        Op::DeleteRange { start: pos.clone(), end }.into()
+    }
+    /// the block `pos` is in, all the text in that block joined together (so
+    /// words can run across spans), and where `pos` is in that text
+    pub(crate) fn block_text(&self, pos: &Pos) -> (String, String, usize) {
+        let block = self.block_of(&pos.hash).expect("text node not in a block");
+        let mut text = String::new();
+        let mut offset = 0;
+        for hash in self.texts(&block) {
+            if hash == pos.hash { offset = text.len() + pos.offset }
+            text.push_str(self.text(&hash));
+        }
+        (block, text, offset)
+    }
+    /// offset into the joined text of the block -> position in one of its
+    /// text nodes. a block w/o text (e.g. a divider) gives the start of the
+    /// next block that has some, or the end of the one before. `None` if
+    /// there's no text anywhere (e.g. a file w/ just `---` in it)
+    pub(crate) fn pos_in_block(&self, block: &str, mut offset: usize) -> Option<Pos> {
+        let texts = self.texts(block);
+        if texts.is_empty() {
+            if let Some(next) = self.next_text_block(block) { return self.pos_in_block(&next, 0) }
+            return self.pos_in_block(&self.prev_text_block(block)?, usize::MAX);
+        }
+        for hash in &texts {
+            let len = self.text(hash).len();
+            if offset <= len { return Some(Pos::new(hash, offset)) }
+            offset -= len;
+        }
+        let last = texts.last()?;
+        Some(Pos::new(last, self.text(last).len()))
+    }
+    /// the edit that turns the text node's text into `new`, only replacing
+    /// the part that changed (e.g. to catch up w/ text the browser changed)
+    pub fn diff_text(&self, hash: &str, new: &str) -> Transaction {
+        let old = self.text(hash);
+        let prefix: usize = old.chars().zip(new.chars())
+            .take_while(|(a, b)| a == b).map(|(a, _)| a.len_utf8()).sum();
+        let suffix: usize = old[prefix..].chars().rev().zip(new[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b).map(|(a, _)| a.len_utf8()).sum();
+        let removed_end = old.len() - suffix;
+        let inserted = &new[prefix..new.len() - suffix];
+
+        let start = Pos::new(hash, prefix);
+        let mut tx = Transaction::new();
+        if removed_end > prefix {
+            tx.push(Op::DeleteRange { start: start.clone(), end: Pos::new(hash, removed_end) });
+        }
+        if !inserted.is_empty() {
+            tx.push(Op::InsertText { pos: start, text: inserted.to_string() });
+        }
+        tx
+    }
+    /// Space key just typed after a block prefix (e.g. "#", "- [ ]" or 
+    /// whatever the profile has) at the start of a text block. turns the block 
+    /// into that kind and removes the prefix
+    pub fn apply_block_prefix(&self, pos: &Pos, profile: &SyntaxProfile) -> Option<Transaction> {
+        if pos.offset == 0 { return None }
+        let block = self.parent(&pos.hash)?.to_string();
+        if *self.kind(&block) != PageNodeType::TextBlock
+            || self.children(&block).first() != Some(&pos.hash) {
+            return None
+        }
+        let line = format!("{} ", self.text(&pos.hash).get(..pos.offset)?);
+        let (kind, checked) = match profile.block_prefix(&line)? {
+            (PageNodeType::Dot, rest) => match check_prefix(rest) {
+                Some((checked, "")) => (PageNodeType::Check, Some(checked)),
+                _ if rest.is_empty() => (PageNodeType::Dot, None),
+                _ => return None,
+            },
+            (kind, "") => (kind, None),
+            _ => return None,
+        };
+        let mut tx = Transaction::from(Op::DeleteRange {
+            start: Pos::new(&pos.hash, 0), end: pos.clone(),
+        });
+        tx.push(match kind {
+            PageNodeType::Quote => Op::WrapIn { hashes: vec![block.clone()], kind },
+            _ => Op::SetBlockKind { hash: block.clone(), kind },
+        });
+        if let Some(checked) = checked {
+            tx.push(Op::SetContent { hash: block, key: "checked".into(), 
+                val: Some(checked.to_string()) });
+        }
+        Some(tx)
+    }
+    /// a char was just typed before `pos`. if it closed a span (e.g. the 
+    /// second `*` of `*word*`), turn the text into that span. the text after 
+    /// the caret stays in `pos.hash`, so the caret goes to the start of it
+    pub fn apply_span_syntax(&self, pos: &Pos, profile: &SyntaxProfile) -> Option<Transaction> {
+        if *self.kind(&pos.hash) != PageNodeType::RawText { return None }
+        let parent = self.parent(&pos.hash)?.to_string();
+        if *self.kind(&parent) == PageNodeType::CodeInline { return None }
+        let text = self.text(&pos.hash);
+        let before = text.get(..pos.offset)?;
+        let (start, span) = before.char_indices().find_map(|(i, c)| {
+            // the opening delim has to be the start of a run, otherwise 
+            // `**word*` would be read as italics on the way to being bold
+            if before[..i].ends_with(c) { return None }
+            let (span, len) = parse_span(before, i, profile)?;
+            // links are left alone (`[a](b)` is typed in bits), except
+            // `[[note]]`, which is done once the `]]` is
+            let is_profile_span = profile.spans.iter().any(|s| s.kind == span.kind)
+                || span.kind == PageNodeType::FileLink;
+            (is_profile_span && i + len == pos.offset).then_some((i, span))
+        })?;
+        let mut nodes = Vec::new();
+        if start > 0 {
+            nodes.push(NodeTree::text(&before[..start]));
+        }
+        nodes.push(span);
+        Some(Transaction::from(vec![
+            Op::SetText { hash: pos.hash.clone(), text: text[pos.offset..].to_string() },
+            Op::InsertNodes { parent, index: self.index_in_parent(&pos.hash)?, nodes },
+        ]))
+    }
+    /// the block w/ the caret goes from text to a heading, or from any
+    /// heading back to text
+    pub fn toggle_heading(&self, pos: &Pos) -> Option<Transaction> {
+        let block = self.block_of(&pos.hash)?;
+        let kind = match self.kind(&block) {
+            PageNodeType::TextBlock => PageNodeType::H1,
+            PageNodeType::H1 | PageNodeType::H2 | PageNodeType::H3
+                | PageNodeType::H4 | PageNodeType::H5 => PageNodeType::TextBlock,
+            _ => return None,
+        };
+        Some(Op::SetBlockKind { hash: block, kind }.into())
+    }
+    /// Tab/Shift-Tab w/ the caret in a code block types/removes a tab (at the
+    /// start of the caret's line) instead of indenting the block. `None` if
+    /// it's not in code. gives the edit (empty if there's no tab to remove)
+    /// and where the caret goes
+    pub fn code_tab(&self, start: &Pos, end: &Pos, outdent: bool) -> Option<(Transaction, Pos)> {
+        let block = self.block_of(&start.hash)?;
+        if *self.kind(&block) != PageNodeType::CodeBlock { return None }
+        if !outdent {
+            let mut tx = Transaction::new();
+            if start != end {
+                tx.push(Op::DeleteRange { start: start.clone(), end: end.clone() });
+            }
+            tx.push(Op::InsertText { pos: start.clone(), text: "\t".into() });
+            return Some((tx, Pos::new(&start.hash, start.offset + 1)));
+        }
+        let (_, text, offset) = self.block_text(start);
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        if !text[line_start..].starts_with('\t') { return Some((Transaction::new(), start.clone())) }
+        let tab = (self.pos_in_block(&block, line_start)?, self.pos_in_block(&block, line_start + 1)?);
+        let caret = self.pos_in_block(&block, if offset > line_start { offset - 1 } else { offset })?;
+        Some((Op::DeleteRange { start: tab.0, end: tab.1 }.into(), caret))
+    }
+    /// an empty 2x2 table below the block w/ the caret, so it's the block's
+    /// `next_leaf_block` once applied
+    pub fn insert_table(&self, pos: &Pos) -> Option<Transaction> {
+        let block = self.block_of(&pos.hash)?;
+        let parent = self.parent(&block)?.to_string();
+        let index = self.index_in_parent(&block)? + 1;
+        let table = NodeTree::block(PageNodeType::Table, &table_skeleton(""));
+        Some(Op::InsertNodes { parent, index, nodes: vec![table] }.into())
+    }
+    /// paste `md` over `start`..`end` as blocks. the block w/ the caret is
+    /// split and the pasted blocks go in between, w/ a plain first/last line
+    /// joining the text on either side like it was typed. code blocks and
+    /// tables keep their text raw, so it goes in as is. several applies, so
+    /// call it inside a journal to undo it all at once. returns where the
+    /// caret goes
+    pub fn paste_markdown(&mut self, start: &Pos, end: &Pos, md: &str, profile: &SyntaxProfile,
+    ) -> Result<Pos, OpError> {
+        let mut tx = Transaction::new();
+        if start != end {
+            tx.push(Op::DeleteRange { start: start.clone(), end: end.clone() });
+        }
+        let raw = self.block_of(&start.hash)
+            .is_some_and(|b| matches!(self.kind(&b), PageNodeType::CodeBlock | PageNodeType::Table));
+        let mut page = parse_markdown_with(md, profile);
+        if raw || page.children.is_empty() {
+            tx.push(Op::InsertText { pos: start.clone(), text: md.into() });
+            return Ok(self.apply(&tx)?.caret.unwrap_or_else(|| start.clone()));
+        }
+        // a copy, so it can't keep the `^block-id`s
+        clear_hashes(&mut page);
+        tx.push(Op::SplitBlock { pos: start.clone() });
+        let tail_start = self.apply(&tx)?.caret.unwrap_or_else(|| start.clone());
+        let head = self.block_of(&start.hash).expect("text node not in a block");
+        let tail = self.block_of(&tail_start.hash).expect("text node not in a block");
+        let parent = self.parent(&head).expect("block not in the doc").to_string();
+        let index = self.index_in_parent(&head).expect("block not in the doc") + 1;
+        let first_plain = page.children[0].kind == PageNodeType::TextBlock;
+        self.apply(&Op::InsertNodes { parent, index, nodes: page.children }.into())?;
+
+        let mut tx = Transaction::new();
+        let first = self.next_leaf_block(&head).expect("nothing was pasted");
+        let last = self.prev_leaf_block(&tail).expect("nothing was pasted");
+        let last_kind = self.kind(&last).clone();
+        if first_plain {
+            tx.push(Op::MergeBlocks { hash: first.clone() });
+        } else if self.plain_text(&head).is_empty() {
+            tx.push(Op::RemoveNodes { hashes: vec![head] });
+        }
+        let joins = self.first_text(&last).is_some()
+            && !matches!(last_kind, PageNodeType::CodeBlock | PageNodeType::Table);
+        if joins {
+            // the tail keeps its kind, same as if the lines were typed
+            if last_kind == PageNodeType::TextBlock && !(first_plain && first == last) {
+                tx.push(Op::SetBlockKind { hash: last.clone(), kind: self.kind(&tail).clone() });
+            }
+            tx.push(Op::MergeBlocks { hash: tail });
+        } else if self.plain_text(&tail).is_empty() && self.next_text_block(&tail).is_some() {
+            tx.push(Op::RemoveNodes { hashes: vec![tail] });
+        }
+        let applied = self.apply(&tx)?;
+        let caret = match joins {
+            true => applied.caret,
+            // e.g. it ended w/ a divider, so the caret goes after it
+            false => self.next_text_block(&last).and_then(|b| self.pos_in_block(&b, 0)),
+        };
+        Ok(caret.unwrap_or(tail_start))
+    }
+    /// swap everything in the page for `page`'s blocks, e.g. when opening a
+    /// file. the old blocks go first, so opening the same file again doesn't
+    /// clash w/ its own `^block-id`s. a page w/o any text (e.g. just `---`)
+    /// gets an empty block at the end for the caret
+    pub fn replace_page(&self, mut page: NodeTree) -> Transaction {
+        let old = self.children(ROOT_HASH).to_vec();
+        if !page.has_text() {
+            page.children.push(NodeTree::block(PageNodeType::TextBlock, ""));
+        }
+        Transaction::from(vec![
+            Op::RemoveNodes { hashes: old },
+            Op::InsertNodes { parent: ROOT_HASH.into(), index: 0, nodes: page.children },
+        ])
+    }
+    /// where a block dropped on `target` goes, as `(parent, index)`: before
+    /// or after it. dropping after the last block in a branch (e.g. a quote)
+    /// can go `outdent` levels out of it instead
+    pub fn drop_position(&self, target: &str, after: bool, outdent: usize) -> Option<(String, usize)> {
+        let mut hash = target;
+        if after {
+            for _ in 0..outdent {
+                let parent = self.parent(hash)?;
+                if parent == ROOT_HASH || self.next_sibling(hash).is_some() { break }
+                hash = parent;
+            }
+        }
+        let parent = self.parent(hash)?.to_string();
+        Some((parent, self.index_in_parent(hash)? + usize::from(after)))
+    }
+    /// move the block (w/ everything in it) to be child number `index` of
+    /// `parent`, counting the block if it's already there. branch blocks it
+    /// leaves empty go too. `None` if it wouldn't move, or can't go there
+    pub fn move_block(&self, hash: &str, parent: &str, index: usize) -> Option<Transaction> {
+        if hash == ROOT_HASH || hash == parent || self.is_ancestor(hash, parent) { return None }
+        let kind = self.kind(parent);
+        if !kind.is_block() || kind.is_leaf_block() { return None }
+        let old_parent = self.parent(hash)?;
+        let old_index = self.index_in_parent(hash)?;
+        let mut index = index.min(self.children(parent).len());
+        if old_parent == parent {
+            if index == old_index || index == old_index + 1 { return None }
+            if old_index < index { index -= 1 }
+        }
+        let mut tx = Transaction::from(Op::MoveNodes {
+            hashes: vec![hash.to_string()], parent: parent.to_string(), index,
+        });
+        // the branch blocks that only had this block in them
+        let mut emptied = None;
+        let mut branch = old_parent;
+        while branch != ROOT_HASH && self.children(branch).len() == 1
+            && !self.is_ancestor(branch, parent) && branch != parent {
+            emptied = Some(branch.to_string());
+            branch = self.parent(branch)?;
+        }
+        if let Some(emptied) = emptied {
+            tx.push(Op::RemoveNodes { hashes: vec![emptied] });
+        }
+        Some(tx)
+    }
+    /// the blocks between `start` and `end`, as siblings (see `block_range`)
+    pub fn selected_blocks(&self, start: &Pos, end: &Pos) -> Vec<String> {
+        match (self.block_of(&start.hash), self.block_of(&end.hash)) {
+            (Some(first), Some(last)) => self.block_range(&first, &last),
+            _ => Vec::new(),
+        }
+    }
+    /// the blocks from `a` to `b` (either way round), as siblings. if they're
+    /// in different branches (e.g. one in a quote), it's the ancestors of the
+    /// two that are siblings, and everything between them
+    pub fn block_range(&self, a: &str, b: &str) -> Vec<String> {
+        if a == b { return vec![a.to_string()] }
+        let (first, last) = match self.cmp_pos(&Pos::new(a, 0), &Pos::new(b, 0)).is_gt() {
+            true => (b, a),
+            false => (a, b),
+        };
+        // `first` and its ancestors, up to the one that has `last` in it too
+        let mut first_side = first;
+        while let Some(parent) = self.parent(first_side) {
+            if parent == ROOT_HASH || self.is_ancestor(parent, last) { break }
+            first_side = parent;
+        }
+        let Some(common) = self.parent(first_side) else { return Vec::new() };
+        let mut last_side = last;
+        while let Some(parent) = self.parent(last_side) {
+            if parent == common { break }
+            last_side = parent;
+        }
+        let (Some(from), Some(to)) = (self.index_in_parent(first_side), self.index_in_parent(last_side)) else {
+            return Vec::new()
+        };
+        self.children(common)[from..=to.max(from)].to_vec()
+    }
+    /// Alt + Up/Down. moves sibling blocks past the block above/below. a
+    /// branch block (e.g. a quote) there gets moved into, and at the edge of
+    /// a branch they move out of it
+    pub fn move_blocks(&self, hashes: &[String], up: bool) -> Option<Transaction> {
+        let (first, last) = (hashes.first()?, hashes.last()?);
+        let parent = self.parent(first)?;
+        let siblings = self.children(parent);
+        let (from, to) = (self.index_in_parent(first)?, self.index_in_parent(last)?);
+        let neighbour = match up {
+            true => from.checked_sub(1).map(|i| &siblings[i]),
+            false => siblings.get(to + 1),
+        };
+        let hashes = hashes.to_vec();
+        let op = match neighbour {
+            Some(next) if self.kind(next).is_block() && !self.kind(next).is_leaf_block() => {
+                let index = if up { usize::MAX } else { 0 };
+                Op::MoveNodes { hashes, parent: next.clone(), index }
+            },
+            // (indexes don't count the moved blocks)
+            Some(_) => {
+                let index = if up { from - 1 } else { from + 1 };
+                Op::MoveNodes { hashes, parent: parent.to_string(), index }
+            },
+            None if parent == ROOT_HASH => return None,
+            None => {
+                let grandparent = self.parent(parent)?.to_string();
+                let index = self.index_in_parent(parent)? + usize::from(!up);
+                let mut tx = Transaction::from(Op::MoveNodes { hashes, parent: grandparent, index });
+                if siblings.len() == to - from + 1 {
+                    tx.push(Op::RemoveNodes { hashes: vec![parent.to_string()] });
+                }
+                return Some(tx);
+            },
+        };
+        Some(op.into())
+    }
+    /// copies of the blocks (w/ new hashes) below them
+    pub fn duplicate_blocks(&self, hashes: &[String]) -> Option<Transaction> {
+        let last = hashes.last()?;
+        let parent = self.parent(last)?.to_string();
+        let index = self.index_in_parent(last)? + 1;
+        let nodes = hashes.iter().map(|h| {
+            let mut tree = self.tree(h);
+            clear_hashes(&mut tree);
+            tree
+        }).collect();
+        Some(Op::InsertNodes { parent, index, nodes }.into())
+    }
+    /// remove the blocks, and any branch blocks that leaves empty. returns
+    /// the edit and the block the caret goes to after (`None` if that's the
+    /// empty block put in bc no block w/ text was left)
+    pub fn delete_blocks(&self, hashes: &[String]) -> (Transaction, Option<String>) {
+        let mut removed = hashes.to_vec();
+        // the branch blocks that'd be left empty
+        while let Some(parent) = removed.first().and_then(|h| self.parent(h)) {
+            if parent == ROOT_HASH || !self.children(parent).iter().all(|c| removed.contains(c)) { break }
+            removed = vec![parent.to_string()];
+        }
+        let (Some(first), Some(last)) = (removed.first(), removed.last()) else {
+            return (Transaction::new(), None)
+        };
+        // one w/ text, for the caret
+        let after = self.last_leaf_block(last).and_then(|b| self.next_text_block(&b))
+            .or_else(|| self.first_leaf_block(first).and_then(|b| self.prev_text_block(&b)));
+        let mut tx = Transaction::new();
+        // there has to be a block left for the caret
+        if after.is_none() {
+            tx.push(Op::InsertNodes { parent: ROOT_HASH.into(), index: 0,
+                nodes: vec![NodeTree::block(PageNodeType::TextBlock, "")] });
+        }
+        tx.push(Op::RemoveNodes { hashes: removed });
+        (tx, after)
+    }
+}
+
+/// so the doc gives the nodes new ones when they're inserted
+fn clear_hashes(tree: &mut NodeTree) {
+    tree.hash.clear();
+    for child in tree.children.iter_mut() {
+        clear_hashes(child);
+    }
+}
+
+/// a 2x2 table's markdown w/ `first_cell` in the top left, e.g. for a new
+/// table. tables are kept as their raw text (see `markdown.rs`)
+pub(crate) fn table_skeleton(first_cell: &str) -> String {
+    format!("| {} |  |\n| - | - |\n|  |  |", first_cell.replace('|', "\\|"))
+}
+
+/// whether a piece of text split on word boundaries is a word, rather than
+/// spaces or punctuation
+pub(crate) fn is_word(text: &str) -> bool {
+    text.chars().any(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockSyntax, SpanSyntax, parse_markdown};
+    use PageNodeType::*;
+
+    fn doc_from(blocks: Vec<NodeTree>) -> Doc {
+        Doc::from_tree(NodeTree::new(Page, blocks), 1)
+    }
+    fn block_texts(doc: &Doc) -> Vec<String> {
+        doc.leaf_blocks().iter().map(|b| doc.plain_text(b)).collect()
+    }
+
+    #[test]
+    fn split_plain_block() {
+        let mut doc = doc_from(vec![NodeTree::block(H1, "hello world")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let caret = doc.split_block(&Pos::new(&text, 5));
+        assert_eq!(block_texts(&doc), vec!["hello", " world"]);
+        assert_eq!(*doc.kind(&doc.leaf_blocks()[1]), H1);
+        assert_eq!(doc.block_of(&caret.hash), Some(doc.leaf_blocks()[1].clone()));
+        assert_eq!(caret.offset, 0);
+    }
+
+    #[test]
+    fn split_inside_span_keeps_span() {
+        let mut doc = doc_from(vec![NodeTree::new(TextBlock, vec![
+            NodeTree::text("a "),
+            NodeTree::new(Bold, vec![NodeTree::text("bold")]),
+            NodeTree::text(" c"),
+        ])]);
+        let block = doc.leaf_blocks()[0].clone();
+        let bold_text = doc.texts(&block)[1].clone();
+        let caret = doc.split_block(&Pos::new(&bold_text, 2));
+        assert_eq!(block_texts(&doc), vec!["a bo", "ld c"]);
+        let new_block = doc.leaf_blocks()[1].clone();
+        assert_eq!(doc.children(&new_block).len(), 2);
+        assert_eq!(*doc.kind(doc.parent(&caret.hash).unwrap()), Bold);
+    }
+
+    #[test]
+    fn merge_joins_matching_spans() {
+        let mut doc = doc_from(vec![
+            NodeTree::new(TextBlock, vec![NodeTree::new(Bold, vec![NodeTree::text("ab")])]),
+            NodeTree::new(TextBlock, vec![NodeTree::new(Bold, vec![NodeTree::text("cd")])]),
+        ]);
+        let second = doc.leaf_blocks()[1].clone();
+        let caret = doc.merge_into_prev(&second).unwrap();
+        assert_eq!(block_texts(&doc), vec!["abcd"]);
+        assert_eq!(caret.offset, 2);
+        let block = doc.leaf_blocks()[0].clone();
+        assert_eq!(doc.children(&block).len(), 1);
+    }
+
+    /// apply the backspace for `pos`, returning the new caret
+    fn backspace(doc: &mut Doc, pos: &Pos) -> Pos {
+        let tx = doc.backspace(pos);
+        doc.apply(&tx).unwrap().caret.unwrap_or_else(|| pos.clone())
+    }
+
+    #[test]
+    fn split_then_backspace_round_trips() {
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "one two")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let caret = doc.split_block(&Pos::new(&text, 3));
+        let caret = backspace(&mut doc, &caret);
+        assert_eq!(block_texts(&doc), vec!["one two"]);
+        assert_eq!(caret, Pos::new(&text, 3));
+    }
+
+    #[test]
+    fn backspace_steps() {
+        let mut doc = doc_from(vec![
+            NodeTree::block(TextBlock, "top"),
+            NodeTree::new(Quote, vec![
+                NodeTree::block(TextBlock, "a"),
+                NodeTree::block(H2, "b"),
+                NodeTree::block(TextBlock, "c"),
+            ]),
+        ]);
+        let b = doc.leaf_blocks()[2].clone();
+        let b_text = doc.first_text(&b).unwrap();
+        // heading -> text block
+        backspace(&mut doc, &Pos::new(&b_text, 0));
+        assert_eq!(*doc.kind(&b), TextBlock);
+        // lifted out of the quote, splitting it in two
+        backspace(&mut doc, &Pos::new(&b_text, 0));
+        assert_eq!(doc.parent(&b), Some(ROOT_HASH));
+        let kinds: Vec<_> = doc.children(ROOT_HASH).iter().map(|c| doc.kind(c).clone()).collect();
+        assert_eq!(kinds, vec![TextBlock, Quote, TextBlock, Quote]);
+        // merged into the block above
+        let caret = backspace(&mut doc, &Pos::new(&b_text, 0));
+        assert_eq!(block_texts(&doc), vec!["top", "ab", "c"]);
+        assert_eq!(caret.offset, 1);
+    }
+
+    #[test]
+    fn backspace_unwraps_first_child() {
+        let mut doc = doc_from(vec![NodeTree::new(Quote, vec![
+            NodeTree::block(TextBlock, "a"),
+            NodeTree::block(TextBlock, "b"),
+        ])]);
+        let a_text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        backspace(&mut doc, &Pos::new(&a_text, 0));
+        let kinds: Vec<_> = doc.children(ROOT_HASH).iter().map(|c| doc.kind(c).clone()).collect();
+        assert_eq!(kinds, vec![TextBlock, TextBlock]);
+    }
+
+    #[test]
+    fn backspace_multibyte_char() {
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "añ")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let caret = backspace(&mut doc, &Pos::new(&text, 3));
+        assert_eq!(doc.text(&text), "a");
+        assert_eq!(caret.offset, 1);
+    }
+
+    #[test]
+    fn backspace_whole_grapheme() {
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "ae\u{301}👍🏽")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let end = doc.text(&text).len();
+        let caret = backspace(&mut doc, &Pos::new(&text, end));
+        assert_eq!(doc.text(&text), "ae\u{301}");
+        let caret = backspace(&mut doc, &caret);
+        assert_eq!(doc.text(&text), "a");
+        assert_eq!(caret.offset, 1);
+    }
+
+    #[test]
+    fn block_prefixes() {
+        let mut doc = doc_from(vec![
+            NodeTree::block(TextBlock, "#"),
+            NodeTree::block(TextBlock, ">"),
+            NodeTree::block(TextBlock, "x"),
+        ]);
+        let blocks = doc.leaf_blocks();
+        let texts: Vec<_> = blocks.iter().map(|b| doc.first_text(b).unwrap()).collect();
+        let profile = SyntaxProfile::standard();
+        let tx = doc.apply_block_prefix(&Pos::new(&texts[0], 1), &profile).unwrap();
+        assert_eq!(doc.apply(&tx).unwrap().caret, Some(Pos::new(&texts[0], 0)));
+        assert_eq!(*doc.kind(&blocks[0]), H1);
+        assert_eq!(doc.text(&texts[0]), "");
+        let tx = doc.apply_block_prefix(&Pos::new(&texts[1], 1), &profile).unwrap();
+        doc.apply(&tx).unwrap();
+        assert_eq!(*doc.kind(doc.parent(&blocks[1]).unwrap()), Quote);
+        assert!(doc.apply_block_prefix(&Pos::new(&texts[2], 1), &profile).is_none());
+    }
+
+    #[test]
+    fn block_prefixes_from_profile() {
+        let mut doc = doc_from(vec![
+            NodeTree::block(TextBlock, "##"),
+            NodeTree::block(TextBlock, "- [x]"),
+            NodeTree::block(TextBlock, "+"),
+        ]);
+        let blocks = doc.leaf_blocks();
+        let texts: Vec<_> = blocks.iter().map(|b| doc.first_text(b).unwrap()).collect();
+        let profile = SyntaxProfile::with_user(&SyntaxProfile {
+            blocks: vec![BlockSyntax { prefix: "+".into(), kind: Check }],
+            ..Default::default()
+        });
+        for (i, text) in texts.iter().enumerate() {
+            let end = doc.text(text).len();
+            let tx = doc.apply_block_prefix(&Pos::new(text, end), &profile).unwrap();
+            doc.apply(&tx).unwrap();
+            assert_eq!(doc.text(&texts[i]), "");
+        }
+        assert_eq!(blocks.iter().map(|b| doc.kind(b).clone()).collect::<Vec<_>>(), 
+            vec![H2, Check, Check]);
+        assert_eq!(doc.content(&blocks[1], "checked"), Some("true"));
+    }
+
+    #[test]
+    fn span_syntax_while_typing() {
+        let profile = SyntaxProfile::with_user(&SyntaxProfile {
+            spans: vec![SpanSyntax { delim: "/".into(), kind: Italic }],
+            ..Default::default()
+        });
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "a /b/ c")]);
+        let block = doc.leaf_blocks()[0].clone();
+        let text = doc.first_text(&block).unwrap();
+        assert!(doc.apply_span_syntax(&Pos::new(&text, 4), &profile).is_none());
+        let tx = doc.apply_span_syntax(&Pos::new(&text, 5), &profile).unwrap();
+        doc.apply(&tx).unwrap();
+        let kinds: Vec<_> = doc.children(&block).iter().map(|c| doc.kind(c).clone()).collect();
+        assert_eq!(kinds, vec![RawText, Italic, RawText]);
+        assert_eq!(doc.children(&block)[2], text);
+        assert_eq!(doc.text(&text), " c");
+        assert_eq!(doc.to_markdown(), "a *b* c\n");
+
+        // `**b*` is on the way to being bold, not italic
+        let doc = doc_from(vec![NodeTree::block(TextBlock, "**b*")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        assert!(doc.apply_span_syntax(&Pos::new(&text, 4), &profile).is_none());
+        // and paths aren't italic
+        let doc = doc_from(vec![NodeTree::block(TextBlock, "a/b/")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        assert!(doc.apply_span_syntax(&Pos::new(&text, 4), &profile).is_none());
+        // a wikilink once its `]]` is typed
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "see [[Note]]")]);
+        let block = doc.leaf_blocks()[0].clone();
+        let text = doc.first_text(&block).unwrap();
+        assert!(doc.apply_span_syntax(&Pos::new(&text, 11), &profile).is_none());
+        doc.apply(&doc.apply_span_syntax(&Pos::new(&text, 12), &profile).unwrap()).unwrap();
+        assert_eq!(doc.to_markdown(), "see [[Note]]\n");
+        assert_eq!(*doc.kind(&doc.children(&block)[1]), FileLink);
+    }
+
+    #[test]
+    fn delete_range_across_blocks() {
+        let mut doc = doc_from(vec![
+            NodeTree::new(TextBlock, vec![
+                NodeTree::text("one "),
+                NodeTree::new(Bold, vec![NodeTree::text("two")]),
+            ]),
+            NodeTree::new(Quote, vec![NodeTree::block(TextBlock, "three")]),
+            NodeTree::block(TextBlock, "four five"),
+        ]);
+        let blocks = doc.leaf_blocks();
+        let start = Pos::new(&doc.first_text(&blocks[0]).unwrap(), 2);
+        let end = Pos::new(&doc.first_text(&blocks[2]).unwrap(), 5);
+        let caret = doc.delete_range(&start, &end);
+        assert_eq!(block_texts(&doc), vec!["onfive"]);
+        assert_eq!(caret, start);
+        // the bold text and the emptied quote are gone too
+        assert_eq!(doc.children(&blocks[0]).len(), 1);
+        assert_eq!(doc.children(ROOT_HASH).len(), 1);
+    }
+
+    #[test]
+    fn forward_delete_steps() {
+        let mut doc = doc_from(vec![
+            NodeTree::new(TextBlock, vec![
+                NodeTree::text("a"),
+                NodeTree::new(Bold, vec![NodeTree::text("👍🏽")]),
+            ]),
+            NodeTree::new(Quote, vec![NodeTree::block(H2, "b")]),
+        ]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let pos = Pos::new(&text, 1);
+        // skips to the bold text, and deletes the whole emoji
+        doc.apply(&doc.forward_delete(&pos)).unwrap();
+        assert_eq!(block_texts(&doc), vec!["a", "b"]);
+        // merges the next block in, and the emptied quote goes too
+        doc.apply(&doc.forward_delete(&pos)).unwrap();
+        assert_eq!(block_texts(&doc), vec!["ab"]);
+        assert_eq!(doc.children(ROOT_HASH).len(), 1);
+        assert_eq!(*doc.kind(&doc.leaf_blocks()[0]), TextBlock);
+        // nothing after the last block
+        let last = doc.last_text(&doc.leaf_blocks()[0]).unwrap();
+        let end = Pos::new(&last, doc.text(&last).len());
+        assert!(doc.forward_delete(&end).is_empty());
+    }
+
+    #[test]
+    fn delete_words() {
+        let mut doc = doc_from(vec![
+            NodeTree::block(TextBlock, "x"),
+            NodeTree::new(TextBlock, vec![
+                NodeTree::text("one, tw"),
+                NodeTree::new(Italic, vec![NodeTree::text("o three")]),
+            ]),
+        ]);
+        let blocks = doc.leaf_blocks();
+        let texts = doc.texts(&blocks[1]);
+        // the word runs across the italic span
+        let tx = doc.delete_word_backward(&Pos::new(&texts[1], 1));
+        doc.apply(&tx).unwrap();
+        assert_eq!(doc.plain_text(&blocks[1]), "one,  three");
+        let tx = doc.delete_word_backward(&Pos::new(&texts[0], 5));
+        doc.apply(&tx).unwrap();
+        assert_eq!(doc.plain_text(&blocks[1]), " three");
+        let tx = doc.delete_word_forward(&Pos::new(&texts[0], 0));
+        doc.apply(&tx).unwrap();
+        assert_eq!(doc.plain_text(&blocks[1]), "");
+        // at the start of the block it's a backspace
+        let first = doc.first_text(&blocks[1]).unwrap();
+        doc.apply(&doc.delete_word_backward(&Pos::new(&first, 0))).unwrap();
+        assert_eq!(block_texts(&doc), vec!["x"]);
+    }
+
+    #[test]
+    fn delete_to_block_edges() {
+        let mut doc = doc_from(vec![
+            NodeTree::block(TextBlock, "one"),
+            NodeTree::block(TextBlock, "two three"),
+        ]);
+        let text = doc.first_text(&doc.leaf_blocks()[1]).unwrap();
+        doc.apply(&doc.delete_to_block_end(&Pos::new(&text, 3))).unwrap();
+        assert_eq!(block_texts(&doc), vec!["one", "two"]);
+        doc.apply(&doc.delete_to_block_start(&Pos::new(&text, 2))).unwrap();
+        assert_eq!(block_texts(&doc), vec!["one", "o"]);
+        doc.apply(&doc.delete_to_block_start(&Pos::new(&text, 0))).unwrap();
+        assert_eq!(block_texts(&doc), vec!["oneo"]);
+    }
+
+    #[test]
+    fn diff_only_touches_changes() {
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "hello world")]);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let tx = doc.diff_text(&text, "hello brave world");
+        assert_eq!(tx, Op::InsertText { pos: Pos::new(&text, 6), text: "brave ".into() }.into());
+        doc.apply(&tx).unwrap();
+        let tx = doc.diff_text(&text, "hello bravé world");
+        assert_eq!(tx.ops.len(), 2);
+        doc.apply(&tx).unwrap();
+        assert_eq!(doc.text(&text), "hello bravé world");
+        assert!(doc.diff_text(&text, "hello bravé world").is_empty());
+    }
+
+    #[test]
+    fn toggles_heading_and_inserts_table() {
+        let mut doc = doc_from(vec![NodeTree::block(TextBlock, "title")]);
+        let block = doc.leaf_blocks()[0].clone();
+        let caret = Pos::new(&doc.first_text(&block).unwrap(), 2);
+        doc.apply(&doc.toggle_heading(&caret).unwrap()).unwrap();
+        assert_eq!(*doc.kind(&block), H1);
+        doc.apply(&doc.insert_table(&caret).unwrap()).unwrap();
+        let table = doc.next_leaf_block(&block).unwrap();
+        assert_eq!(*doc.kind(&table), Table);
+        assert_eq!(doc.to_markdown(), "# title\n|  |  |\n| - | - |\n|  |  |\n");
+        doc.apply(&doc.toggle_heading(&caret).unwrap()).unwrap();
+        assert_eq!(*doc.kind(&block), TextBlock);
+        let cell = Pos::new(&doc.first_text(&table).unwrap(), 0);
+        assert!(doc.toggle_heading(&cell).is_none());
+
+        let opened = Doc::from_markdown("# other\nfile\n", 2).root_tree();
+        doc.apply(&doc.replace_page(opened)).unwrap();
+        assert_eq!(doc.to_markdown(), "# other\nfile\n");
+        // a file w/ no text still leaves somewhere for the caret
+        doc.apply(&doc.replace_page(parse_markdown("---\n"))).unwrap();
+        assert_eq!(doc.text_blocks().len(), 1);
+        assert!(doc.select_all().is_some());
+    }
+
+    #[test]
+    fn tab_in_code() {
+        let mut doc = Doc::from_markdown("```\na\n\tb\n```\n\ntext\n", 1);
+        let blocks = doc.leaf_blocks();
+        let code = doc.first_text(&blocks[0]).unwrap();
+        let text = Pos::new(&doc.first_text(&blocks[1]).unwrap(), 0);
+        assert!(doc.code_tab(&text, &text, false).is_none());
+        let caret = Pos::new(&code, 1);
+        let (tx, caret) = doc.code_tab(&caret, &caret, false).unwrap();
+        doc.apply(&tx).unwrap();
+        assert_eq!((doc.plain_text(&blocks[0]), caret.offset), ("a\t\n\tb".to_string(), 2));
+        // only a tab at the start of the line comes out
+        let (tx, _) = doc.code_tab(&caret, &caret, true).unwrap();
+        assert!(tx.is_empty());
+        let caret = Pos::new(&code, 5);
+        let (tx, caret) = doc.code_tab(&caret, &caret, true).unwrap();
+        doc.apply(&tx).unwrap();
+        assert_eq!((doc.plain_text(&blocks[0]), caret.offset), ("a\t\nb".to_string(), 4));
+    }
+
+    #[test]
+    fn pastes_lines_as_blocks() {
+        let profile = SyntaxProfile::standard();
+        let paste = |md: &str, offset: usize, pasted: &str| {
+            let mut doc = Doc::from_markdown(md, 1);
+            let pos = Pos::new(&doc.first_text(&doc.leaf_blocks()[0]).unwrap(), offset);
+            doc.start_journal();
+            let caret = doc.paste_markdown(&pos, &pos, pasted, &profile).unwrap();
+            let inverse = doc.end_journal();
+            let out = doc.to_markdown();
+            let (block, text, at) = doc.block_text(&caret);
+            let kind = doc.kind(&block).clone();
+            doc.apply(&inverse).unwrap();
+            assert_eq!(doc.to_markdown(), Doc::from_markdown(md, 1).to_markdown());
+            (out, kind, text[..at].to_string())
+        };
+        // the first and last lines join the text around them
+        let (out, kind, before) = paste("one two\n", 3, "a\n- b\nc");
+        assert_eq!(out, "onea\n- b\nc two\n");
+        assert_eq!((kind, before.as_str()), (TextBlock, "c"));
+        // and the rest of the heading is still a heading
+        let (out, _, before) = paste("# title\n", 0, "a\nb\n");
+        assert_eq!(out, "# a\n# btitle\n");
+        assert_eq!(before, "b");
+        // nothing to join onto after a divider
+        let (out, _, before) = paste("ab\n\nc\n", 2, "x\n---\n");
+        assert_eq!(out, Doc::from_markdown("abx\n---\nc\n", 1).to_markdown());
+        assert_eq!(before, "");
+        // code keeps the lines
+        let (out, kind, _) = paste("```\nab\n```\n", 1, "x\ny");
+        assert_eq!(out, "```\nax\nyb\n```\n");
+        assert_eq!(kind, CodeBlock);
+    }
+
+    #[test]
+    fn moves_blocks() {
+        let mut doc = Doc::from_markdown("a\n> b\n> > c\n\nd\n", 1);
+        let blocks = doc.leaf_blocks();
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| blocks[i].clone());
+        let quote = doc.parent(&b).unwrap().to_string();
+        // into the quote, above `c`'s quote
+        let (parent, index) = doc.drop_position(&b, true, 0).unwrap();
+        doc.apply(&doc.move_block(&d, &parent, index).unwrap()).unwrap();
+        assert_eq!(doc.parent(&d), Some(quote.as_str()));
+        assert_eq!(doc.index_in_parent(&d), Some(1));
+        // out of both quotes, to after them. `c`'s quote is left empty so it goes
+        let (parent, index) = doc.drop_position(&c, true, 2).unwrap();
+        assert_eq!((parent.as_str(), index), (ROOT_HASH, 2));
+        doc.apply(&doc.move_block(&c, &parent, index).unwrap()).unwrap();
+        assert_eq!(block_texts(&doc), vec!["a", "b", "d", "c"]);
+        assert_eq!(doc.children(&quote).len(), 2);
+        assert_eq!(doc.parent(&c), Some(ROOT_HASH));
+        // to the top, w/ the quote (and everything in it)
+        let (parent, index) = doc.drop_position(&a, false, 0).unwrap();
+        doc.apply(&doc.move_block(&quote, &parent, index).unwrap()).unwrap();
+        assert_eq!(block_texts(&doc), vec!["b", "d", "a", "c"]);
+        // next to itself, or inside itself
+        assert!(doc.move_block(&a, ROOT_HASH, 1).is_none());
+        assert!(doc.move_block(&a, ROOT_HASH, 2).is_none());
+        assert!(doc.move_block(&quote, &quote, 0).is_none());
+        assert!(doc.move_block(&a, &b, 0).is_none());
+    }
+
+    #[test]
+    fn block_commands() {
+        use std::slice;
+        let mut doc = Doc::from_markdown("a\n> b\n> c\n\nd\n", 1);
+        let blocks = doc.leaf_blocks();
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| blocks[i].clone());
+        let quote = doc.parent(&b).unwrap().to_string();
+        let start = |h: &str| Pos::new(&doc.first_text(h).unwrap(), 0);
+        // `a` to `c` are the `a` block and the quote
+        assert_eq!(doc.selected_blocks(&start(&a), &start(&c)), vec![a.clone(), quote.clone()]);
+        assert_eq!(doc.selected_blocks(&start(&b), &start(&c)), vec![b.clone(), c.clone()]);
+
+        // `a` goes into the quote, past `b`, then out the bottom
+        doc.apply(&doc.move_blocks(slice::from_ref(&a), false).unwrap()).unwrap();
+        assert_eq!(doc.parent(&a), Some(quote.as_str()));
+        doc.apply(&doc.move_blocks(slice::from_ref(&a), false).unwrap()).unwrap();
+        doc.apply(&doc.move_blocks(slice::from_ref(&a), false).unwrap()).unwrap();
+        doc.apply(&doc.move_blocks(slice::from_ref(&a), false).unwrap()).unwrap();
+        assert_eq!(block_texts(&doc), vec!["b", "c", "a", "d"]);
+        assert_eq!(doc.parent(&a), Some(ROOT_HASH));
+        doc.apply(&doc.move_blocks(&[a.clone(), d.clone()], true).unwrap()).unwrap();
+        assert_eq!(block_texts(&doc), vec!["b", "c", "a", "d"]);
+        assert_eq!(doc.parent(&d), Some(quote.as_str()));
+        // moving everything out of the quote removes it
+        doc.apply(&doc.move_blocks(&[b.clone(), c.clone(), a.clone(), d.clone()], true).unwrap()).unwrap();
+        assert!(!doc.contains(&quote));
+        assert!(doc.move_blocks(slice::from_ref(&b), true).is_none());
+
+        doc.apply(&doc.duplicate_blocks(&[b.clone(), c.clone()]).unwrap()).unwrap();
+        assert_eq!(block_texts(&doc), vec!["b", "c", "b", "c", "a", "d"]);
+        let copy = doc.leaf_blocks()[2].clone();
+        assert!(copy != b && copy != c);
+
+        let (tx, caret) = doc.delete_blocks(&[b.clone(), c.clone()]);
+        doc.apply(&tx).unwrap();
+        assert_eq!(caret, Some(copy));
+        let all = doc.children(ROOT_HASH).to_vec();
+        let (tx, caret) = doc.delete_blocks(&all);
+        doc.apply(&tx).unwrap();
+        assert_eq!(caret, None);
+        assert_eq!(block_texts(&doc), vec![""]);
+    }
+
+    #[test]
+    fn dividers_are_skipped() {
+        use std::slice;
+        let doc = Doc::from_markdown("---\n\na\n\n---\n\nb\n\n---\n", 1);
+        let blocks = doc.leaf_blocks();
+        let [first, a, middle, b, last] = [0, 1, 2, 3, 4].map(|i| blocks[i].clone());
+        assert_eq!(doc.text_blocks(), vec![a.clone(), b.clone()]);
+        let (a_text, b_text) = (doc.first_text(&a).unwrap(), doc.first_text(&b).unwrap());
+        let all = doc.select_all().unwrap();
+        assert_eq!((all.anchor, all.head), (Pos::new(&a_text, 0), Pos::new(&b_text, 1)));
+        // the block w/ text after it, or the end of the one before
+        assert_eq!(doc.pos_in_block(&first, 3), Some(Pos::new(&a_text, 0)));
+        assert_eq!(doc.pos_in_block(&middle, 0), Some(Pos::new(&b_text, 0)));
+        assert_eq!(doc.pos_in_block(&last, 0), Some(Pos::new(&b_text, 1)));
+
+        // the caret goes past the divider next to the deleted block
+        assert_eq!(doc.delete_blocks(slice::from_ref(&a)).1, Some(b.clone()));
+        assert_eq!(doc.delete_blocks(slice::from_ref(&b)).1, Some(a.clone()));
+        // only dividers left, so there's a new block for the caret
+        let mut doc = doc;
+        let (tx, caret) = doc.delete_blocks(&[a, middle, b]);
+        doc.apply(&tx).unwrap();
+        assert_eq!(caret, None);
+        let text_blocks = doc.text_blocks();
+        assert_eq!((text_blocks.len(), doc.plain_text(&text_blocks[0])), (1, String::new()));
+
+        // a file w/ just a divider has nowhere for the caret
+        let doc = Doc::from_markdown("---\n", 1);
+        assert_eq!(doc.pos_in_block(&doc.leaf_blocks()[0], 0), None);
+        assert_eq!(doc.select_all(), None);
+    }
+}
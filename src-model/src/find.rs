@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+use regex::{Regex, RegexBuilder};
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, Op, Pos, Transaction};
+
+// find & replace over the whole doc, not just the blocks that are rendered.
+// each leaf block's text is searched as one string, so a match can go across
+// e.g. bold text, but not from one block into the next
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FindOptions {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// the query is a regex, and the replacement can use its groups (`$1`)
+    pub regex: bool,
+}
+impl FindOptions {
+    /// the query as a regex. `Err` says what's wrong w/ it if it isn't one
+    pub fn compile(&self, query: &str) -> Result<Regex, String> {
+        let mut pattern = match self.regex {
+            true => query.to_string(),
+            false => regex::escape(query),
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FindMatch {
+    pub block: String,
+    /// byte offsets into the joined text of the block
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Doc {
+    /// every match in the doc, in order. empty matches (e.g. from `a*`) are
+    /// skipped, bc there'd be nothing to show or replace
+    pub fn find_all(&self, finder: &Regex) -> Vec<FindMatch> {
+        let mut matches = Vec::new();
+        for block in self.leaf_blocks() {
+            let text = self.plain_text(&block);
+            for m in finder.find_iter(&text) {
+                if m.start() == m.end() { continue }
+                matches.push(FindMatch { block: block.clone(), start: m.start(), end: m.end() });
+            }
+        }
+        matches
+    }
+    /// the match as positions in the text nodes. `None` if the doc has no
+    /// text left for it
+    pub fn match_range(&self, m: &FindMatch) -> Option<(Pos, Pos)> {
+        Some((self.pos_in_block(&m.block, m.start)?, self.pos_in_block(&m.block, m.end)?))
+    }
+    /// the first match that starts at or after `pos`, going back around to
+    /// the first one if there's none
+    pub fn match_from(&self, matches: &[FindMatch], pos: &Pos) -> Option<usize> {
+        if matches.is_empty() { return None }
+        let idx = matches.iter().position(|m| {
+            self.match_range(m).is_some_and(|(start, _)| self.cmp_pos(&start, pos) != Ordering::Less)
+        });
+        Some(idx.unwrap_or(0))
+    }
+    /// one transaction replacing all the `matches` (which must be in doc
+    /// order, like `find_all` gives them), so it's all undone at once. w/ the
+    /// `regex` option `$1` etc in the replacement are the match's groups
+    pub fn replace_matches(&self, finder: &Regex, options: &FindOptions, matches: &[FindMatch],
+        replacement: &str,
+    ) -> Transaction {
+        let mut tx = Transaction::default();
+        // from the end, so the positions of the ones before stay right
+        for m in matches.iter().rev() {
+            if !self.contains(&m.block) { continue }
+            let text = self.plain_text(&m.block);
+            if m.end > text.len() || !text.is_char_boundary(m.start) || !text.is_char_boundary(m.end) {
+                continue
+            }
+            let new = match options.regex {
+                true => {
+                    let Some(caps) = finder.captures_iter(&text)
+                        .find(|c| c.get(0).is_some_and(|g| g.start() == m.start)) else { continue };
+                    let mut new = String::new();
+                    caps.expand(replacement, &mut new);
+                    new
+                },
+                false => replacement.to_string(),
+            };
+            let Some((start, end)) = self.match_range(m) else { continue };
+            tx.push(Op::DeleteRange { start: start.clone(), end });
+            if !new.is_empty() {
+                tx.push(Op::InsertText { pos: start, text: new });
+            }
+        }
+        tx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NodeTree, PageNodeType::*};
+
+    fn texts(doc: &Doc) -> Vec<String> {
+        doc.leaf_blocks().iter().map(|b| doc.plain_text(b)).collect()
+    }
+
+    #[test]
+    fn find_options() {
+        let doc = Doc::from_markdown("Cat cat\nconcat cat.\n", 1);
+        let count = |query: &str, options: FindOptions| {
+            doc.find_all(&options.compile(query).unwrap()).len()
+        };
+        assert_eq!(count("cat", FindOptions::default()), 4);
+        assert_eq!(count("cat", FindOptions { case_sensitive: true, ..Default::default() }), 3);
+        assert_eq!(count("cat", FindOptions { whole_word: true, ..Default::default() }), 3);
+        // not a regex unless asked
+        assert_eq!(count("c.t", FindOptions::default()), 0);
+        assert_eq!(count("c.t", FindOptions { regex: true, ..Default::default() }), 4);
+        assert_eq!(count("x*", FindOptions { regex: true, ..Default::default() }), 0);
+        assert!(FindOptions { regex: true, ..Default::default() }.compile("(").is_err());
+
+        let matches = doc.find_all(&FindOptions::default().compile("cat").unwrap());
+        let second = doc.leaf_blocks()[1].clone();
+        let pos = doc.pos_in_block(&second, 4).unwrap();
+        assert_eq!(doc.match_from(&matches, &pos), Some(3));
+        let end = doc.pos_in_block(&second, 11).unwrap();
+        assert_eq!(doc.match_from(&matches, &end), Some(0));
+    }
+
+    #[test]
+    fn replace_all_at_once() {
+        let mut doc = Doc::from_tree(NodeTree::new(Page, vec![
+            NodeTree::new(TextBlock, vec![
+                NodeTree::text("one fo"),
+                NodeTree::new(Bold, vec![NodeTree::text("o foo")]),
+            ]),
+            NodeTree::block(TextBlock, "foo two"),
+        ]), 1);
+        let before = texts(&doc);
+        let options = FindOptions::default();
+        let finder = options.compile("foo").unwrap();
+        let tx = doc.replace_matches(&finder, &options, &doc.find_all(&finder), "bar");
+        let applied = doc.apply(&tx).unwrap();
+        assert_eq!(texts(&doc), vec!["one bar bar", "bar two"]);
+        doc.apply(&applied.inverse).unwrap();
+        assert_eq!(texts(&doc), before);
+
+        let options = FindOptions { regex: true, ..Default::default() };
+        let finder = options.compile(r"(\w+) (\w+)").unwrap();
+        let matches = doc.find_all(&finder);
+        doc.apply(&doc.replace_matches(&finder, &options, &matches[1..], "$2 $1")).unwrap();
+        assert_eq!(texts(&doc), vec!["one foo foo", "two foo"]);
+    }
+}
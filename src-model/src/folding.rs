@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, PageNodeType, ROOT_HASH};
+
+// folding hides blocks under a block w/o changing the doc. which blocks a
+// fold hides is worked out from the doc each time, so a fold keeps up w/
+// edits (e.g. a new block typed at the end of a folded section is hidden too)
+
+/// a fold, saved in a way that still works once the file is opened again
+/// and all the hashes are new: where the block is, plus its text to check
+/// it's still the same block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fold {
+    pub path: Vec<usize>,
+    pub text: String,
+}
+
+impl Doc {
+    /// the blocks folding `hash` would hide:
+    /// - a heading: the blocks after it in the doc, up to the next heading
+    ///   of the same or a higher level. that can go out of a quote the
+    ///   heading's in, or into part of one w/ the next heading in it
+    /// - a branch block (e.g. a quote): all but its first child, so there's
+    ///   still something to click to unfold it
+    /// - a list item: the indent under it
+    pub fn fold_hides(&self, hash: &str) -> Vec<String> {
+        if !self.contains(hash) || hash == ROOT_HASH { return Vec::new() }
+        let kind = self.kind(hash);
+        if let Some(level) = kind.heading_level() {
+            let mut hidden = Vec::new();
+            let mut hash = hash.to_string();
+            // the siblings after it, then the ones after its parent, etc
+            while hash != ROOT_HASH {
+                match self.next_sibling(&hash) {
+                    Some(next) => {
+                        if self.section_until(&next, level, &mut hidden) { break }
+                        hash = next;
+                    },
+                    None => hash = self.parent(&hash).unwrap().to_string(),
+                }
+            }
+            return hidden;
+        }
+        match kind {
+            PageNodeType::Quote | PageNodeType::Indent => {
+                self.children(hash).iter().skip(1).cloned().collect()
+            },
+            PageNodeType::Dot | PageNodeType::Num | PageNodeType::Check => {
+                match self.next_sibling(hash) {
+                    Some(next) if *self.kind(&next) == PageNodeType::Indent => vec![next],
+                    _ => Vec::new(),
+                }
+            },
+            _ => Vec::new(),
+        }
+    }
+    /// add `hash` to a heading's section, or only the blocks in it before a
+    /// heading of `level` or higher. returns whether it got to that heading
+    fn section_until(&self, hash: &str, level: usize, hidden: &mut Vec<String>) -> bool {
+        if self.kind(hash).heading_level().is_some_and(|l| l <= level) { return true }
+        if !self.has_heading_within(hash, level) {
+            hidden.push(hash.to_string());
+            return false;
+        }
+        self.children(hash).iter()
+            .filter(|c| self.kind(c).is_block())
+            .any(|c| self.section_until(c, level, hidden))
+    }
+    fn has_heading_within(&self, hash: &str, level: usize) -> bool {
+        self.children(hash).iter().any(|c| {
+            self.kind(c).heading_level().is_some_and(|l| l <= level)
+                || (self.kind(c).is_block() && self.has_heading_within(c, level))
+        })
+    }
+    /// the block a fold chevron next to `hash` folds. it's the block itself
+    /// if it has anything to hide, otherwise the branch block it's the first
+    /// child of (so a quote can be folded from its first line)
+    pub fn fold_target(&self, hash: &str) -> Option<String> {
+        let mut hash = hash;
+        loop {
+            if !self.fold_hides(hash).is_empty() { return Some(hash.to_string()) }
+            if self.index_in_parent(hash) != Some(0) { return None }
+            hash = self.parent(hash).filter(|p| *p != ROOT_HASH)?;
+        }
+    }
+    /// every block hidden by the folds, incl. the blocks inside them. folds
+    /// on blocks that are gone or have nothing to hide are skipped
+    pub fn folded_away(&self, folds: &HashSet<String>) -> HashSet<String> {
+        let mut hidden = HashSet::new();
+        for fold in folds {
+            for block in self.fold_hides(fold) {
+                self.add_with_descendants(&block, &mut hidden);
+            }
+        }
+        hidden
+    }
+    fn add_with_descendants(&self, hash: &str, set: &mut HashSet<String>) {
+        set.insert(hash.to_string());
+        for child in self.children(hash) {
+            if self.kind(child).is_block() {
+                self.add_with_descendants(child, set);
+            }
+        }
+    }
+    /// the folds that would have to be opened for `hash` to be seen
+    pub fn folds_hiding(&self, hash: &str, folds: &HashSet<String>) -> Vec<String> {
+        folds.iter().filter(|fold| {
+            self.fold_hides(fold).iter().any(|b| b == hash || self.is_ancestor(b, hash))
+        }).cloned().collect()
+    }
+    /// the folds, for saving
+    pub fn fold_keys(&self, folds: &HashSet<String>) -> Vec<Fold> {
+        let mut keys: Vec<_> = folds.iter()
+            .filter(|h| self.contains(h) && !self.fold_hides(h).is_empty())
+            .map(|h| Fold { path: self.path(h), text: self.fold_text(h) })
+            .collect();
+        keys.sort_by(|a, b| a.path.cmp(&b.path));
+        keys
+    }
+    /// saved folds -> the blocks they're on. folds whose block has changed
+    /// since are dropped
+    pub fn folds_from_keys(&self, keys: &[Fold]) -> HashSet<String> {
+        keys.iter().filter_map(|key| {
+            let mut hash = ROOT_HASH.to_string();
+            for idx in &key.path {
+                hash = self.children(&hash).get(*idx)?.clone();
+            }
+            let same = self.kind(&hash).is_block() && self.fold_text(&hash) == key.text
+                && !self.fold_hides(&hash).is_empty();
+            same.then_some(hash)
+        }).collect()
+    }
+    /// the text a fold is recognised by: its first line
+    fn fold_text(&self, hash: &str) -> String {
+        self.first_leaf_block(hash).map(|b| self.plain_text(&b)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn what_folds_hide() {
+        let doc = Doc::from_markdown("# a\nb\n## c\nd\n# e\n> f\n> g\n\n- h\n\t- i\n", 1);
+        let blocks = doc.leaf_blocks();
+        let text = |hashes: Vec<String>| hashes.iter().map(|h| doc.plain_text(h)).collect::<Vec<_>>();
+        assert_eq!(text(doc.fold_hides(&blocks[0])), vec!["b", "c", "d"]);
+        assert_eq!(text(doc.fold_hides(&blocks[2])), vec!["d"]);
+        let quote = doc.parent(&blocks[5]).unwrap().to_string();
+        assert_eq!(text(doc.fold_hides(&quote)), vec!["g"]);
+        assert_eq!(doc.fold_target(&blocks[5]), Some(quote.clone()));
+        assert_eq!(doc.fold_target(&blocks[6]), None);
+        let indent = doc.parent(&blocks[8]).unwrap().to_string();
+        assert_eq!(doc.fold_hides(&blocks[7]), vec![indent.clone()]);
+
+        let folds = HashSet::from([blocks[0].clone(), blocks[7].clone(), blocks[1].clone()]);
+        let hidden = doc.folded_away(&folds);
+        // the indent and what's in it
+        assert!(hidden.contains(&indent) && hidden.contains(&blocks[8]));
+        assert!(hidden.contains(&blocks[3]) && !hidden.contains(&blocks[4]));
+        assert_eq!(doc.folds_hiding(&blocks[3], &folds), vec![blocks[0].clone()]);
+    }
+
+    #[test]
+    fn heading_sections_go_in_doc_order() {
+        let doc = Doc::from_markdown("> # a\n> b\n\nc\n> d\n> # e\nf\n", 1);
+        let blocks = doc.leaf_blocks();
+        let text = |hashes: Vec<String>| hashes.iter().map(|h| doc.plain_text(h)).collect::<Vec<_>>();
+        // out of its quote, and into the next one up to `e`, but not the
+        // quote `e` is in
+        assert_eq!(text(doc.fold_hides(&blocks[0])), vec!["b", "c", "d"]);
+        assert_eq!(text(doc.fold_hides(&blocks[4])), vec!["f"]);
+        let hidden = doc.folded_away(&HashSet::from([blocks[0].clone()]));
+        assert!(!hidden.contains(doc.parent(&blocks[4]).unwrap()));
+        // a quote w/ only lower headings in it is hidden whole
+        let doc = Doc::from_markdown("# a\n> ## b\n> c\n", 1);
+        let quote = doc.parent(&doc.leaf_blocks()[1]).unwrap().to_string();
+        assert_eq!(doc.fold_hides(&doc.leaf_blocks()[0]), vec![quote]);
+    }
+
+    #[test]
+    fn folds_survive_reopening() {
+        let md = "# a\nb\n> c\n> d\n";
+        let doc = Doc::from_markdown(md, 1);
+        let blocks = doc.leaf_blocks();
+        let quote = doc.parent(&blocks[2]).unwrap().to_string();
+        // `b` has nothing to fold, so it isn't kept
+        let keys = doc.fold_keys(&HashSet::from([blocks[0].clone(), quote, blocks[1].clone()]));
+        assert_eq!(keys, vec![
+            Fold { path: vec![0], text: "a".into() },
+            Fold { path: vec![2], text: "c".into() },
+        ]);
+        let reopened = Doc::from_markdown(md, 2);
+        let folds = reopened.folds_from_keys(&keys);
+        assert_eq!(folds.len(), 2);
+        assert!(folds.contains(&reopened.leaf_blocks()[0]));
+        // the file changed, so the heading isn't where it was
+        let changed = Doc::from_markdown("intro\n# a\nb\n> c\n> d\n", 3);
+        assert!(changed.folds_from_keys(&keys).is_empty());
+    }
+}
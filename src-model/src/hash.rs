@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+/// chars a hash can be made of. kept to lowercase ascii letters and digits so
+/// the hash is safe in a DOM attribute and can be written to the markdown file
+/// as a `^block-id`
+const HASH_CHARS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+/// goes between the `^` and the hash in a `^block-id`, so text that just 
+/// happens to end in e.g. `^2` or `^todo` isn't read as one
+const BLOCK_ID_PREFIX: &str = "id-";
+/// 36^4 = 1.68 million, which is way more nodes than any page should have. if
+/// the page does somehow fill up, the length is bumped (see `new_hash`)
+const HASH_LEN: usize = 4;
+
+/// the one place hashes get created, so every hash in the page is unique
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashAllocator {
+    used: HashSet<String>,
+    len: usize,
+    /// xorshift state. there's no `Math::random()` outside the webview, so
+    /// whoever creates the allocator passes in a seed
+    rng: u64,
+}
+impl HashAllocator {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0 forever
+        let rng = if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed };
+        Self { used: HashSet::new(), len: HASH_LEN, rng }
+    }
+    /// generate a hash not used by any other node
+    pub fn new_hash(&mut self) -> String {
+        let mut collisions = 0;
+        loop {
+            let hash = self.rand_hash();
+            if self.used.insert(hash.clone()) { return hash }
+            // if we keep hitting used hashes the page is getting full, so
+            // start making longer hashes rather than looping forever
+            collisions += 1;
+            if collisions > 8 {
+                self.len += 1;
+                collisions = 0;
+            }
+        }
+    }
+    /// claim an existing hash (e.g. one read from a `^block-id`). returns
+    /// false if the hash is invalid or already taken, in which case the
+    /// node needs a new one
+    pub fn reserve(&mut self, hash: &str) -> bool {
+        is_valid_hash(hash) && self.used.insert(hash.to_string())
+    }
+    pub fn contains(&self, hash: &str) -> bool {
+        self.used.contains(hash)
+    }
+    fn next_rand(&mut self) -> u64 {
+        // xorshift64*
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+    /// generate a random hash string of length `self.len`
+    fn rand_hash(&mut self) -> String {
+        let base = HASH_CHARS.len() as u64;
+        let mut hash_str = String::with_capacity(self.len);
+        let mut carry = self.next_rand();
+        for _ in 0..self.len {
+            // we're treating the hash as a number with base 36 (like how the
+            // decimal system uses a number with base 10)
+            hash_str.push(HASH_CHARS[(carry % base) as usize] as char);
+            carry /= base;
+            if carry == 0 { carry = self.next_rand() }
+        }
+        hash_str
+    }
+}
+
+pub fn is_valid_hash(hash: &str) -> bool {
+    !hash.is_empty() && hash.bytes().all(|b| HASH_CHARS.contains(&b) || b == b'-')
+}
+
+/// the hash in a `^id-xxxx` block id. only chars a new hash can have, so
+/// the hash is the same when it's written back out
+fn parse_block_id(id: &str) -> Option<&str> {
+    let hash = id.strip_prefix('^')?.strip_prefix(BLOCK_ID_PREFIX)?;
+    let valid = !hash.is_empty() && hash.bytes().all(|b| HASH_CHARS.contains(&b));
+    valid.then_some(hash)
+}
+
+/// whether `text` is a whole `^block-id`, e.g. to escape it when it's just
+/// text
+pub fn is_block_id(text: &str) -> bool {
+    parse_block_id(text).is_some()
+}
+
+/// split the `^block-id` off the end of a markdown line, e.g.
+/// `"some text ^id-x1b9"` -> `("some text", Some("x1b9"))`
+pub fn split_block_id(line: &str) -> (&str, Option<&str>) {
+    if let Some(idx) = line.rfind(" ^") {
+        if let Some(hash) = parse_block_id(&line[idx + 1..]) {
+            return (&line[..idx], Some(hash));
+        }
+    }
+    (line, None)
+}
+
+/// add a `^block-id` to the end of a markdown line so the block keeps its
+/// hash next time the file is opened
+pub fn append_block_id(line: &str, hash: &str) -> String {
+    format!("{} ^{}{}", line, BLOCK_ID_PREFIX, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_are_unique_and_printable() {
+        let mut hashes = HashAllocator::new(7);
+        let mut seen = HashSet::new();
+        for _ in 0..10_000 {
+            let hash = hashes.new_hash();
+            assert!(is_valid_hash(&hash));
+            assert!(seen.insert(hash));
+        }
+    }
+
+    #[test]
+    fn zero_seed_still_random() {
+        let mut hashes = HashAllocator::new(0);
+        assert_ne!(hashes.new_hash(), hashes.new_hash());
+    }
+
+    #[test]
+    fn reserve_rejects_taken_and_invalid() {
+        let mut hashes = HashAllocator::new(1);
+        assert!(hashes.reserve("ab12"));
+        assert!(!hashes.reserve("ab12"));
+        assert!(!hashes.reserve(""));
+        assert!(!hashes.reserve("no spaces"));
+        assert!(hashes.contains("ab12"));
+    }
+
+    #[test]
+    fn block_ids() {
+        assert_eq!(split_block_id("some text ^id-x1b9"), ("some text", Some("x1b9")));
+        assert_eq!(split_block_id("some text ^id-not valid"), ("some text ^id-not valid", None));
+        assert_eq!(split_block_id("2^10"), ("2^10", None));
+        assert_eq!(append_block_id("some text", "x1b9"), "some text ^id-x1b9");
+        // w/o the prefix it's just text
+        for line in ["see note ^2", "x ^todo", "a ^x1b9", "a ^id-", "a ^id-a-b"] {
+            assert_eq!(split_block_id(line), (line, None));
+        }
+        assert!(is_block_id("^id-x1b9"));
+        assert!(!is_block_id("^id-x1b9 and more"));
+    }
+
+    #[test]
+    fn new_hashes_skip_reserved_ones() {
+        // same seed, so the 2nd allocator would make the 1st one's hashes
+        let mut first = HashAllocator::new(3);
+        let taken: Vec<String> = (0..100).map(|_| first.new_hash()).collect();
+        let mut hashes = HashAllocator::new(3);
+        for hash in &taken {
+            assert!(hashes.reserve(hash));
+        }
+        for _ in 0..100 {
+            assert!(!taken.contains(&hashes.new_hash()));
+        }
+    }
+
+    #[test]
+    fn block_ids_round_trip() {
+        let mut hashes = HashAllocator::new(5);
+        for line in ["some text", "2^10", "ends w/ a caret ^", "a ^not valid one", ""] {
+            let hash = hashes.new_hash();
+            let with_id = append_block_id(line, &hash);
+            assert_eq!(split_block_id(&with_id), (line, Some(hash.as_str())));
+        }
+    }
+}
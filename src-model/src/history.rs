@@ -0,0 +1,194 @@
+use super::{Doc, Op, Selection, Transaction};
+
+// undo/redo. every edit gives back the transaction that undoes it (see
+// `Doc::apply`), and that goes on the undo stack w/ the selection from before
+// the edit. undoing applies it, and puts what undoes *that* on the redo stack.
+// a run of typed chars is one step, so undo doesn't go a letter at a time
+
+/// how many edits can be undone
+pub const MAX_UNDO: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    tx: Transaction,
+    /// where the selection goes once `tx` is applied
+    selection: Option<Selection>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+}
+impl History {
+    /// keep `inverse` (from applying an edit) so the edit can be undone.
+    /// `selection` is the one from before the edit
+    pub fn record(&mut self, inverse: Transaction, selection: Option<Selection>) {
+        if inverse.is_empty() { return }
+        self.redo.clear();
+        // typing right after the last typed char makes the last step longer
+        if let (Some(last), [Op::DeleteRange { start, end }]) = (self.undo.last_mut(), inverse.ops.as_slice()) {
+            if let [Op::DeleteRange { start: last_start, end: last_end }] = last.tx.ops.as_mut_slice() {
+                if last_start.hash == start.hash && last_end == start && start.hash == end.hash {
+                    *last_end = end.clone();
+                    return;
+                }
+            }
+        }
+        self.undo.push(Step { tx: inverse, selection });
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    /// undo the last edit. `selection` is the current one, for redo to put
+    /// back. returns where the selection goes now, or `None` if there was
+    /// nothing to undo
+    pub fn undo(&mut self, doc: &mut Doc, selection: Option<Selection>) -> Option<Selection> {
+        let step = self.undo.pop()?;
+        let sel = Self::apply(doc, step, selection, &mut self.redo);
+        if sel.is_none() {
+            // the doc was changed some other way, so the rest won't fit it
+            self.clear();
+        }
+        sel
+    }
+    /// redo the last edit that was undone
+    pub fn redo(&mut self, doc: &mut Doc, selection: Option<Selection>) -> Option<Selection> {
+        let step = self.redo.pop()?;
+        let sel = Self::apply(doc, step, selection, &mut self.undo);
+        if sel.is_none() {
+            self.clear();
+        }
+        sel
+    }
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+    /// apply the step, and push what undoes it onto `other`
+    fn apply(doc: &mut Doc, step: Step, selection: Option<Selection>, other: &mut Vec<Step>) -> Option<Selection> {
+        let applied = doc.apply(&step.tx).ok()?;
+        other.push(Step { tx: applied.inverse, selection });
+        let sel = step.selection.filter(|s| doc.selection_is_valid(s));
+        sel.or_else(|| applied.caret.map(Selection::caret))
+            .or_else(|| doc.select_all().map(|s| Selection::caret(s.anchor)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FindOptions, Pos};
+
+    fn type_text(doc: &mut Doc, history: &mut History, text: &str, pos: &Pos) {
+        for (i, c) in text.chars().enumerate() {
+            let pos = Pos::new(&pos.hash, pos.offset + i);
+            let tx = Op::InsertText { pos: pos.clone(), text: c.into() }.into();
+            history.record(doc.apply(&tx).unwrap().inverse, Some(Selection::caret(pos)));
+        }
+    }
+
+    #[test]
+    fn typing_is_one_step() {
+        let mut doc = Doc::from_markdown("one\n\ntwo\n", 1);
+        let mut history = History::default();
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let caret = |offset| Some(Selection::caret(Pos::new(&text, offset)));
+        type_text(&mut doc, &mut history, "abc", &Pos::new(&text, 3));
+        // typing somewhere else is a new step
+        type_text(&mut doc, &mut history, "x", &Pos::new(&text, 0));
+        assert_eq!(doc.to_markdown(), "xoneabc\n\ntwo\n");
+        assert_eq!(history.undo(&mut doc, caret(1)), caret(0));
+        assert_eq!(doc.to_markdown(), "oneabc\n\ntwo\n");
+        // all 3 chars at once, and the caret goes back to before them
+        assert_eq!(history.undo(&mut doc, caret(6)), caret(3));
+        assert_eq!(doc.to_markdown(), "one\n\ntwo\n");
+        assert!(!history.can_undo());
+        assert!(history.undo(&mut doc, caret(3)).is_none());
+    }
+
+    #[test]
+    fn redo_puts_it_back() {
+        let mut doc = Doc::from_markdown("one\n", 1);
+        let mut history = History::default();
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let caret = |offset| Some(Selection::caret(Pos::new(&text, offset)));
+        type_text(&mut doc, &mut history, "abc", &Pos::new(&text, 3));
+        history.undo(&mut doc, caret(6));
+        assert_eq!(history.redo(&mut doc, caret(3)), caret(6));
+        assert_eq!(doc.to_markdown(), "oneabc\n");
+        // and undoing the redo works too
+        history.undo(&mut doc, caret(6));
+        assert_eq!(doc.to_markdown(), "one\n");
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut doc = Doc::from_markdown("one\n", 1);
+        let mut history = History::default();
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        type_text(&mut doc, &mut history, "abc", &Pos::new(&text, 3));
+        history.undo(&mut doc, None);
+        assert!(history.can_redo());
+        let tx = Op::SplitBlock { pos: Pos::new(&text, 1) }.into();
+        history.record(doc.apply(&tx).unwrap().inverse, None);
+        assert!(!history.can_redo());
+        assert!(history.redo(&mut doc, None).is_none());
+        assert_eq!(doc.to_markdown(), "o\n\nne\n");
+        history.undo(&mut doc, None);
+        assert_eq!(doc.to_markdown(), "one\n");
+    }
+
+    #[test]
+    fn replace_all_is_one_step() {
+        let mut doc = Doc::from_markdown("a cat\n\n**cat** cat\n", 1);
+        let before = doc.to_markdown();
+        let mut history = History::default();
+        let options = FindOptions::default();
+        let finder = options.compile("cat").unwrap();
+        let tx = doc.replace_matches(&finder, &options, &doc.find_all(&finder), "dog");
+        history.record(doc.apply(&tx).unwrap().inverse, None);
+        assert_eq!(doc.to_markdown(), "a dog\n\n**dog** dog\n");
+        history.undo(&mut doc, None);
+        assert_eq!(doc.to_markdown(), before);
+        assert!(!history.can_undo());
+        history.redo(&mut doc, None);
+        assert_eq!(doc.to_markdown(), "a dog\n\n**dog** dog\n");
+    }
+
+    #[test]
+    fn journal_undoes_several_applies() {
+        let mut doc = Doc::from_markdown("one\n", 1);
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        doc.start_journal();
+        doc.apply(&Op::InsertText { pos: Pos::new(&text, 0), text: "x".into() }.into()).unwrap();
+        doc.apply(&Op::SplitBlock { pos: Pos::new(&text, 2) }.into()).unwrap();
+        let inverse = doc.end_journal();
+        assert_eq!(doc.to_markdown(), "xo\n\nne\n");
+        doc.apply(&inverse).unwrap();
+        assert_eq!(doc.to_markdown(), "one\n");
+    }
+
+    #[test]
+    fn keeps_the_last_edits() {
+        let mut doc = Doc::from_markdown("one\n", 1);
+        let mut history = History::default();
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        // splits aren't grouped, so each is a step
+        for _ in 0..MAX_UNDO + 5 {
+            let tx = Op::SplitBlock { pos: Pos::new(&text, 0) }.into();
+            history.record(doc.apply(&tx).unwrap().inverse, None);
+        }
+        let mut undone = 0;
+        while history.undo(&mut doc, None).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO);
+    }
+}
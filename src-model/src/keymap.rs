@@ -0,0 +1,441 @@
+use std::{collections::HashMap, fmt};
+use serde::{Serialize, Deserialize};
+
+// shortcuts are looked up in a keymap rather than matched on key codes, so
+// they can be changed by the user. a keymap starts w/ the defaults for the
+// platform, then the bindings from the user's config file are put on top
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Mac,
+    /// linux and windows
+    Other,
+}
+
+/// what a shortcut does. the name is what goes in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    MoveToPageStart,
+    MoveToPageEnd,
+    PageUp,
+    PageDown,
+    SelectAll,
+    Indent,
+    Outdent,
+    ToggleHeading,
+    InsertTable,
+    CommandPalette,
+    OpenFile,
+    ExportMarkdown,
+    ToggleVim,
+    MoveBlockUp,
+    MoveBlockDown,
+    DuplicateBlock,
+    DeleteBlock,
+    Find,
+    FindInWorkspace,
+    Backlinks,
+    RenameNote,
+    Undo,
+    Redo,
+}
+impl Command {
+    pub const ALL: [Command; 23] = [
+        Command::MoveToPageStart,
+        Command::MoveToPageEnd,
+        Command::PageUp,
+        Command::PageDown,
+        Command::SelectAll,
+        Command::Indent,
+        Command::Outdent,
+        Command::ToggleHeading,
+        Command::InsertTable,
+        Command::CommandPalette,
+        Command::OpenFile,
+        Command::ExportMarkdown,
+        Command::ToggleVim,
+        Command::MoveBlockUp,
+        Command::MoveBlockDown,
+        Command::DuplicateBlock,
+        Command::DeleteBlock,
+        Command::Find,
+        Command::FindInWorkspace,
+        Command::Backlinks,
+        Command::RenameNote,
+        Command::Undo,
+        Command::Redo,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::MoveToPageStart => "move_to_page_start",
+            Command::MoveToPageEnd => "move_to_page_end",
+            Command::PageUp => "page_up",
+            Command::PageDown => "page_down",
+            Command::SelectAll => "select_all",
+            Command::Indent => "indent",
+            Command::Outdent => "outdent",
+            Command::ToggleHeading => "toggle_heading",
+            Command::InsertTable => "insert_table",
+            Command::CommandPalette => "command_palette",
+            Command::OpenFile => "open_file",
+            Command::ExportMarkdown => "export_markdown",
+            Command::ToggleVim => "toggle_vim",
+            Command::MoveBlockUp => "move_block_up",
+            Command::MoveBlockDown => "move_block_down",
+            Command::DuplicateBlock => "duplicate_block",
+            Command::DeleteBlock => "delete_block",
+            Command::Find => "find",
+            Command::FindInWorkspace => "find_in_workspace",
+            Command::Backlinks => "backlinks",
+            Command::RenameNote => "rename_note",
+            Command::Undo => "undo",
+            Command::Redo => "redo",
+        }
+    }
+    /// what it's called in the command palette
+    pub fn title(&self) -> &'static str {
+        match self {
+            Command::MoveToPageStart => "Go to start of page",
+            Command::MoveToPageEnd => "Go to end of page",
+            Command::PageUp => "Page up",
+            Command::PageDown => "Page down",
+            Command::SelectAll => "Select all",
+            Command::Indent => "Indent",
+            Command::Outdent => "Outdent",
+            Command::ToggleHeading => "Toggle heading",
+            Command::InsertTable => "Insert table",
+            Command::CommandPalette => "Show all commands",
+            Command::OpenFile => "Open file",
+            Command::ExportMarkdown => "Export as markdown",
+            Command::ToggleVim => "Toggle vim mode",
+            Command::MoveBlockUp => "Move block up",
+            Command::MoveBlockDown => "Move block down",
+            Command::DuplicateBlock => "Duplicate block",
+            Command::DeleteBlock => "Delete block",
+            Command::Find => "Find and replace",
+            Command::FindInWorkspace => "Find and replace in workspace",
+            Command::Backlinks => "Show backlinks",
+            Command::RenameNote => "Rename or move note",
+            Command::Undo => "Undo",
+            Command::Redo => "Redo",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+/// a key plus the modifiers held down w/ it, e.g. "Ctrl-B", "Cmd-Shift-7",
+/// "Alt-Up"
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Chord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// Cmd on mac, the Windows/Super key everywhere else
+    pub meta: bool,
+    /// lowercase for letters, otherwise a name from `KEY_NAMES`
+    pub key: String,
+}
+
+/// (name, other names it can be written as). the other names include what
+/// the browser calls the key (`KeyboardEvent.key`)
+const KEY_NAMES: [(&str, &[&str]); 14] = [
+    ("Up", &["ArrowUp"]),
+    ("Down", &["ArrowDown"]),
+    ("Left", &["ArrowLeft"]),
+    ("Right", &["ArrowRight"]),
+    ("Home", &[]),
+    ("End", &[]),
+    ("PageUp", &[]),
+    ("PageDown", &[]),
+    ("Tab", &[]),
+    ("Enter", &["Return"]),
+    ("Escape", &["Esc"]),
+    ("Backspace", &[]),
+    ("Delete", &["Del"]),
+    ("Space", &[" "]),
+];
+
+/// the key's name in a chord, or `None` if it isn't a key that can be bound
+/// (e.g. a modifier on its own)
+pub fn key_name(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c != ' ' { return Some(c.to_lowercase().collect()) }
+    }
+    for (name, others) in KEY_NAMES {
+        if name.eq_ignore_ascii_case(key) || others.iter().any(|o| o.eq_ignore_ascii_case(key)) {
+            return Some(name.to_string());
+        }
+    }
+    // F1-F12
+    let is_f_key = key.get(..1).is_some_and(|f| f.eq_ignore_ascii_case("f"))
+        && key[1..].parse::<u8>().is_ok_and(|n| (1..=12).contains(&n));
+    is_f_key.then(|| key.to_uppercase())
+}
+
+impl Chord {
+    /// a chord from a key event. `key` can be what the browser calls it
+    pub fn from_event(key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool) -> Option<Self> {
+        Some(Self { ctrl, alt, shift, meta, key: key_name(key)? })
+    }
+    /// parse e.g. "Ctrl-B" or "Mod-Shift-7". "Mod" is Cmd on mac and Ctrl
+    /// everywhere else, so one binding works on both
+    pub fn parse(chord: &str, platform: Platform) -> Result<Self, KeymapError> {
+        let bad = || KeymapError::BadChord(chord.to_string());
+        // the key itself can be "-"
+        let (mods, key) = match chord.strip_suffix("--") {
+            Some(mods) => (mods, "-"),
+            None => chord.rsplit_once('-').unwrap_or(("", chord)),
+        };
+        let mut parsed = Self { ctrl: false, alt: false, shift: false, meta: false,
+            key: key_name(key).ok_or_else(bad)? };
+        for modifier in mods.split('-').filter(|m| !m.is_empty()) {
+            let flag = match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => &mut parsed.ctrl,
+                "alt" | "option" | "opt" => &mut parsed.alt,
+                "shift" => &mut parsed.shift,
+                "cmd" | "meta" | "super" | "win" => &mut parsed.meta,
+                "mod" if platform == Platform::Mac => &mut parsed.meta,
+                "mod" => &mut parsed.ctrl,
+                _ => return Err(bad()),
+            };
+            *flag = true;
+        }
+        Ok(parsed)
+    }
+}
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mods = [(self.ctrl, "Ctrl-"), (self.alt, "Alt-"), (self.shift, "Shift-"),
+            (self.meta, "Cmd-")];
+        for (held, name) in mods {
+            if held { write!(f, "{}", name)? }
+        }
+        match self.key.len() {
+            1 => write!(f, "{}", self.key.to_uppercase()),
+            _ => write!(f, "{}", self.key),
+        }
+    }
+}
+
+/// one entry in the user's config file. a `null` command unbinds the chord
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub key: String,
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapError {
+    BadChord(String),
+    UnknownCommand(String),
+    /// the config binds the same chord more than once. the first one wins
+    Conflict { chord: Chord, commands: (Option<Command>, Option<Command>) },
+    /// the config binds a chord that already has a default command, so the
+    /// default can't be run w/ it anymore. the config still wins, this is
+    /// just so it isn't a surprise. unbinding it (`null`) isn't reported
+    ReplacesDefault { chord: Chord, default: Command, command: Command },
+}
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |c: &Option<Command>| c.map_or("nothing", |c| c.name());
+        match self {
+            KeymapError::BadChord(chord) => write!(f, "can't read key {:?}", chord),
+            KeymapError::UnknownCommand(name) => write!(f, "no command called {:?}", name),
+            KeymapError::Conflict { chord, commands: (first, second) } => write!(f,
+                "{} is bound to both {} and {}", chord, name(first), name(second)),
+            KeymapError::ReplacesDefault { chord, default, command } => write!(f,
+                "{} is bound to {}, so it doesn't do {} anymore", chord, command.name(), default.name()),
+        }
+    }
+}
+impl std::error::Error for KeymapError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    pub platform: Platform,
+    bindings: HashMap<Chord, Command>,
+}
+impl Keymap {
+    /// the shortcuts you get w/o a config file
+    pub fn defaults(platform: Platform) -> Self {
+        let shared = [
+            ("Mod-A", Command::SelectAll),
+            ("PageUp", Command::PageUp),
+            ("PageDown", Command::PageDown),
+            // (in a code block these type/remove a tab instead, see `Doc::code_tab`)
+            ("Tab", Command::Indent),
+            ("Shift-Tab", Command::Outdent),
+            ("Mod-Alt-1", Command::ToggleHeading),
+            ("Mod-Shift-P", Command::CommandPalette),
+            ("Mod-O", Command::OpenFile),
+            ("Mod-Shift-E", Command::ExportMarkdown),
+            ("Alt-Up", Command::MoveBlockUp),
+            ("Alt-Down", Command::MoveBlockDown),
+            ("Mod-D", Command::DuplicateBlock),
+            ("Mod-Shift-K", Command::DeleteBlock),
+            ("Mod-F", Command::Find),
+            ("Mod-Shift-F", Command::FindInWorkspace),
+            ("Mod-Shift-B", Command::Backlinks),
+            ("F2", Command::RenameNote),
+            ("Mod-Z", Command::Undo),
+            ("Mod-Shift-Z", Command::Redo),
+        ];
+        let per_platform = match platform {
+            Platform::Mac => [
+                ("Cmd-Up", Command::MoveToPageStart),
+                ("Cmd-Down", Command::MoveToPageEnd),
+            ],
+            Platform::Other => [
+                ("Ctrl-Home", Command::MoveToPageStart),
+                ("Ctrl-End", Command::MoveToPageEnd),
+            ],
+        };
+        let bindings = shared.into_iter().chain(per_platform)
+            .map(|(chord, command)| (Chord::parse(chord, platform).unwrap(), command))
+            .collect();
+        Self { platform, bindings }
+    }
+    /// the defaults w/ the user's bindings on top. bad bindings are skipped
+    /// (and returned, so they can be shown to the user) rather than throwing
+    /// the whole config away
+    pub fn with_overrides(platform: Platform, overrides: &[KeyBinding]) -> (Self, Vec<KeymapError>) {
+        let mut keymap = Self::defaults(platform);
+        let mut errors = Vec::new();
+        let mut seen: HashMap<Chord, Option<Command>> = HashMap::new();
+        for binding in overrides {
+            let chord = match Chord::parse(&binding.key, platform) {
+                Ok(chord) => chord,
+                Err(err) => { errors.push(err); continue },
+            };
+            let command = match binding.command.as_deref().map(|n| (n, Command::from_name(n))) {
+                Some((_, Some(command))) => Some(command),
+                Some((name, None)) => {
+                    errors.push(KeymapError::UnknownCommand(name.to_string()));
+                    continue;
+                },
+                None => None,
+            };
+            if let Some(first) = seen.get(&chord) {
+                if *first != command {
+                    errors.push(KeymapError::Conflict { chord, commands: (*first, command) });
+                }
+                continue;
+            }
+            seen.insert(chord.clone(), command);
+            match command {
+                Some(command) => {
+                    let default = keymap.bindings.insert(chord.clone(), command);
+                    if let Some(default) = default.filter(|d| *d != command) {
+                        errors.push(KeymapError::ReplacesDefault { chord, default, command });
+                    }
+                },
+                None => { keymap.bindings.remove(&chord); },
+            }
+        }
+        (keymap, errors)
+    }
+    pub fn command(&self, chord: &Chord) -> Option<Command> {
+        self.bindings.get(chord).copied()
+    }
+    /// all the chords bound to the command, e.g. to show next to it in a menu
+    pub fn chords(&self, command: Command) -> Vec<&Chord> {
+        let mut chords: Vec<_> = self.bindings.iter()
+            .filter(|(_, c)| **c == command).map(|(chord, _)| chord).collect();
+        chords.sort_by_key(|c| c.to_string());
+        chords
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bind(key: &str, command: Option<&str>) -> KeyBinding {
+        KeyBinding { key: key.into(), command: command.map(String::from) }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = Chord::parse("cmd-shift-7", Platform::Other).unwrap();
+        assert!(chord.meta && chord.shift && !chord.ctrl);
+        assert_eq!(chord.to_string(), "Shift-Cmd-7");
+        assert_eq!(Chord::parse("Alt-Up", Platform::Mac).unwrap(),
+            Chord::from_event("ArrowUp", false, true, false, false).unwrap());
+        assert_eq!(Chord::parse("Mod-B", Platform::Mac).unwrap().to_string(), "Cmd-B");
+        assert_eq!(Chord::parse("Mod-B", Platform::Other).unwrap().to_string(), "Ctrl-B");
+        assert_eq!(Chord::parse("Ctrl--", Platform::Other).unwrap().key, "-");
+        assert_eq!(Chord::parse("F5", Platform::Other).unwrap().key, "F5");
+        assert!(Chord::parse("Hyper-B", Platform::Other).is_err());
+        assert!(Chord::parse("Ctrl-Nope", Platform::Other).is_err());
+        // modifiers on their own aren't chords
+        assert_eq!(Chord::from_event("Shift", false, false, true, false), None);
+    }
+
+    #[test]
+    fn defaults_per_platform() {
+        let mac = Keymap::defaults(Platform::Mac);
+        let other = Keymap::defaults(Platform::Other);
+        let select_all = |p| Chord::parse("Mod-A", p).unwrap();
+        assert_eq!(mac.command(&select_all(Platform::Mac)), Some(Command::SelectAll));
+        assert_eq!(other.command(&select_all(Platform::Other)), Some(Command::SelectAll));
+        assert_eq!(other.command(&select_all(Platform::Mac)), None);
+        let cmd_up = Chord::parse("Cmd-Up", Platform::Mac).unwrap();
+        assert_eq!(mac.command(&cmd_up), Some(Command::MoveToPageStart));
+        assert_eq!(other.command(&cmd_up), None);
+        let shift_tab = Chord::from_event("Tab", false, false, true, false).unwrap();
+        assert_eq!(other.command(&shift_tab), Some(Command::Outdent));
+        let redo = Chord::parse("Cmd-Shift-Z", Platform::Mac).unwrap();
+        assert_eq!(mac.command(&redo), Some(Command::Redo));
+    }
+
+    #[test]
+    fn overrides_and_conflicts() {
+        let (keymap, errors) = Keymap::with_overrides(Platform::Other, &[
+            bind("Alt-Up", Some("move_to_page_start")),
+            bind("Tab", None),
+            bind("Ctrl-B", Some("select_all")),
+            bind("ctrl-b", Some("page_down")),
+            bind("Ctrl-Q", Some("quit")),
+            bind("Ctrl-", Some("indent")),
+        ]);
+        let chord = |c| Chord::parse(c, Platform::Other).unwrap();
+        assert_eq!(keymap.command(&chord("Alt-Up")), Some(Command::MoveToPageStart));
+        // the defaults are still there
+        assert_eq!(keymap.command(&chord("Ctrl-Home")), Some(Command::MoveToPageStart));
+        assert_eq!(keymap.command(&chord("Tab")), None);
+        assert_eq!(keymap.command(&chord("Ctrl-B")), Some(Command::SelectAll));
+        assert_eq!(keymap.chords(Command::SelectAll).len(), 2);
+        assert_eq!(errors, vec![
+            // Alt-Up moves the block up by default
+            KeymapError::ReplacesDefault { chord: chord("Alt-Up"),
+                default: Command::MoveBlockUp, command: Command::MoveToPageStart },
+            KeymapError::Conflict { chord: chord("Ctrl-B"),
+                commands: (Some(Command::SelectAll), Some(Command::PageDown)) },
+            KeymapError::UnknownCommand("quit".into()),
+            KeymapError::BadChord("Ctrl-".into()),
+        ]);
+        // binding a default chord to its own command again, or unbinding
+        // it, isn't a surprise
+        let (_, errors) = Keymap::with_overrides(Platform::Other, &[
+            bind("Ctrl-F", Some("find")),
+            bind("Ctrl-D", None),
+        ]);
+        assert!(errors.is_empty());
+        let (keymap, errors) = Keymap::with_overrides(Platform::Other, &[bind("Ctrl-F", Some("open_file"))]);
+        assert_eq!(keymap.command(&chord("Ctrl-F")), Some(Command::OpenFile));
+        assert_eq!(errors[0].to_string(), "Ctrl-F is bound to open_file, so it doesn't do find anymore");
+    }
+
+    #[test]
+    fn command_names_round_trip() {
+        for command in Command::ALL {
+            assert_eq!(Command::from_name(command.name()), Some(command));
+            let json = serde_json::to_string(&command).unwrap();
+            assert_eq!(json, format!("{:?}", command.name()));
+        }
+    }
+}
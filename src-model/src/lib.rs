@@ -0,0 +1,22 @@
+mod node_type; pub use node_type::*;
+mod hash; pub use hash::*;
+mod text_offset; pub use text_offset::*;
+mod doc; pub use doc::*;
+mod edit;
+mod transaction; pub use transaction::*;
+mod selection; pub use selection::*;
+mod syntax; pub use syntax::*;
+mod markdown; pub use markdown::*;
+mod keymap; pub use keymap::*;
+mod slash; pub use slash::*;
+mod palette; pub use palette::*;
+mod vim; pub use vim::*;
+mod block_selection; pub use block_selection::*;
+mod folding; pub use folding::*;
+mod outline; pub use outline::*;
+mod find; pub use find::*;
+mod workspace_replace; pub use workspace_replace::*;
+mod wikilink; pub use wikilink::*;
+mod backlinks; pub use backlinks::*;
+mod rename; pub use rename::*;
+mod history; pub use history::*;
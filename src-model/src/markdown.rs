@@ -0,0 +1,513 @@
+use std::{collections::HashSet, mem};
+
+use super::{Doc, NodeTree, PageNodeType, SyntaxProfile, split_block_id, append_block_id, is_block_id};
+use super::syntax::is_intraword_delim;
+
+/// content key marking a block whose hash gets written to the file as a
+/// `^block-id` (so links to it keep working after a reload)
+pub const PINNED: &str = "pinned";
+
+impl Doc {
+    pub fn from_markdown(md: &str, seed: u64) -> Self {
+        Self::from_tree(parse_markdown(md), seed)
+    }
+    pub fn to_markdown(&self) -> String {
+        tree_to_markdown(&self.root_tree())
+    }
+    pub fn from_markdown_with(md: &str, seed: u64, profile: &SyntaxProfile) -> Self {
+        Self::from_tree(parse_markdown_with(md, profile), seed)
+    }
+    pub fn to_markdown_with(&self, profile: &SyntaxProfile) -> String {
+        tree_to_markdown_with(&self.root_tree(), profile)
+    }
+}
+
+/// parse a markdown file into a `Page` tree. hashes are left empty (apart
+/// from `^block-id`s) for the doc to fill in
+pub fn parse_markdown(md: &str) -> NodeTree {
+    parse_markdown_with(md, &SyntaxProfile::standard())
+}
+/// parse w/ the user's syntax as well as standard markdown
+pub fn parse_markdown_with(md: &str, profile: &SyntaxProfile) -> NodeTree {
+    let lines: Vec<&str> = md.lines().collect();
+    NodeTree::new(PageNodeType::Page, parse_blocks(&lines, profile, &mut HashSet::new()))
+}
+
+pub fn tree_to_markdown(page: &NodeTree) -> String {
+    tree_to_markdown_with(page, &SyntaxProfile::standard())
+}
+/// still standard markdown, but w/ the user's picks where they're standard 
+/// (e.g. `_italic_` rather than `*italic*`)
+pub fn tree_to_markdown_with(page: &NodeTree, profile: &SyntaxProfile) -> String {
+    let mut lines = Vec::new();
+    write_blocks(&page.children, "", profile, &mut lines);
+    let mut md = lines.join("\n");
+    md.push('\n');
+    md
+}
+
+// ===PARSING===
+
+/// `ids` are the `^block-id`s seen so far, so a 2nd block w/ the same one 
+/// keeps it as text
+fn parse_blocks(lines: &[&str], profile: &SyntaxProfile, ids: &mut HashSet<String>) -> Vec<NodeTree> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        // branch blocks: gather all the lines with the same prefix and parse
+        // them as the branch's children
+        let quote_prefix = profile.blocks.iter()
+            .filter(|b| b.kind == PageNodeType::Quote)
+            .find(|b| line.starts_with(b.prefix.as_str()))
+            .map(|b| b.prefix.as_str());
+        let branch = if let Some(prefix) = quote_prefix {
+            Some((PageNodeType::Quote, prefix))
+        } else if line.starts_with('\t') {
+            Some((PageNodeType::Indent, "\t"))
+        } else { None };
+        if let Some((kind, prefix)) = branch {
+            let mut inner = Vec::new();
+            while i < lines.len() && lines[i].starts_with(prefix) {
+                let inner_line = &lines[i][prefix.len()..];
+                inner.push(if kind == PageNodeType::Quote {
+                    inner_line.strip_prefix(' ').unwrap_or(inner_line)
+                } else { inner_line });
+                i += 1;
+            }
+            blocks.push(NodeTree::new(kind, parse_blocks(&inner, profile, ids)));
+            continue;
+        }
+        if let Some(lang) = line.strip_prefix("```") {
+            let mut code = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].starts_with("```") {
+                code.push(lines[i]);
+                i += 1;
+            }
+            i += 1; // closing fence
+            let mut block = NodeTree::block(PageNodeType::CodeBlock, &code.join("\n"));
+            if !lang.trim().is_empty() {
+                block.content.insert("lang".into(), lang.trim().into());
+            }
+            blocks.push(block);
+            continue;
+        }
+        if is_divider(line) {
+            blocks.push(NodeTree::new(PageNodeType::Divider, Vec::new()));
+            i += 1;
+            continue;
+        }
+        // tables are kept as raw text for now
+        if line.starts_with('|') {
+            let mut rows = Vec::new();
+            while i < lines.len() && lines[i].starts_with('|') {
+                rows.push(lines[i]);
+                i += 1;
+            }
+            blocks.push(NodeTree::block(PageNodeType::Table, &rows.join("\n")));
+            continue;
+        }
+        i += 1;
+        if line.trim().is_empty() { continue }
+        blocks.push(parse_leaf_block(line, profile, ids));
+    }
+    blocks
+}
+
+fn parse_leaf_block(line: &str, profile: &SyntaxProfile, ids: &mut HashSet<String>) -> NodeTree {
+    let (line, id) = match split_block_id(line) {
+        (text, Some(id)) if ids.insert(id.to_string()) => (text, Some(id)),
+        _ => (line, None),
+    };
+    let mut checked = None;
+    let (kind, text) = match profile.block_prefix(line) {
+        // a list item that starts w/ a checkbox
+        Some((PageNodeType::Dot, text)) => match check_prefix(text) {
+            Some((is_checked, text)) => {
+                checked = Some(is_checked);
+                (PageNodeType::Check, text)
+            },
+            None => (PageNodeType::Dot, text),
+        },
+        // quotes are branches, so they're done in `parse_blocks`
+        Some((kind, text)) if kind != PageNodeType::Quote => (kind, text),
+        _ => match num_prefix(line) {
+            Some(text) => (PageNodeType::Num, text),
+            None => (PageNodeType::TextBlock, line),
+        },
+    };
+    let mut block = NodeTree::new(kind, parse_spans(text, profile));
+    if let Some(checked) = checked {
+        block.content.insert("checked".into(), checked.to_string());
+    }
+    if let Some(id) = id {
+        block.hash = id.to_string();
+        block.content.insert(PINNED.into(), String::new());
+    }
+    block
+}
+
+/// `---`, `***` or `___` (w/ any spaces between)
+fn is_divider(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| *c != ' ').collect();
+    chars.len() >= 3 && ['-', '*', '_'].iter().any(|d| chars.iter().all(|c| c == d))
+}
+
+/// `"[x] text"` -> `Some((true, "text"))`
+pub(crate) fn check_prefix(text: &str) -> Option<(bool, &str)> {
+    if let Some(text) = text.strip_prefix("[ ] ") {
+        return Some((false, text));
+    }
+    ["[x] ", "[X] "].iter().find_map(|p| text.strip_prefix(p)).map(|text| (true, text))
+}
+
+/// `"12. text"` -> `Some("text")`
+fn num_prefix(line: &str) -> Option<&str> {
+    let digits = line.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 { return None }
+    line[digits..].strip_prefix(". ")
+}
+
+/// parse the inline markdown of a block. always returns at least one node so
+/// the caret has somewhere to go
+pub(crate) fn parse_spans(text: &str, profile: &SyntaxProfile) -> Vec<NodeTree> {
+    let mut spans = Vec::new();
+    let mut raw = String::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap();
+        if c == '\\' {
+            if let Some(next) = rest[1..].chars().next().filter(|c| c.is_ascii_punctuation()) {
+                raw.push(next);
+                i += 1 + next.len_utf8();
+                continue;
+            }
+        }
+        if let Some((span, len)) = parse_span(text, i, profile) {
+            if !raw.is_empty() {
+                spans.push(NodeTree::text(&mem::take(&mut raw)));
+            }
+            spans.push(span);
+            i += len;
+            continue;
+        }
+        raw.push(c);
+        i += c.len_utf8();
+    }
+    if !raw.is_empty() || spans.is_empty() {
+        spans.push(NodeTree::text(&raw));
+    }
+    spans
+}
+
+/// try to parse a span starting at byte `i` of `text`. returns the span and
+/// how many bytes of `text` it covers
+pub(crate) fn parse_span(text: &str, i: usize, profile: &SyntaxProfile) -> Option<(NodeTree, usize)> {
+    let rest = &text[i..];
+    if let Some(inner) = rest.strip_prefix("[[") {
+        let end = inner.find("]]")?;
+        let inner = &inner[..end];
+        if inner.is_empty() { return None }
+        let (link, label) = inner.split_once('|').unwrap_or((inner, inner));
+        let mut span = NodeTree::new(PageNodeType::FileLink, vec![NodeTree::text(label)]);
+        span.content.insert("link".into(), link.into());
+        return Some((span, end + 4));
+    }
+    if rest.starts_with('[') {
+        let label_end = rest.find("](")?;
+        let url_len = rest[label_end + 2..].find(')')?;
+        let url = &rest[label_end + 2..label_end + 2 + url_len];
+        let mut span = NodeTree::new(PageNodeType::UrlLink, 
+            parse_spans(&rest[1..label_end], profile));
+        span.content.insert("url".into(), url.into());
+        return Some((span, label_end + 2 + url_len + 1));
+    }
+    for syntax in profile.spans_longest_first() {
+        let (delim, kind) = (syntax.delim.as_str(), syntax.kind.clone());
+        if !rest.starts_with(delim) { continue }
+        // e.g. `_` only counts at word boundaries so snake_case isn't italic
+        let intraword = |c: Option<char>| {
+            !is_intraword_delim(delim) && c.is_some_and(|c| c.is_alphanumeric())
+        };
+        if intraword(text[..i].chars().next_back()) { continue }
+        let after = &rest[delim.len()..];
+        let Some(mut close) = after.find(delim) else { continue };
+        // with a run like `***` the closing delim is the end of the run, so
+        // `**a *b***` is bold around italic
+        let first_char = delim.chars().next().unwrap();
+        while after[close + delim.len()..].starts_with(first_char) {
+            close += first_char.len_utf8();
+        }
+        let inner = &after[..close];
+        if inner.is_empty() || inner.starts_with(' ') || inner.ends_with(' ') { continue }
+        if intraword(after[close + delim.len()..].chars().next()) { continue }
+        let children = match kind {
+            PageNodeType::CodeInline => vec![NodeTree::text(inner)],
+            _ => parse_spans(inner, profile),
+        };
+        return Some((NodeTree::new(kind, children), delim.len() * 2 + close));
+    }
+    None
+}
+
+// ===WRITING===
+
+fn write_blocks(blocks: &[NodeTree], prefix: &str, profile: &SyntaxProfile, 
+    lines: &mut Vec<String>,
+) {
+    let mut num = 0;
+    for (i, block) in blocks.iter().enumerate() {
+        // paragraphs need a blank line between them or other editors will
+        // read them as one
+        if i > 0 && block.kind == PageNodeType::TextBlock
+            && blocks[i - 1].kind == PageNodeType::TextBlock {
+            lines.push(prefix.trim_end().to_string());
+        }
+        num = if block.kind == PageNodeType::Num { num + 1 } else { 0 };
+        match block.kind {
+            PageNodeType::Quote => write_blocks(&block.children, &format!("{}> ", prefix), 
+                profile, lines),
+            PageNodeType::Indent => write_blocks(&block.children, &format!("{}\t", prefix), 
+                profile, lines),
+            PageNodeType::CodeBlock => {
+                let lang = block.content.get("lang").map(|l| l.as_str()).unwrap_or("");
+                lines.push(format!("{}```{}", prefix, lang));
+                for line in block.plain_text().split('\n') {
+                    lines.push(format!("{}{}", prefix, line));
+                }
+                lines.push(format!("{}```", prefix));
+            },
+            PageNodeType::Divider => lines.push(format!("{}---", prefix)),
+            PageNodeType::Table => {
+                for line in block.plain_text().lines() {
+                    lines.push(format!("{}{}", prefix, line));
+                }
+            },
+            _ => {
+                let dot = profile.write_prefix(&PageNodeType::Dot);
+                let marker = match &block.kind {
+                    PageNodeType::Num => format!("{}. ", num),
+                    PageNodeType::Check => match block.content.get("checked").map(|c| c.as_str()) {
+                        Some("true") => format!("{} [x] ", dot),
+                        _ => format!("{} [ ] ", dot),
+                    },
+                    PageNodeType::TextBlock => String::new(),
+                    kind => format!("{} ", profile.write_prefix(kind)),
+                };
+                let mut text = write_spans(&block.children, profile);
+                if block.kind == PageNodeType::TextBlock {
+                    text = escape_block_prefix(text, profile);
+                }
+                let mut line = format!("{}{}{}", prefix, marker, text);
+                if block.content.contains_key(PINNED) {
+                    line = append_block_id(&line, &block.hash);
+                }
+                lines.push(line);
+            },
+        }
+    }
+}
+
+fn write_spans(spans: &[NodeTree], profile: &SyntaxProfile) -> String {
+    spans.iter().map(|span| match &span.kind {
+        PageNodeType::RawText => {
+            escape(span.content.get("text").map(|t| t.as_str()).unwrap_or(""), profile)
+        },
+        kind @ (PageNodeType::Bold | PageNodeType::Italic | PageNodeType::Highlight) => {
+            let delim = profile.write_delim(kind);
+            format!("{}{}{}", delim, write_spans(&span.children, profile), delim)
+        },
+        PageNodeType::CodeInline => format!("`{}`", span.plain_text()),
+        PageNodeType::FileLink => {
+            let label = span.plain_text();
+            // no link means it goes to whatever the label says
+            let link = span.content.get("link").filter(|l| !l.is_empty())
+                .cloned().unwrap_or_else(|| label.clone());
+            if label == link { format!("[[{}]]", link) } else { format!("[[{}|{}]]", link, label) }
+        },
+        PageNodeType::UrlLink => format!("[{}]({})",
+            write_spans(&span.children, profile),
+            span.content.get("url").map(|u| u.as_str()).unwrap_or("")),
+        _ => write_spans(&span.children, profile),
+    }).collect()
+}
+
+/// backslash anything that would otherwise be read back as a span. this 
+/// includes the user's own delimiters, so the text is still text when they 
+/// open the file again. the same goes for text ending in a `^block-id`
+fn escape(text: &str, profile: &SyntaxProfile) -> String {
+    let extra = profile.extra_delim_chars();
+    let mut escaped = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    let mut prev = None;
+    while let Some((i, c)) = chars.next() {
+        let is_id = c == '^' && matches!(prev, None | Some(' ')) && is_block_id(&text[i..]);
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') || extra.contains(&c) || is_id
+            || (c == '=' && chars.peek().map(|(_, c)| c) == Some(&'=')) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        prev = Some(c);
+    }
+    escaped
+}
+
+/// backslash the start of a paragraph that would otherwise be read back as
+/// a heading, list item, etc
+fn escape_block_prefix(text: String, profile: &SyntaxProfile) -> String {
+    if text.starts_with(['#', '-', '+', '>', '|']) || text.starts_with("```") {
+        return format!("\\{}", text);
+    }
+    // the user's own prefixes. only ones starting w/ punctuation can be 
+    // escaped and still be standard markdown
+    let is_user_prefix = profile.block_prefix(&text).is_some()
+        && text.starts_with(|c: char| c.is_ascii_punctuation());
+    if is_user_prefix {
+        return format!("\\{}", text);
+    }
+    let digits = text.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits > 0 && text[digits..].starts_with(". ") {
+        return format!("{}\\{}", &text[..digits], &text[digits..]);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ROOT_HASH, BlockSyntax, SpanSyntax};
+    use PageNodeType::*;
+
+    fn kinds(nodes: &[NodeTree]) -> Vec<PageNodeType> {
+        nodes.iter().map(|n| n.kind.clone()).collect()
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let page = parse_markdown("# title\n\npara\n- dot\n- [x] done\n2. num\n> quoted\n> > nested\n\tindented\n```rs\nlet x;\n```\n| a | b |\n| - | - |");
+        assert_eq!(kinds(&page.children), vec![H1, TextBlock, Dot, Check, Num, Quote, Indent, CodeBlock, Table]);
+        assert_eq!(page.children[3].content.get("checked").unwrap(), "true");
+        let quote = &page.children[5];
+        assert_eq!(kinds(&quote.children), vec![TextBlock, Quote]);
+        assert_eq!(quote.children[1].children[0].plain_text(), "nested");
+        assert_eq!(page.children[7].plain_text(), "let x;");
+        assert_eq!(page.children[7].content.get("lang").unwrap(), "rs");
+    }
+
+    #[test]
+    fn parses_spans() {
+        let page = parse_markdown("a **b *c*** ==d== `e*` [[f|g]] [h](i) snake_case \\*j");
+        let spans = &page.children[0].children;
+        assert_eq!(kinds(spans), vec![
+            RawText, Bold, RawText, Highlight, RawText, CodeInline, RawText,
+            FileLink, RawText, UrlLink, RawText,
+        ]);
+        assert_eq!(kinds(&spans[1].children), vec![RawText, Italic]);
+        assert_eq!(spans[5].plain_text(), "e*");
+        assert_eq!(spans[7].content.get("link").unwrap(), "f");
+        assert_eq!(spans[7].plain_text(), "g");
+        assert_eq!(spans[9].content.get("url").unwrap(), "i");
+        assert_eq!(spans[10].plain_text(), " snake_case *j");
+    }
+
+    #[test]
+    fn block_ids_are_kept() {
+        let doc = Doc::from_markdown("linked ^id-ab12\n\nnot linked\n", 1);
+        let blocks = doc.leaf_blocks();
+        assert_eq!(blocks[0], "ab12");
+        assert!(doc.content("ab12", PINNED).is_some());
+        assert_eq!(doc.plain_text(&blocks[0]), "linked");
+        assert_eq!(doc.to_markdown(), "linked ^id-ab12\n\nnot linked\n");
+    }
+
+    #[test]
+    fn carets_that_arent_block_ids_are_text() {
+        let md = "text ^2\n\nx ^todo\n- ^id\n";
+        let doc = Doc::from_markdown(md, 1);
+        assert_eq!(doc.plain_text(&doc.leaf_blocks()[0]), "text ^2");
+        assert!(doc.leaf_blocks().iter().all(|b| doc.content(b, PINNED).is_none()));
+        assert_eq!(doc.to_markdown(), md);
+        // text that does look like one is escaped, so it's still text
+        let page = NodeTree::new(Page, vec![NodeTree::block(TextBlock, "a ^id-ab12")]);
+        let md = tree_to_markdown(&page);
+        assert_eq!(md, "a \\^id-ab12\n");
+        let doc = Doc::from_markdown(&md, 1);
+        assert_eq!(doc.plain_text(&doc.leaf_blocks()[0]), "a ^id-ab12");
+        assert_eq!(doc.to_markdown(), md);
+    }
+
+    #[test]
+    fn duplicate_block_ids_stay_text() {
+        let doc = Doc::from_markdown("a ^id-ab12\n> b ^id-ab12\n", 1);
+        let blocks = doc.leaf_blocks();
+        assert_eq!(blocks[0], "ab12");
+        assert_ne!(blocks[1], "ab12");
+        assert!(doc.content(&blocks[1], PINNED).is_none());
+        assert_eq!(doc.plain_text(&blocks[1]), "b ^id-ab12");
+        let md = doc.to_markdown();
+        assert_eq!(md, "a ^id-ab12\n> b \\^id-ab12\n");
+        assert_eq!(Doc::from_markdown(&md, 1).to_markdown(), md);
+    }
+
+    #[test]
+    fn reopening_keeps_block_ids() {
+        let md = "a ^id-ab12\n\nb\n";
+        let mut doc = Doc::from_markdown(md, 1);
+        doc.apply(&doc.replace_page(parse_markdown(md))).unwrap();
+        assert_eq!(doc.leaf_blocks()[0], "ab12");
+        assert_eq!(doc.to_markdown(), md);
+        // a copy can't have the same hash, so it isn't saved w/ an id at all
+        let tx = doc.duplicate_blocks(&["ab12".to_string()]).unwrap();
+        doc.apply(&tx).unwrap();
+        assert_eq!(doc.to_markdown(), "a ^id-ab12\n\na\n\nb\n");
+    }
+
+    #[test]
+    fn house_style_saves_as_standard() {
+        let profile = SyntaxProfile::with_user(&SyntaxProfile {
+            spans: vec![
+                SpanSyntax { delim: "/".into(), kind: Italic },
+                SpanSyntax { delim: "_".into(), kind: Italic },
+            ],
+            blocks: vec![BlockSyntax { prefix: "+".into(), kind: Dot }],
+        });
+        let doc = Doc::from_markdown_with("+ /a/ *b* a/b/c\n+ [x] done\n", 1, &profile);
+        let blocks = doc.leaf_blocks();
+        assert_eq!(*doc.kind(&blocks[0]), Dot);
+        assert_eq!(*doc.kind(&blocks[1]), Check);
+        let spans = doc.children(&blocks[0]);
+        assert_eq!(*doc.kind(&spans[0]), Italic);
+        assert_eq!(*doc.kind(&spans[2]), Italic);
+        assert_eq!(doc.plain_text(&spans[3]), " a/b/c");
+        let md = doc.to_markdown_with(&profile);
+        assert_eq!(md, "+ _a_ _b_ a\\/b\\/c\n+ [x] done\n");
+        // other editors read it the same, and so does the profile
+        assert_eq!(Doc::from_markdown_with(&md, 1, &profile).to_markdown_with(&profile), md);
+        assert_eq!(parse_markdown(&md).children[0].plain_text(), "a b a/b/c");
+    }
+
+    #[test]
+    fn round_trips() {
+        let md = "# title\n\
+            some **bold *and italic*** text\n\
+            \n\
+            \\# not a heading \\*or italic\\*\n\
+            - dot [[page|alias]]\n\
+            - [ ] todo\n\
+            1. one\n\
+            2. two\n\
+            > quote\n\
+            > > nested\n\
+            \tindented `code`\n\
+            ---\n\
+            ```\n\
+            fn main() {}\n\
+            ```\n\
+            | a | b |\n";
+        let doc = Doc::from_markdown(md, 1);
+        assert_eq!(doc.to_markdown(), md);
+        assert_eq!(parse_markdown(&doc.to_markdown()).children.len(), doc.children(ROOT_HASH).len());
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PageNodeType {
+    // root
+    Page, // using Page as root so able to attach a method to index node (as 
+          // otherwise you would have to first index the root RwSignal<Vec<PageNode>> 
+          // before runniing PageNode index method)
+          //
+          // also it allows you to insert root level elements without needing 
+          // to have access to elem_ref, nor account for the spacers at top 
+          // and bottom
+    // block-branch
+    Indent, Quote, 
+    // block-leaf
+    TextBlock, H1, H2, H3, H4, H5, CodeBlock, Dot, Num, Check, Table, // tbh table could be a branch too ???
+    Divider, // no text
+    // text-branch
+    Bold, Italic, Highlight, CodeInline, FileLink, UrlLink,
+    // text-leaf
+    RawText,
+}
+#[derive(Clone, Copy)]
+struct PageNodeTypeInfo {
+    val: &'static str,
+    /// true = block, false = span
+    block: bool,
+    /// true = branch, false = leaf
+    branch: bool,
+    innate_height: u32,
+}
+const NUM_TYPES: usize = 22;
+const PAGE_NODE_TYPES: [PageNodeType; NUM_TYPES] = [
+    PageNodeType::Page,
+    PageNodeType::Indent, PageNodeType::Quote,
+    PageNodeType::TextBlock, PageNodeType::H1, PageNodeType::H2, 
+    PageNodeType::H3, PageNodeType::H4, PageNodeType::H5, 
+    PageNodeType::CodeBlock, PageNodeType::Dot, PageNodeType::Num, 
+    PageNodeType::Check, PageNodeType::Table, PageNodeType::Divider,
+    PageNodeType::Bold, PageNodeType::Italic, PageNodeType::Highlight, 
+    PageNodeType::CodeInline, PageNodeType::FileLink, PageNodeType::UrlLink,
+    PageNodeType::RawText,
+];
+// NOTE: THIS MUST BE IN SAME ORDER AS THE ENUM  FOR THE INDEXING TO WORK
+const PAGE_NODE_TYPE_INFO: [PageNodeTypeInfo; NUM_TYPES] = [
+    // Page
+    PageNodeTypeInfo { val: "p", block: true, branch: true, innate_height: 0 },
+
+    // Indent
+    PageNodeTypeInfo { val: "in", block: true, branch: true, innate_height: 0 },
+    // Quote
+    PageNodeTypeInfo { val: "q", block: true, branch: true, innate_height: 0 },
+
+    // TextBlock
+    PageNodeTypeInfo { val: "tb", block: true, branch: false, innate_height: 0 },
+    // H1
+    PageNodeTypeInfo { val: "h1", block: true, branch: false, innate_height: 0 },
+    // H2
+    PageNodeTypeInfo { val: "h2", block: true, branch: false, innate_height: 0 },
+    // H3
+    PageNodeTypeInfo { val: "h3", block: true, branch: false, innate_height: 0 },
+    // H4
+    PageNodeTypeInfo { val: "h4", block: true, branch: false, innate_height: 0 },
+    // H5
+    PageNodeTypeInfo { val: "h5", block: true, branch: false, innate_height: 0 },
+    // CodeBlock
+    PageNodeTypeInfo { val: "cd", block: true, branch: false, innate_height: 0 },
+    // Dot
+    PageNodeTypeInfo { val: "d", block: true, branch: false, innate_height: 0 },
+    // Num
+    PageNodeTypeInfo { val: "n", block: true, branch: false, innate_height: 0 },
+    // Check
+    PageNodeTypeInfo { val: "ch", block: true, branch: false, innate_height: 0 },
+    // Table
+    PageNodeTypeInfo { val: "tl", block: true, branch: false, innate_height: 0 },
+    // Divider
+    PageNodeTypeInfo { val: "dv", block: true, branch: false, innate_height: 0 },
+
+    // Bold
+    PageNodeTypeInfo { val: "b", block: false, branch: true, innate_height: 0 },
+    // Italic
+    PageNodeTypeInfo { val: "i", block: false, branch: true, innate_height: 0 },
+    // Highlight
+    PageNodeTypeInfo { val: "h", block: false, branch: true, innate_height: 0 },
+    // CodeInline
+    PageNodeTypeInfo { val: "ci", block: false, branch: true, innate_height: 0 },
+    // FileLink
+    PageNodeTypeInfo { val: "fl", block: false, branch: true, innate_height: 0 },
+    // UrlLink
+    PageNodeTypeInfo { val: "ul", block: false, branch: true, innate_height: 0 },
+
+    // RawText
+    PageNodeTypeInfo { val: "t", block: false, branch: false, innate_height: 0 },
+];
+
+impl PageNodeType {
+    pub fn value(&self) -> &str {
+        PAGE_NODE_TYPE_INFO[self.clone() as usize].val
+    }
+    pub fn is_block(&self) -> bool {
+        PAGE_NODE_TYPE_INFO[self.clone() as usize].block
+    }
+    pub fn is_branch(&self) -> bool {
+        PAGE_NODE_TYPE_INFO[self.clone() as usize].branch
+    }
+    /// get the height of the node without any contents (e.g. if the quote 
+    /// block had top/bottom padding, get the sum height of the padding)
+    pub fn innate_height(&self) -> u32 {
+        PAGE_NODE_TYPE_INFO[self.clone() as usize].innate_height
+    }
+    /// get the type from its DOM `type` attribute value
+    pub fn from_value(val: &str) -> Option<Self> {
+        PAGE_NODE_TYPES.iter().find(|kind| kind.value() == val).cloned()
+    }
+    /// `Some(1)` for `H1`, `Some(2)` for `H2`, etc
+    pub fn heading_level(&self) -> Option<usize> {
+        match self {
+            PageNodeType::H1 => Some(1),
+            PageNodeType::H2 => Some(2),
+            PageNodeType::H3 => Some(3),
+            PageNodeType::H4 => Some(4),
+            PageNodeType::H5 => Some(5),
+            _ => None,
+        }
+    }
+    pub fn heading(level: usize) -> Option<Self> {
+        PAGE_NODE_TYPES.iter().find(|kind| kind.heading_level() == Some(level)).cloned()
+    }
+    pub fn is_leaf_block(&self) -> bool {
+        self.is_block() && !self.is_branch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_table_matches_enum_order() {
+        for kind in PAGE_NODE_TYPES {
+            assert_eq!(PageNodeType::from_value(kind.value()), Some(kind));
+        }
+        assert_eq!(PageNodeType::from_value("nope"), None);
+    }
+
+    #[test]
+    fn headings() {
+        assert_eq!(PageNodeType::H3.heading_level(), Some(3));
+        assert_eq!(PageNodeType::heading(5), Some(PageNodeType::H5));
+        assert_eq!(PageNodeType::heading(6), None);
+        assert_eq!(PageNodeType::TextBlock.heading_level(), None);
+    }
+}
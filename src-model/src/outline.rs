@@ -0,0 +1,138 @@
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, Op, Transaction, ROOT_HASH};
+
+// the outline: the page's headings, for the sidebar. a heading's section is
+// the heading plus the blocks under it, same as what folding it hides (see
+// `Doc::fold_hides`)
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineEntry {
+    pub hash: String,
+    pub level: usize,
+    /// how far in to show it. not always `level - 1`, bc a heading under one
+    /// that's 2 levels bigger (e.g. an H3 under an H1) only goes in 1 step
+    pub depth: usize,
+    pub text: String,
+}
+
+impl Doc {
+    /// the headings in the page, in order
+    pub fn outline(&self) -> Vec<OutlineEntry> {
+        // the levels of the headings the next one could be under
+        let mut parents: Vec<usize> = Vec::new();
+        self.leaf_blocks().into_iter().filter_map(|hash| {
+            let level = self.kind(&hash).heading_level()?;
+            while parents.last().is_some_and(|l| *l >= level) {
+                parents.pop();
+            }
+            let depth = parents.len();
+            parents.push(level);
+            let text = self.plain_text(&hash);
+            Some(OutlineEntry { hash, level, depth, text })
+        }).collect()
+    }
+    /// the heading of the section `block` is in, i.e. the last heading at or
+    /// above it
+    pub fn current_heading(&self, block: &str) -> Option<String> {
+        let mut block = self.first_leaf_block(block)?;
+        loop {
+            if self.kind(&block).heading_level().is_some() { return Some(block) }
+            block = self.prev_leaf_block(&block)?;
+        }
+    }
+    /// the heading and the blocks under it
+    pub fn section(&self, heading: &str) -> Vec<String> {
+        let mut section = vec![heading.to_string()];
+        section.extend(self.fold_hides(heading));
+        section
+    }
+    /// move the heading's section to just before the `before` heading, or
+    /// the end of the page. `None` if that's where it already is, or inside
+    /// itself
+    pub fn move_section(&self, heading: &str, before: Option<&str>) -> Option<Transaction> {
+        let section = self.section(heading);
+        let (parent, index) = match before {
+            Some(before) => {
+                if section.iter().any(|b| b == before || self.is_ancestor(b, before)) { return None }
+                let parent = self.parent(before)?.to_string();
+                // (the index doesn't count the moved blocks)
+                let index = self.children(&parent).iter()
+                    .take_while(|c| *c != before)
+                    .filter(|c| !section.contains(c))
+                    .count();
+                (parent, index)
+            },
+            None => (ROOT_HASH.to_string(), usize::MAX),
+        };
+        // already there
+        let after_section = self.next_sibling(section.last()?);
+        let in_place = match before {
+            Some(before) => after_section.as_deref() == Some(before),
+            None => after_section.is_none() && self.parent(heading) == Some(ROOT_HASH),
+        };
+        if in_place { return None }
+        // a heading in a quote has a section that can go out of it, so the
+        // quote can be left w/ nothing in it
+        let mut emptied = Vec::new();
+        for block in &section {
+            let mut top = None;
+            let mut branch = self.parent(block)?;
+            while branch != ROOT_HASH && branch != parent && !self.is_ancestor(branch, &parent)
+                && self.children(branch).iter().all(|c| section.contains(c) || top.as_ref() == Some(c)) {
+                top = Some(branch.to_string());
+                branch = self.parent(branch)?;
+            }
+            if let Some(top) = top.filter(|t| !emptied.contains(t)) {
+                emptied.push(top);
+            }
+        }
+        let mut tx = Transaction::from(Op::MoveNodes { hashes: section, parent, index });
+        if !emptied.is_empty() {
+            tx.push(Op::RemoveNodes { hashes: emptied });
+        }
+        Some(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(doc: &Doc) -> Vec<String> {
+        doc.leaf_blocks().iter().map(|b| doc.plain_text(b)).collect()
+    }
+
+    #[test]
+    fn outline_depths() {
+        let doc = Doc::from_markdown("# a\n### b\n## c\ntext\n# d\n> ## e\n", 1);
+        let outline: Vec<_> = doc.outline().into_iter().map(|e| (e.text, e.depth)).collect();
+        assert_eq!(outline, vec![("a".into(), 0), ("b".into(), 1), ("c".into(), 1),
+            ("d".into(), 0), ("e".into(), 1)]);
+        let blocks = doc.leaf_blocks();
+        assert_eq!(doc.current_heading(&blocks[3]), Some(blocks[2].clone()));
+        assert_eq!(doc.current_heading(&blocks[0]), Some(blocks[0].clone()));
+        assert_eq!(Doc::from_markdown("no headings\n", 1).outline(), vec![]);
+    }
+
+    #[test]
+    fn moves_sections() {
+        let mut doc = Doc::from_markdown("# a\none\n# b\ntwo\n## c\nthree\n", 1);
+        let blocks = doc.leaf_blocks();
+        let (a, b, c) = (blocks[0].clone(), blocks[2].clone(), blocks[4].clone());
+        assert!(doc.move_section(&a, Some(&b)).is_none());
+        assert!(doc.move_section(&b, Some(&c)).is_none());
+        doc.apply(&doc.move_section(&b, Some(&a)).unwrap()).unwrap();
+        assert_eq!(texts(&doc), vec!["b", "two", "c", "three", "a", "one"]);
+        assert!(doc.move_section(&a, None).is_none());
+        doc.apply(&doc.move_section(&c, None).unwrap()).unwrap();
+        assert_eq!(texts(&doc), vec!["b", "two", "a", "one", "c", "three"]);
+
+        // the section goes out of the quote, which is left empty so it goes
+        let mut doc = Doc::from_markdown("# z\n> # a\n> b\n\nc\n# d\n", 1);
+        let a = doc.leaf_blocks()[1].clone();
+        doc.apply(&doc.move_section(&a, None).unwrap()).unwrap();
+        assert_eq!(texts(&doc), vec!["z", "d", "a", "b", "c"]);
+        assert_eq!(doc.children(ROOT_HASH).len(), 5);
+    }
+}
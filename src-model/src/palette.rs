@@ -0,0 +1,105 @@
+use super::{Command, Keymap};
+
+// the command palette: every command, found by typing a few letters of its
+// name. recently used commands come first
+
+/// a row in the palette
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub command: Command,
+    pub title: &'static str,
+    /// the shortcuts that run it, e.g. "Ctrl-Shift-P"
+    pub chords: Vec<String>,
+}
+
+/// how many recent commands are remembered
+pub const MAX_RECENT: usize = 10;
+
+/// how well `query` matches `text`, or `None` if its letters don't all
+/// appear in `text` in order. letters at the start of a word and runs of
+/// letters in a row score higher, so "tog h" finds "Toggle heading" first
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut i = 0;
+    let mut prev_match = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (i..text.len()).find(|&j| text[j] == q)?;
+        let word_start = found == 0 || !text[found - 1].is_alphanumeric();
+        score += 1 + if word_start { 4 } else { 0 }
+            + if prev_match == Some(found.wrapping_sub(1)) { 2 } else { 0 };
+        prev_match = Some(found);
+        i = found + 1;
+    }
+    Some(score)
+}
+
+/// the commands matching `query`. `recent` is most recent first. the recent
+/// ones that match come first (most recent first), then the rest best match
+/// first. w/ an empty query it's the recent commands then the rest in order
+pub fn palette_entries(keymap: &Keymap, query: &str, recent: &[Command]) -> Vec<PaletteEntry> {
+    let recency = |command: &Command| recent.iter().position(|r| r == command).unwrap_or(usize::MAX);
+    let mut found: Vec<(u32, usize, PaletteEntry)> = Command::ALL.iter().filter_map(|command| {
+        let score = fuzzy_score(query, command.title())?;
+        let chords = keymap.chords(*command).iter().map(|c| c.to_string()).collect();
+        Some((score, recency(command), PaletteEntry { command: *command, title: command.title(), chords }))
+    }).collect();
+    // stable, so ties stay in `Command::ALL` order
+    // (the ones that aren't recent all have `usize::MAX`, so they go by score)
+    found.sort_by_key(|(score, recency, _)| (*recency, std::cmp::Reverse(*score)));
+    found.into_iter().map(|(_, _, entry)| entry).collect()
+}
+
+/// remember that `command` was just run
+pub fn push_recent(recent: &mut Vec<Command>, command: Command) {
+    recent.retain(|r| *r != command);
+    recent.insert(0, command);
+    recent.truncate(MAX_RECENT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Platform;
+
+    fn titles(entries: &[PaletteEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.title).collect()
+    }
+
+    #[test]
+    fn fuzzy_matches() {
+        assert!(fuzzy_score("tgh", "Toggle heading").is_some());
+        assert!(fuzzy_score("hx", "Toggle heading").is_none());
+        // word starts beat letters in the middle of words
+        assert!(fuzzy_score("th", "Toggle heading") > fuzzy_score("th", "Go to start of page"));
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn ranks_recent_first() {
+        let keymap = Keymap::defaults(Platform::Other);
+        let mut recent = Vec::new();
+        push_recent(&mut recent, Command::InsertTable);
+        push_recent(&mut recent, Command::OpenFile);
+        push_recent(&mut recent, Command::InsertTable);
+        assert_eq!(recent, vec![Command::InsertTable, Command::OpenFile]);
+
+        let all = palette_entries(&keymap, "", &recent);
+        assert_eq!(all.len(), Command::ALL.len());
+        assert_eq!(titles(&all[..3]), vec!["Insert table", "Open file", "Go to start of page"]);
+        let palette = all.iter().find(|e| e.command == Command::CommandPalette).unwrap();
+        assert_eq!(palette.chords, vec!["Ctrl-Shift-P"]);
+
+        assert_eq!(titles(&palette_entries(&keymap, "heading", &recent)), vec!["Toggle heading"]);
+        // every "page" command matches as well, so the recent one wins the tie
+        let page = palette_entries(&keymap, "page", &[Command::PageDown]);
+        assert_eq!(page[0].title, "Page down");
+        assert_eq!(page.len(), 4);
+        assert!(palette_entries(&keymap, "zzz", &recent).is_empty());
+        // recent beats a better match: the `p` in "Export" is mid-word, the
+        // one in "Page up" isn't
+        assert!(fuzzy_score("p", "Export as markdown") < fuzzy_score("p", "Page up"));
+        let p = palette_entries(&keymap, "p", &[Command::PageDown, Command::ExportMarkdown]);
+        assert_eq!(titles(&p[..3]), vec!["Page down", "Export as markdown", "Go to start of page"]);
+    }
+}
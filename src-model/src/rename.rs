@@ -0,0 +1,200 @@
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+
+use super::{NoteInfo, LinkTarget, note_title, resolve_link, resolve_url, same_path};
+
+// renaming/moving a note, and every link to it w/ it. the files are done by
+// the backend (see `rename.rs` there), this is the text part. it goes
+// through the markdown itself rather than parsing and writing it again, so
+// nothing but the links changes. a `[[note]]` that could be to more than one
+// note (two notes w/ the same title) is left for the user, and reported
+
+/// move the note at `from` to `to`. all 3 are full paths
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteRename {
+    /// the workspace folder
+    pub root: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// a link that might be to the renamed note, but might not
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmbiguousLink {
+    pub path: String,
+    /// counting from 1
+    pub line: usize,
+    pub link: String,
+}
+
+/// what a rename did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameReport {
+    /// the files where links were changed (w/ the renamed note at its new
+    /// path, if its own links changed)
+    pub changed: Vec<String>,
+    pub ambiguous: Vec<AmbiguousLink>,
+}
+
+/// the path from the folder the note at `from` is in to `to`, w/ `/`s
+fn relative_url(from: &str, to: &str) -> String {
+    let (from, to) = (from.replace('\\', "/"), to.replace('\\', "/"));
+    let mut from: Vec<&str> = from.split('/').collect();
+    from.pop();
+    let to: Vec<&str> = to.split('/').collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+impl NoteRename {
+    /// where the note at `path` is once the rename's done
+    pub fn moved(&self, path: &str) -> String {
+        match same_path(path, &self.from) {
+            true => self.to.clone(),
+            false => path.to_string(),
+        }
+    }
+    /// the note's path from the workspace folder, w/o `.md`, for a
+    /// `[[path/to/note]]`
+    fn link_path(&self) -> String {
+        let root = self.root.replace('\\', "/");
+        let to = self.to.replace('\\', "/");
+        let path = to.strip_prefix(&root).unwrap_or(&to).trim_start_matches('/');
+        path.strip_suffix(".md").unwrap_or(path).to_string()
+    }
+    /// the text of the note at `path` w/ its links to the renamed note
+    /// changed, and the links it couldn't be sure about. `notes` are all the
+    /// notes as they are before the rename. the renamed note's own relative
+    /// links are changed too if it's moving to another folder
+    pub fn rewrite_links(&self, notes: &[NoteInfo], path: &str, text: &str) -> (String, Vec<AmbiguousLink>) {
+        let wikilink = Regex::new(r"\[\[([^\]\n]+)\]\]").unwrap();
+        let url_link = Regex::new(r"\[([^\]\n]*)\]\(([^)\s]+)\)").unwrap();
+        let mut ambiguous = Vec::new();
+        let mut new_text = String::with_capacity(text.len());
+        let mut in_code = false;
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+            }
+            if in_code {
+                new_text.push_str(line);
+                continue;
+            }
+            let line = wikilink.replace_all(line, |caps: &Captures| {
+                let all = caps.get(0).unwrap();
+                let escaped = line[..all.start()].ends_with('\\');
+                let new = (!escaped).then(|| self.rewrite_wikilink(notes, &caps[1])).flatten();
+                match new {
+                    Some(Ok(new)) => format!("[[{}]]", new),
+                    Some(Err(())) => {
+                        ambiguous.push(AmbiguousLink { path: path.into(), line: i + 1, link: all.as_str().into() });
+                        all.as_str().to_string()
+                    },
+                    None => all.as_str().to_string(),
+                }
+            });
+            let line = url_link.replace_all(&line, |caps: &Captures| {
+                let all = caps.get(0).unwrap().as_str();
+                // `\[a\](b)` is just text
+                if caps[1].ends_with('\\') || all.starts_with("[[") { return all.to_string() }
+                match self.rewrite_url(path, &caps[2]) {
+                    Some(url) => format!("[{}]({})", &caps[1], url),
+                    None => all.to_string(),
+                }
+            });
+            new_text.push_str(&line);
+        }
+        (new_text, ambiguous)
+    }
+    /// what goes in the `[[...]]` now. `None` if it isn't to the renamed
+    /// note, `Err` if it might be
+    fn rewrite_wikilink(&self, notes: &[NoteInfo], inner: &str) -> Option<Result<String, ()>> {
+        let (link, label) = match inner.split_once('|') {
+            Some((link, label)) => (link, Some(label)),
+            None => (inner, None),
+        };
+        let target = LinkTarget::parse(link);
+        if target.note.is_empty() { return None }
+        let is_path = target.note.contains(['/', '\\']);
+        if !is_path {
+            let same_title: Vec<&NoteInfo> = notes.iter()
+                .filter(|n| n.title.to_lowercase() == target.note.to_lowercase())
+                .collect();
+            let to_renamed = same_title.iter().any(|n| same_path(&n.path, &self.from));
+            if !to_renamed { return None }
+            if same_title.len() > 1 { return Some(Err(())) }
+        } else if !resolve_link(notes, link).is_some_and(|n| same_path(&n.path, &self.from)) {
+            return None
+        }
+        // the new title, unless another note already has it
+        let title = note_title(&self.to);
+        let taken = notes.iter().any(|n| {
+            !same_path(&n.path, &self.from) && n.title.to_lowercase() == title.to_lowercase()
+        });
+        let mut new = if is_path || taken { self.link_path() } else { title };
+        if let Some(heading) = &target.heading {
+            new = format!("{}#{}", new, heading);
+        }
+        if let Some(label) = label {
+            new = format!("{}|{}", new, label);
+        }
+        Some(Ok(new))
+    }
+    /// the url a markdown link in the note at `path` should have now, if
+    /// it's changed
+    fn rewrite_url(&self, path: &str, url: &str) -> Option<String> {
+        let target = resolve_url(path, url)?;
+        let (new_path, new_target) = (self.moved(path), self.moved(&target));
+        if resolve_url(&new_path, url).is_some_and(|t| same_path(&t, &new_target)) { return None }
+        let mut new = relative_url(&new_path, &new_target);
+        if url.contains("%20") {
+            new = new.replace(' ', "%20");
+        }
+        if let Some(i) = url.find('#') {
+            new.push_str(&url[i..]);
+        }
+        Some(new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(path: &str) -> NoteInfo {
+        NoteInfo::new(path, "")
+    }
+
+    #[test]
+    fn rewrites_links() {
+        let notes = vec![note("/w/Ideas.md"), note("/w/a.md"), note("/w/sub/b.md"), note("/w/old/Todo.md"),
+            note("/w/Todo.md")];
+        let rename = NoteRename { root: "/w".into(), from: "/w/Ideas.md".into(), to: "/w/sub/Plans.md".into() };
+        let text = "see [[Ideas]], [[ideas#Later|later]] and [[Ideaslist]]\n\
+            [it](Ideas.md#top) [site](https://x.md) \\[[Ideas]]\n\
+            ```\n[[Ideas]]\n```\n";
+        let (new, ambiguous) = rename.rewrite_links(&notes, "/w/a.md", text);
+        assert_eq!(new, "see [[Plans]], [[Plans#Later|later]] and [[Ideaslist]]\n\
+            [it](sub/Plans.md#top) [site](https://x.md) \\[[Ideas]]\n\
+            ```\n[[Ideas]]\n```\n");
+        assert!(ambiguous.is_empty());
+        // from a note in another folder
+        let (new, _) = rename.rewrite_links(&notes, "/w/sub/b.md", "[x](../Ideas.md) [y](../a.md)\r\n");
+        assert_eq!(new, "[x](Plans.md) [y](../a.md)\r\n");
+        // the renamed note's own links, from its new folder
+        let (new, _) = rename.rewrite_links(&notes, "/w/Ideas.md", "[a](a.md) [b](sub/b.md) [[a]]");
+        assert_eq!(new, "[a](../a.md) [b](b.md) [[a]]");
+
+        // 2 notes called `Todo`, so `[[Todo]]` could be either
+        let rename = NoteRename { root: "/w".into(), from: "/w/old/Todo.md".into(), to: "/w/old/Done.md".into() };
+        let (new, ambiguous) = rename.rewrite_links(&notes, "/w/a.md", "x\n[[Todo]] [[old/Todo]]\n");
+        assert_eq!(new, "x\n[[Todo]] [[old/Done]]\n");
+        assert_eq!(ambiguous, vec![AmbiguousLink { path: "/w/a.md".into(), line: 2, link: "[[Todo]]".into() }]);
+        // a title that's taken goes in as a path
+        let rename = NoteRename { root: "/w".into(), from: "/w/Ideas.md".into(), to: "/w/sub/a.md".into() };
+        let (new, _) = rename.rewrite_links(&notes, "/w/Todo.md", "[[Ideas]]");
+        assert_eq!(new, "[[sub/a]]");
+    }
+}
@@ -0,0 +1,174 @@
+use std::cmp::Ordering;
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Doc, Pos};
+use super::edit::is_word;
+
+/// a selection in the doc. kept in doc positions rather than DOM ones so it
+/// still exists when the blocks it's in aren't rendered
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Selection {
+    /// where the selection started
+    pub anchor: Pos,
+    /// where the caret is. can be before `anchor` if selecting backwards
+    pub head: Pos,
+}
+impl Selection {
+    pub fn new(anchor: Pos, head: Pos) -> Self {
+        Self { anchor, head }
+    }
+    /// just a caret
+    pub fn caret(pos: Pos) -> Self {
+        Self { anchor: pos.clone(), head: pos }
+    }
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.head
+    }
+}
+
+impl Doc {
+    /// which of two positions comes first in the doc
+    pub fn cmp_pos(&self, a: &Pos, b: &Pos) -> Ordering {
+        if a.hash == b.hash { return a.offset.cmp(&b.offset) }
+        self.path(&a.hash).cmp(&self.path(&b.hash))
+            .then(a.offset.cmp(&b.offset))
+    }
+    /// index of the node in its parent, the parent in its parent, etc, from
+    /// the root down. comparing paths compares where the nodes are in the doc
+    pub(crate) fn path(&self, hash: &str) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut hash = hash;
+        while let Some(idx) = self.index_in_parent(hash) {
+            path.push(idx);
+            hash = self.parent(hash).unwrap();
+        }
+        path.reverse();
+        path
+    }
+    /// the start and end of the selection, in doc order
+    pub fn selection_range(&self, sel: &Selection) -> (Pos, Pos) {
+        match self.cmp_pos(&sel.anchor, &sel.head) {
+            Ordering::Greater => (sel.head.clone(), sel.anchor.clone()),
+            _ => (sel.anchor.clone(), sel.head.clone()),
+        }
+    }
+    /// the whole doc. `None` if there's no text to select
+    pub fn select_all(&self) -> Option<Selection> {
+        // dividers have no text to put the ends in
+        let blocks = self.text_blocks();
+        let first = self.first_text(blocks.first()?)?;
+        let last = self.last_text(blocks.last()?)?;
+        let end = self.text(&last).len();
+        Some(Selection::new(Pos::new(&first, 0), Pos::new(&last, end)))
+    }
+    /// the word at `pos` (e.g. for a double click). if `pos` is on spaces or
+    /// punctuation, that gets selected instead, like most editors
+    pub fn select_word(&self, pos: &Pos) -> Selection {
+        let (block, text, offset) = self.block_text(pos);
+        let segments: Vec<_> = text.split_word_bound_indices().collect();
+        // prefer the word before the caret if it's right at the end of one
+        let segment = segments.iter()
+            .find(|(i, seg)| *i <= offset && offset < i + seg.len() && is_word(seg))
+            .or_else(|| segments.iter().find(|(i, seg)| *i < offset && offset == i + seg.len() && is_word(seg)))
+            .or_else(|| segments.iter().find(|(i, seg)| *i <= offset && offset < i + seg.len()))
+            .or(segments.last());
+        match segment {
+            Some((i, seg)) => self.select_in_block(&block, *i, i + seg.len())
+                .unwrap_or_else(|| Selection::caret(pos.clone())),
+            None => Selection::caret(pos.clone()),
+        }
+    }
+    /// all the text in the block `pos` is in (e.g. for a triple click)
+    pub fn select_block(&self, pos: &Pos) -> Selection {
+        let (block, text, _) = self.block_text(pos);
+        self.select_in_block(&block, 0, text.len()).unwrap_or_else(|| Selection::caret(pos.clone()))
+    }
+    fn select_in_block(&self, block: &str, start: usize, end: usize) -> Option<Selection> {
+        Some(Selection::new(self.pos_in_block(block, start)?, self.pos_in_block(block, end)?))
+    }
+    /// whether both ends of the selection are still in the doc (they might
+    /// not be after an edit)
+    pub fn selection_is_valid(&self, sel: &Selection) -> bool {
+        [&sel.anchor, &sel.head].iter().all(|pos| {
+            self.contains(&pos.hash) && self.block_of(&pos.hash).is_some()
+                && self.text(&pos.hash).is_char_boundary(pos.offset)
+        })
+    }
+    /// the text between two positions w/ one line per block, e.g. for
+    /// copying to the clipboard
+    pub fn text_between(&self, start: &Pos, end: &Pos) -> String {
+        let (Some(start_block), Some(end_block)) =
+            (self.block_of(&start.hash), self.block_of(&end.hash)) else { return String::new() };
+        let mut lines = Vec::new();
+        let mut block = Some(start_block);
+        while let Some(hash) = block {
+            let texts = self.texts(&hash);
+            // only the start/end blocks have these
+            let start_idx = texts.iter().position(|t| *t == start.hash);
+            let end_idx = texts.iter().position(|t| *t == end.hash);
+            let mut line = String::new();
+            for (i, text) in texts.iter().enumerate() {
+                if start_idx.is_some_and(|idx| i < idx) || end_idx.is_some_and(|idx| i > idx) {
+                    continue;
+                }
+                let content = self.text(text);
+                let from = if Some(i) == start_idx { start.offset } else { 0 };
+                let to = if Some(i) == end_idx { end.offset } else { content.len() };
+                line.push_str(&content[from.min(to)..to]);
+            }
+            lines.push(line);
+            if hash == end_block { break }
+            block = self.next_leaf_block(&hash);
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_positions() {
+        let doc = Doc::from_markdown("a **b** c\n> d\n\ne\n", 1);
+        let blocks = doc.leaf_blocks();
+        let texts = doc.texts(&blocks[0]);
+        let d = doc.first_text(&blocks[1]).unwrap();
+        let e = doc.first_text(&blocks[2]).unwrap();
+        assert!(doc.cmp_pos(&Pos::new(&texts[0], 1), &Pos::new(&texts[0], 2)).is_lt());
+        assert!(doc.cmp_pos(&Pos::new(&texts[2], 0), &Pos::new(&texts[1], 1)).is_gt());
+        assert!(doc.cmp_pos(&Pos::new(&d, 1), &Pos::new(&e, 0)).is_lt());
+        let backwards = Selection::new(Pos::new(&e, 1), Pos::new(&texts[1], 0));
+        assert_eq!(doc.selection_range(&backwards), (Pos::new(&texts[1], 0), Pos::new(&e, 1)));
+    }
+
+    #[test]
+    fn select_words_and_blocks() {
+        let doc = Doc::from_markdown("one, t**wo** three\n\nfour\n", 1);
+        let block = &doc.leaf_blocks()[0];
+        let texts = doc.texts(block);
+        let selected = |sel: Selection| doc.text_between(&sel.anchor, &sel.head);
+        // runs across the bold text
+        assert_eq!(selected(doc.select_word(&Pos::new(&texts[1], 1))), "two");
+        assert_eq!(selected(doc.select_word(&Pos::new(&texts[0], 0))), "one");
+        // at the end of a word
+        assert_eq!(selected(doc.select_word(&Pos::new(&texts[0], 3))), "one");
+        assert_eq!(selected(doc.select_word(&Pos::new(&texts[0], 4))), " ");
+        assert_eq!(selected(doc.select_block(&Pos::new(&texts[2], 2))), "one, two three");
+        let empty = Doc::from_markdown("", 1);
+        assert!(empty.select_all().is_none());
+    }
+
+    #[test]
+    fn text_between_blocks() {
+        let doc = Doc::from_markdown("one **two** three\n> four\n\nfive six\n", 1);
+        let blocks = doc.leaf_blocks();
+        let texts = doc.texts(&blocks[0]);
+        let five = doc.first_text(&blocks[2]).unwrap();
+        assert_eq!(doc.text_between(&Pos::new(&texts[1], 1), &Pos::new(&five, 4)), "wo three\nfour\nfive");
+        assert_eq!(doc.text_between(&Pos::new(&texts[0], 1), &Pos::new(&texts[1], 2)), "ne tw");
+        let all = doc.select_all().unwrap();
+        assert_eq!(doc.text_between(&all.anchor, &all.head), "one two three\nfour\nfive six");
+    }
+}
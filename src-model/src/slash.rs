@@ -0,0 +1,268 @@
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, NodeTree, PageNodeType, Pos, Op, Transaction, SyntaxProfile, parse_markdown_with};
+use super::edit::table_skeleton;
+
+// the menu that opens when typing `/`. the text typed after the `/` filters
+// the items, and picking one turns the `/...` into a block kind or something
+// inserted
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashAction {
+    /// turn the block into this kind
+    SetKind(PageNodeType),
+    Divider,
+    /// e.g. today's date
+    InsertText(String),
+    /// blocks to insert, e.g. a template
+    InsertMarkdown(String),
+    /// a link to a note, w/ the caret in it to type the note's name
+    LinkToNote,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlashItem {
+    pub label: String,
+    /// other words the item can be found by
+    pub keywords: Vec<String>,
+    pub action: SlashAction,
+}
+impl SlashItem {
+    fn new(label: &str, keywords: &[&str], action: SlashAction) -> Self {
+        Self { label: label.into(), keywords: keywords.iter().map(|k| k.to_string()).collect(), action }
+    }
+}
+
+/// a markdown file in the user's templates folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub markdown: String,
+}
+
+/// everything in the menu. `date` is today's date, already formatted
+pub fn slash_items(date: &str, templates: &[Template]) -> Vec<SlashItem> {
+    use PageNodeType::*;
+    use SlashAction::*;
+    let mut items = vec![
+        SlashItem::new("Text", &["paragraph", "plain"], SetKind(TextBlock)),
+        SlashItem::new("Heading 1", &["h1", "title"], SetKind(H1)),
+        SlashItem::new("Heading 2", &["h2", "subtitle"], SetKind(H2)),
+        SlashItem::new("Heading 3", &["h3"], SetKind(H3)),
+        SlashItem::new("Quote", &["blockquote"], SetKind(Quote)),
+        SlashItem::new("Bulleted list", &["dot", "bullet", "ul"], SetKind(Dot)),
+        SlashItem::new("Numbered list", &["number", "ol"], SetKind(Num)),
+        SlashItem::new("To-do", &["check", "task", "checkbox"], SetKind(Check)),
+        SlashItem::new("Code", &["codeblock", "pre"], SetKind(CodeBlock)),
+        SlashItem::new("Table", &["grid"], SetKind(Table)),
+        SlashItem::new("Divider", &["hr", "line", "separator"], SlashAction::Divider),
+        SlashItem::new("Date", &["today", "now"], InsertText(date.to_string())),
+        SlashItem::new("Link to note", &["wikilink", "page", "file"], LinkToNote),
+    ];
+    for template in templates {
+        items.push(SlashItem {
+            label: format!("Template: {}", template.name),
+            keywords: vec!["template".into()],
+            action: InsertMarkdown(template.markdown.clone()),
+        });
+    }
+    items
+}
+
+/// the items that match what was typed after the `/`. every word typed has
+/// to be the start of a word in the label or keywords. items where the label
+/// itself starts w/ the query come first
+pub fn filter_slash_items(items: &[SlashItem], query: &str) -> Vec<SlashItem> {
+    let query = query.to_lowercase();
+    let mut found: Vec<(bool, &SlashItem)> = items.iter().filter_map(|item| {
+        let label = item.label.to_lowercase();
+        let words: Vec<&str> = label.split(|c: char| !c.is_alphanumeric())
+            .chain(item.keywords.iter().map(|k| k.as_str()))
+            .collect();
+        let matches = query.split_whitespace()
+            .all(|q| words.iter().any(|w| w.starts_with(q)));
+        matches.then_some((label.starts_with(query.trim()), item))
+    }).collect();
+    // stable, so otherwise the order stays the same as the menu's
+    found.sort_by_key(|(first, _)| !*first);
+    found.into_iter().map(|(_, item)| item.clone()).collect()
+}
+
+impl Doc {
+    /// the user picked an item from the slash menu. `start` is where the `/`
+    /// is, and `end` the end of what was typed after it (both get removed).
+    /// returns the edit and where the caret goes after it
+    pub fn apply_slash_item(&self, start: &Pos, end: &Pos, action: &SlashAction,
+        profile: &SyntaxProfile,
+    ) -> Option<(Transaction, Pos)> {
+        if start.hash != end.hash { return None }
+        let block = self.block_of(&start.hash)?;
+        let text = self.text(&start.hash);
+        let (before, after) = (text.get(..start.offset)?, text.get(end.offset..)?);
+        let delete = Op::DeleteRange { start: start.clone(), end: end.clone() };
+        // what's left of the block once the `/...` is gone
+        let block_is_empty = self.plain_text(&block).len() == end.offset - start.offset;
+        // new blocks go above the block if it's empty, so the caret stays in
+        // it below them
+        let parent = self.parent(&block)?.to_string();
+        let index = self.index_in_parent(&block)? + usize::from(!block_is_empty);
+        let mut tx = Transaction::from(delete);
+        match action {
+            SlashAction::SetKind(PageNodeType::Quote) => {
+                tx.push(Op::WrapIn { hashes: vec![block], kind: PageNodeType::Quote });
+            },
+            // a table is its markdown, so the block's text goes in the first
+            // cell of a new one
+            SlashAction::SetKind(PageNodeType::Table) => {
+                // a `/` in e.g. bold can't be a table
+                if self.parent(&start.hash) != Some(block.as_str()) { return None }
+                let cell: String = self.texts(&block).iter().map(|t| match *t == start.hash {
+                    true => format!("{}{}", before, after),
+                    false => self.text(t).to_string(),
+                }).collect();
+                let cell = cell.trim();
+                let others: Vec<String> = self.children(&block).iter()
+                    .filter(|c| **c != start.hash).cloned().collect();
+                if !others.is_empty() {
+                    tx.push(Op::RemoveNodes { hashes: others });
+                }
+                let table = table_skeleton(cell);
+                // in the first cell, after the text
+                let caret = Pos::new(&start.hash, table.find(" |").unwrap());
+                tx.push(Op::SetBlockKind { hash: block, kind: PageNodeType::Table });
+                tx.push(Op::SetText { hash: start.hash.clone(), text: table });
+                return Some((tx, caret));
+            },
+            SlashAction::SetKind(kind) => {
+                tx.push(Op::SetBlockKind { hash: block.clone(), kind: kind.clone() });
+                let was_check = *self.kind(&block) == PageNodeType::Check;
+                if *kind == PageNodeType::Check || was_check {
+                    tx.push(Op::SetContent { hash: block, key: "checked".into(),
+                        val: (*kind == PageNodeType::Check).then(|| "false".into()) });
+                }
+            },
+            SlashAction::Divider => {
+                let divider = NodeTree::new(PageNodeType::Divider, Vec::new());
+                tx.push(Op::InsertNodes { parent, index, nodes: vec![divider] });
+            },
+            SlashAction::InsertText(insert) => {
+                tx.push(Op::InsertText { pos: start.clone(), text: insert.clone() });
+                return Some((tx, Pos::new(&start.hash, start.offset + insert.len())));
+            },
+            SlashAction::InsertMarkdown(md) => {
+                let nodes = parse_markdown_with(md, profile).children;
+                tx.push(Op::InsertNodes { parent, index, nodes });
+            },
+            SlashAction::LinkToNote => {
+                // the text node becomes the link's label, w/ the text around
+                // it split off into new nodes
+                let parent = self.parent(&start.hash)?.to_string();
+                let index = self.index_in_parent(&start.hash)?;
+                let mut ops = Vec::new();
+                if !before.is_empty() {
+                    ops.push(Op::InsertNodes { parent: parent.clone(), index,
+                        nodes: vec![NodeTree::text(before)] });
+                }
+                ops.push(Op::SetText { hash: start.hash.clone(), text: String::new() });
+                if !after.is_empty() {
+                    let index = index + usize::from(!before.is_empty()) + 1;
+                    ops.push(Op::InsertNodes { parent, index, nodes: vec![NodeTree::text(after)] });
+                }
+                ops.push(Op::WrapIn { hashes: vec![start.hash.clone()], kind: PageNodeType::FileLink });
+                return Some((Transaction::from(ops), Pos::new(&start.hash, 0)));
+            },
+        }
+        Some((tx, start.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PageNodeType::*;
+
+    fn labels(items: &[SlashItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[test]
+    fn filters_items() {
+        let templates = [Template { name: "Meeting".into(), markdown: "# notes\n".into() }];
+        let items = slash_items("2026-10-19", &templates);
+        assert_eq!(filter_slash_items(&items, "").len(), items.len());
+        assert_eq!(labels(&filter_slash_items(&items, "head 2")), vec!["Heading 2"]);
+        assert_eq!(labels(&filter_slash_items(&items, "h1")), vec!["Heading 1"]);
+        // the label matching comes before the keyword matching
+        assert_eq!(labels(&filter_slash_items(&items, "t")),
+            vec!["Text", "To-do", "Table", "Template: Meeting", "Heading 1", "Date", "Link to note"]);
+        assert_eq!(labels(&filter_slash_items(&items, "meet")), vec!["Template: Meeting"]);
+        assert!(filter_slash_items(&items, "nope").is_empty());
+    }
+
+    #[test]
+    fn applies_items() {
+        let profile = SyntaxProfile::standard();
+        // the same hashes every time
+        let new_doc = || Doc::from_markdown("a /to\n\n/\n", 1);
+        let doc = new_doc();
+        let blocks = doc.leaf_blocks();
+        let (a, empty) = (doc.first_text(&blocks[0]).unwrap(), doc.first_text(&blocks[1]).unwrap());
+        let slash = |doc: &Doc, hash: &str, start, action| {
+            let end = Pos::new(hash, doc.text(hash).len());
+            doc.apply_slash_item(&Pos::new(hash, start), &end, &action, &profile).unwrap()
+        };
+
+        let mut check = new_doc();
+        let (tx, caret) = slash(&check, &a, 2, SlashAction::SetKind(Check));
+        check.apply(&tx).unwrap();
+        assert_eq!(check.to_markdown(), "- [ ] a \n/\n");
+        assert_eq!(caret, Pos::new(&a, 2));
+
+        // the divider goes above an empty block, and below one w/ text
+        let mut divider = new_doc();
+        let (tx, _) = slash(&divider, &empty, 0, SlashAction::Divider);
+        divider.apply(&tx).unwrap();
+        let (tx, _) = slash(&divider, &a, 2, SlashAction::Divider);
+        divider.apply(&tx).unwrap();
+        let kinds: Vec<_> = divider.leaf_blocks().iter().map(|b| divider.kind(b).clone()).collect();
+        assert_eq!(kinds, vec![TextBlock, Divider, Divider, TextBlock]);
+
+        // a table has to be written as one to be read back as one
+        let mut table = Doc::from_markdown("a **b** /tab\n\n/\n", 1);
+        let blocks = table.leaf_blocks();
+        let slash_text = table.texts(&blocks[0])[2].clone();
+        let (tx, caret) = slash(&table, &slash_text, 1, SlashAction::SetKind(Table));
+        table.apply(&tx).unwrap();
+        assert_eq!(table.to_markdown(), "| a b |  |\n| - | - |\n|  |  |\n/\n");
+        assert_eq!(table.text(&caret.hash)[..caret.offset], *"| a b");
+        let reopened = Doc::from_markdown(&table.to_markdown(), 1);
+        assert_eq!(reopened.kind(&reopened.leaf_blocks()[0]), &Table);
+        assert_eq!(reopened.to_markdown(), table.to_markdown());
+        let mut empty_table = new_doc();
+        let (tx, caret) = slash(&empty_table, &empty, 0, SlashAction::SetKind(Table));
+        empty_table.apply(&tx).unwrap();
+        assert_eq!(empty_table.to_markdown(), "a /to\n|  |  |\n| - | - |\n|  |  |\n");
+        assert_eq!(caret, Pos::new(&empty, 2));
+        let reopened = Doc::from_markdown(&empty_table.to_markdown(), 1);
+        assert_eq!(reopened.kind(&reopened.leaf_blocks()[1]), &Table);
+
+        let mut link = new_doc();
+        let (tx, caret) = slash(&link, &a, 2, SlashAction::LinkToNote);
+        link.apply(&tx).unwrap();
+        link.apply(&Op::InsertText { pos: caret, text: "b".into() }.into()).unwrap();
+        assert_eq!(link.to_markdown(), "a [[b]]\n\n/\n");
+
+        let mut date = new_doc();
+        let (tx, caret) = slash(&date, &empty, 0, SlashAction::InsertText("2026-10-19".into()));
+        date.apply(&tx).unwrap();
+        assert_eq!(date.text(&empty), "2026-10-19");
+        assert_eq!(caret.offset, 10);
+
+        let mut template = new_doc();
+        let md = "## notes\n- one\n".to_string();
+        let (tx, caret) = slash(&template, &empty, 0, SlashAction::InsertMarkdown(md));
+        template.apply(&tx).unwrap();
+        assert_eq!(template.to_markdown(), "a /to\n## notes\n- one\n\n");
+        assert!(template.contains(&caret.hash));
+    }
+}
@@ -0,0 +1,163 @@
+use serde::{Serialize, Deserialize};
+
+use super::PageNodeType;
+
+// which markdown makes which node. the parser, the serializer and the
+// shortcuts while typing all go through a profile, so a user can pick their
+// own syntax (e.g. `/italic/`). files are always saved as standard markdown
+// though: the profile's pick is only written if it's standard, otherwise the
+// standard syntax is
+
+/// a delimiter on both sides of a span, e.g. `**` for bold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanSyntax {
+    pub delim: String,
+    pub kind: PageNodeType,
+}
+/// a prefix at the start of a block, e.g. `#` for `H1`. followed by a space
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSyntax {
+    pub prefix: String,
+    pub kind: PageNodeType,
+}
+
+/// the syntax the parser/shortcuts understand. earlier entries are preferred,
+/// so the first one for a kind is what gets written (if it's standard)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxProfile {
+    #[serde(default)]
+    pub spans: Vec<SpanSyntax>,
+    #[serde(default)]
+    pub blocks: Vec<BlockSyntax>,
+}
+
+const STANDARD_SPANS: [(&str, PageNodeType); 5] = [
+    ("**", PageNodeType::Bold), ("==", PageNodeType::Highlight),
+    ("*", PageNodeType::Italic), ("_", PageNodeType::Italic),
+    ("`", PageNodeType::CodeInline),
+];
+const STANDARD_BLOCKS: [(&str, PageNodeType); 9] = [
+    ("#", PageNodeType::H1), ("##", PageNodeType::H2), ("###", PageNodeType::H3),
+    ("####", PageNodeType::H4), ("#####", PageNodeType::H5),
+    ("-", PageNodeType::Dot), ("*", PageNodeType::Dot), ("+", PageNodeType::Dot),
+    (">", PageNodeType::Quote),
+];
+
+impl SyntaxProfile {
+    /// plain markdown
+    pub fn standard() -> Self {
+        Self {
+            spans: STANDARD_SPANS.iter()
+                .map(|(delim, kind)| SpanSyntax { delim: delim.to_string(), kind: kind.clone() })
+                .collect(),
+            blocks: STANDARD_BLOCKS.iter()
+                .map(|(prefix, kind)| BlockSyntax { prefix: prefix.to_string(), kind: kind.clone() })
+                .collect(),
+        }
+    }
+    /// standard markdown plus the user's syntax, which comes first so it's
+    /// preferred. entries that can't work (empty, or for the wrong kind of
+    /// node) are dropped
+    pub fn with_user(user: &SyntaxProfile) -> Self {
+        let mut profile = Self::standard();
+        let spans = user.spans.iter()
+            .filter(|s| !s.delim.trim().is_empty() && matches!(s.kind, PageNodeType::Bold
+                | PageNodeType::Italic | PageNodeType::Highlight | PageNodeType::CodeInline))
+            .cloned();
+        profile.spans.splice(0..0, spans);
+        let blocks = user.blocks.iter()
+            .filter(|b| !b.prefix.trim().is_empty() && !b.prefix.contains(' ')
+                && (b.kind.is_leaf_block() || b.kind == PageNodeType::Quote))
+            .cloned();
+        profile.blocks.splice(0..0, blocks);
+        profile
+    }
+    /// span delimiters to try when parsing, longest first so `**` isn't read
+    /// as two `*`
+    pub(crate) fn spans_longest_first(&self) -> Vec<&SpanSyntax> {
+        let mut spans: Vec<_> = self.spans.iter().collect();
+        spans.sort_by_key(|s| std::cmp::Reverse(s.delim.len()));
+        spans
+    }
+    /// the kind of block a line starts with, and the text after the prefix.
+    /// longest prefix first so `##` isn't read as `#`
+    pub(crate) fn block_prefix<'a>(&self, line: &'a str) -> Option<(PageNodeType, &'a str)> {
+        let mut blocks: Vec<_> = self.blocks.iter().collect();
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.prefix.len()));
+        blocks.into_iter().find_map(|b| {
+            let text = line.strip_prefix(b.prefix.as_str())?.strip_prefix(' ')?;
+            Some((b.kind.clone(), text))
+        })
+    }
+    /// the delimiter to save a span as
+    pub(crate) fn write_delim(&self, kind: &PageNodeType) -> &str {
+        let is_standard = |delim: &str| STANDARD_SPANS.iter().any(|(d, k)| *d == delim && k == kind);
+        self.spans.iter().map(|s| s.delim.as_str()).find(|d| is_standard(d))
+            .or_else(|| STANDARD_SPANS.iter().find(|(_, k)| k == kind).map(|(d, _)| *d))
+            .unwrap_or("")
+    }
+    /// the prefix to save a block as (w/o the space after it)
+    pub(crate) fn write_prefix(&self, kind: &PageNodeType) -> &str {
+        let is_standard = |prefix: &str| STANDARD_BLOCKS.iter().any(|(p, k)| *p == prefix && k == kind);
+        self.blocks.iter().map(|b| b.prefix.as_str()).find(|p| is_standard(p))
+            .or_else(|| STANDARD_BLOCKS.iter().find(|(_, k)| k == kind).map(|(p, _)| *p))
+            .unwrap_or("")
+    }
+    /// chars (other than the standard ones) that start a span, so they need
+    /// escaping when they're just text
+    pub(crate) fn extra_delim_chars(&self) -> Vec<char> {
+        let mut chars: Vec<char> = self.spans.iter()
+            .filter(|s| !STANDARD_SPANS.iter().any(|(d, _)| *d == s.delim))
+            .filter_map(|s| s.delim.chars().next())
+            .filter(|c| c.is_ascii_punctuation())
+            .collect();
+        chars.dedup();
+        chars
+    }
+}
+/// whether the delimiter only counts at word boundaries (so snake_case and
+/// paths like a/b/c aren't italic). only `*`-like delimiters can go inside
+/// words, like in standard markdown
+pub(crate) fn is_intraword_delim(delim: &str) -> bool {
+    matches!(delim, "**" | "*" | "==" | "`")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PageNodeType::*;
+
+    fn house_style() -> SyntaxProfile {
+        SyntaxProfile::with_user(&SyntaxProfile {
+            spans: vec![
+                SpanSyntax { delim: "/".into(), kind: Italic },
+                SpanSyntax { delim: "_".into(), kind: Italic },
+                // not a span
+                SpanSyntax { delim: "~".into(), kind: H1 },
+            ],
+            blocks: vec![
+                BlockSyntax { prefix: "+".into(), kind: Dot },
+                BlockSyntax { prefix: "!".into(), kind: H1 },
+            ],
+        })
+    }
+
+    #[test]
+    fn prefers_the_users_syntax() {
+        let profile = house_style();
+        assert!(profile.spans.iter().all(|s| s.delim != "~"));
+        assert_eq!(profile.block_prefix("! title"), Some((H1, "title")));
+        assert_eq!(profile.block_prefix("## title"), Some((H2, "title")));
+        assert_eq!(profile.block_prefix("#title"), None);
+        // `/` isn't standard, so italics are saved as the next pick
+        assert_eq!(profile.write_delim(&Italic), "_");
+        assert_eq!(profile.write_delim(&Bold), "**");
+        assert_eq!(profile.write_prefix(&Dot), "+");
+        // `!` isn't standard
+        assert_eq!(profile.write_prefix(&H1), "#");
+        assert_eq!(profile.extra_delim_chars(), vec!['/']);
+        let standard = SyntaxProfile::standard();
+        assert_eq!(standard.write_delim(&Italic), "*");
+        assert_eq!(standard.write_prefix(&Dot), "-");
+    }
+}
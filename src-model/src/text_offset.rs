@@ -0,0 +1,105 @@
+use unicode_segmentation::GraphemeCursor;
+
+// there are 3 ways to count offsets into text:
+// - bytes, which is what the doc uses (`Pos::offset`) bc that's how rust
+//   strings are indexed
+// - utf-16 code units, which is what the DOM uses (selection offsets etc)
+// - graphemes, which is what the user sees as one "character" (e.g. an emoji
+//   made of several chars joined together). the caret should only ever be
+//   between graphemes, and backspace etc. removes a whole one
+
+/// DOM offset -> byte offset. an offset in the middle of a surrogate pair goes
+/// to the start of the char. clamped to the end of the text bc an empty text
+/// node is shown with an invisible char the doc doesn't have
+pub fn utf16_to_byte(text: &str, utf16_offset: u32) -> usize {
+    let mut count = 0;
+    for (i, char) in text.char_indices() {
+        count += char.len_utf16();
+        if count > utf16_offset as usize { return i }
+    }
+    text.len()
+}
+/// byte offset -> DOM offset
+pub fn byte_to_utf16(text: &str, byte_offset: usize) -> u32 {
+    text[..floor_char_boundary(text, byte_offset)].encode_utf16().count() as u32
+}
+
+/// move the offset back to the start of the char it's in
+pub fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) { offset -= 1 }
+    offset
+}
+
+/// move the offset back to the start of the grapheme it's in
+pub fn floor_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let offset = floor_char_boundary(text, offset);
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    match cursor.is_boundary(text, 0).unwrap() {
+        true => offset,
+        false => cursor.prev_boundary(text, 0).unwrap().unwrap_or(0),
+    }
+}
+
+/// start of the grapheme before the offset (or 0 if already at the start)
+pub fn prev_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let offset = floor_char_boundary(text, offset);
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    cursor.prev_boundary(text, 0).unwrap().unwrap_or(0)
+}
+
+/// end of the grapheme after the offset (or the end of the text if already
+/// there)
+pub fn next_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let offset = floor_char_boundary(text, offset);
+    let mut cursor = GraphemeCursor::new(offset, text.len(), true);
+    cursor.next_boundary(text, 0).unwrap().unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "e" + combining acute, a family emoji (4 people joined w/ ZWJs), and a
+    // flag (2 regional indicators)
+    const TEXT: &str = "ae\u{301}👨‍👩‍👧‍👦🇳🇿z";
+
+    #[test]
+    fn utf16_round_trip() {
+        let text = "a😀b";
+        assert_eq!(byte_to_utf16(text, 1), 1);
+        assert_eq!(byte_to_utf16(text, 5), 3);
+        assert_eq!(utf16_to_byte(text, 3), 5);
+        // in the middle of the surrogate pair
+        assert_eq!(utf16_to_byte(text, 2), 1);
+        assert_eq!(utf16_to_byte(text, 99), text.len());
+        assert_eq!(utf16_to_byte("", 1), 0);
+    }
+
+    #[test]
+    fn steps_over_whole_graphemes() {
+        let mut offsets = vec![0];
+        while *offsets.last().unwrap() < TEXT.len() {
+            offsets.push(next_grapheme_boundary(TEXT, *offsets.last().unwrap()));
+        }
+        let graphemes: Vec<_> = offsets.windows(2).map(|w| &TEXT[w[0]..w[1]]).collect();
+        assert_eq!(graphemes, vec!["a", "e\u{301}", "👨‍👩‍👧‍👦", "🇳🇿", "z"]);
+
+        let mut offset = TEXT.len();
+        for expected in offsets.iter().rev().skip(1) {
+            offset = prev_grapheme_boundary(TEXT, offset);
+            assert_eq!(offset, *expected);
+        }
+        assert_eq!(prev_grapheme_boundary(TEXT, 0), 0);
+        assert_eq!(next_grapheme_boundary(TEXT, TEXT.len()), TEXT.len());
+    }
+
+    #[test]
+    fn snaps_into_grapheme_boundary() {
+        // inside the combining mark, and inside the emoji
+        assert_eq!(floor_grapheme_boundary(TEXT, 3), 1);
+        assert_eq!(floor_grapheme_boundary(TEXT, 10), 4);
+        assert_eq!(floor_grapheme_boundary(TEXT, 4), 4);
+        assert_eq!(floor_char_boundary("é", 1), 0);
+    }
+}
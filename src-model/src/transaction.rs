@@ -0,0 +1,435 @@
+use std::{cmp::Ordering, collections::HashSet, fmt};
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, NodeTree, PageNodeType, Pos, ROOT_HASH};
+
+/// one edit to the doc. every op can be serialized, so a list of them can be
+/// saved, replayed, or sent somewhere else
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    InsertText { pos: Pos, text: String },
+    /// `start` must come before `end`. if they're in different blocks, the
+    /// blocks between are removed and the two ends joined
+    DeleteRange { start: Pos, end: Pos },
+    /// split the block at `pos`, moving everything after it to a new block
+    SplitBlock { pos: Pos },
+    /// merge the block onto the end of the leaf block above it
+    MergeBlocks { hash: String },
+    /// works on spans too
+    SetBlockKind { hash: String, kind: PageNodeType },
+    /// wrap sibling blocks in a new branch block (e.g. a quote)
+    WrapIn { hashes: Vec<String>, kind: PageNodeType },
+    /// replace a branch block with its children
+    Unwrap { hash: String },
+    /// move a block out of the branch block it's in
+    LiftBlock { hash: String },
+    /// move the nodes to be children of `parent`, starting at `index`.
+    /// `index` counts the children left once the moved nodes are taken out
+    MoveNodes { hashes: Vec<String>, parent: String, index: usize },
+    InsertNodes { parent: String, index: usize, nodes: Vec<NodeTree> },
+    RemoveNodes { hashes: Vec<String> },
+    SetText { hash: String, text: String },
+    /// set (or remove with `None`) a content entry other than `"text"`
+    SetContent { hash: String, key: String, val: Option<String> },
+}
+
+/// ops that are applied together, and undone together
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub ops: Vec<Op>,
+}
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, op: Op) {
+        self.ops.push(op);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+impl From<Op> for Transaction {
+    fn from(op: Op) -> Self {
+        Self { ops: vec![op] }
+    }
+}
+impl From<Vec<Op>> for Transaction {
+    fn from(ops: Vec<Op>) -> Self {
+        Self { ops }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Applied {
+    /// applying this puts the doc back how it was
+    pub inverse: Transaction,
+    /// where the caret should go, if any of the ops moved it
+    pub caret: Option<Pos>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpError {
+    MissingNode(String),
+    /// not a `RawText` node, or the offset is past the end of the text / not
+    /// on a char boundary
+    BadPos(Pos),
+    Invalid(String),
+}
+impl fmt::Display for OpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpError::MissingNode(hash) => write!(f, "no node with hash {:?}", hash),
+            OpError::BadPos(pos) => write!(f, "bad text position {:?}", pos),
+            OpError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+impl std::error::Error for OpError {}
+
+impl Doc {
+    /// the one way to edit the doc from outside the crate. either all the ops
+    /// are applied or (if one is invalid) none are
+    pub fn apply(&mut self, tx: &Transaction) -> Result<Applied, OpError> {
+        self.undo_log = Some(Vec::new());
+        let mut caret = None;
+        for op in &tx.ops {
+            if let Err(err) = self.check(op) {
+                // undo the ops already applied
+                let undo_log = self.undo_log.take().unwrap();
+                for undo in undo_log.iter().rev() {
+                    self.apply_op(undo);
+                }
+                return Err(err);
+            }
+            if let Some(pos) = self.apply_op(op) {
+                caret = Some(pos);
+            }
+        }
+        let mut ops = self.undo_log.take().unwrap();
+        ops.reverse();
+        let inverse = Transaction { ops };
+        if let Some(journal) = &mut self.journal {
+            journal.push(inverse.clone());
+        }
+        Ok(Applied { inverse, caret })
+    }
+    /// start keeping the inverse of every transaction applied, so an edit
+    /// made w/ more than one `apply` (e.g. a vim command) can be undone as
+    /// one (see `History`)
+    pub fn start_journal(&mut self) {
+        self.journal = Some(Vec::new());
+    }
+    /// stop keeping them. returns the one transaction that undoes them all
+    pub fn end_journal(&mut self) -> Transaction {
+        let inverses = self.journal.take().unwrap_or_default();
+        Transaction { ops: inverses.into_iter().rev().flat_map(|tx| tx.ops).collect() }
+    }
+    fn apply_op(&mut self, op: &Op) -> Option<Pos> {
+        match op.clone() {
+            Op::InsertText { pos, text } => {
+                self.insert_text(&pos.hash, pos.offset, &text);
+                Some(Pos::new(&pos.hash, pos.offset + text.len()))
+            },
+            Op::DeleteRange { start, end } => Some(self.delete_range(&start, &end)),
+            Op::SplitBlock { pos } => Some(self.split_block(&pos)),
+            Op::MergeBlocks { hash } => self.merge_into_prev(&hash),
+            Op::SetBlockKind { hash, kind } => {
+                self.set_kind(&hash, kind);
+                None
+            },
+            Op::WrapIn { hashes, kind } => {
+                self.wrap_in(&hashes, kind);
+                None
+            },
+            Op::Unwrap { hash } => {
+                self.unwrap_block(&hash);
+                None
+            },
+            Op::LiftBlock { hash } => {
+                self.lift_block(&hash);
+                None
+            },
+            Op::MoveNodes { hashes, parent, index } => {
+                // the node the moved nodes go in front of (`None` = the end)
+                let anchor = self.children(&parent).iter()
+                    .filter(|c| !hashes.contains(c)).nth(index).cloned();
+                for hash in &hashes {
+                    let mut index = match &anchor {
+                        Some(anchor) => self.index_in_parent(anchor).unwrap(),
+                        None => usize::MAX,
+                    };
+                    // `move_node` takes the node out before inserting it
+                    if self.parent(hash) == Some(&parent)
+                        && self.index_in_parent(hash).unwrap() < index {
+                        index -= 1;
+                    }
+                    self.move_node(hash, &parent, index);
+                }
+                None
+            },
+            Op::InsertNodes { parent, index, nodes } => {
+                for (i, node) in nodes.into_iter().enumerate() {
+                    self.insert_tree(&parent, index.saturating_add(i), node);
+                }
+                None
+            },
+            Op::RemoveNodes { hashes } => {
+                for hash in hashes {
+                    self.remove(&hash);
+                }
+                None
+            },
+            Op::SetText { hash, text } => {
+                self.set_text(&hash, &text);
+                None
+            },
+            Op::SetContent { hash, key, val } => {
+                self.set_content(&hash, &key, val);
+                None
+            },
+        }
+    }
+    /// make sure the op can be applied, so a bad op (e.g. from an old
+    /// transaction) can't leave the doc half edited
+    fn check(&self, op: &Op) -> Result<(), OpError> {
+        match op {
+            Op::InsertText { pos, .. } | Op::SplitBlock { pos } => self.check_pos(pos),
+            Op::DeleteRange { start, end } => {
+                self.check_pos(start)?;
+                self.check_pos(end)?;
+                if self.cmp_pos(start, end) == Ordering::Greater {
+                    return Err(OpError::Invalid("range ends before it starts".into()));
+                }
+                Ok(())
+            },
+            Op::MergeBlocks { hash } => {
+                self.check_leaf_block(hash)?;
+                match self.prev_leaf_block(hash) {
+                    Some(_) => Ok(()),
+                    None => Err(OpError::Invalid("no block above to merge into".into())),
+                }
+            },
+            Op::SetBlockKind { hash, .. } | Op::SetText { hash, .. }
+            | Op::SetContent { hash, .. } => self.check_node(hash),
+            Op::WrapIn { hashes, .. } => {
+                let Some(first) = hashes.first() else {
+                    return Err(OpError::Invalid("nothing to wrap".into()));
+                };
+                self.check_nodes(hashes)?;
+                if hashes.iter().any(|h| self.parent(h) != self.parent(first)) {
+                    return Err(OpError::Invalid("can only wrap siblings".into()));
+                }
+                Ok(())
+            },
+            Op::Unwrap { hash } => {
+                self.check_node(hash)?;
+                match self.kind(hash).is_branch() && hash != ROOT_HASH {
+                    true => Ok(()),
+                    false => Err(OpError::Invalid(format!("{:?} isn't a branch block", hash))),
+                }
+            },
+            Op::LiftBlock { hash } => {
+                self.check_node(hash)?;
+                match self.parent(hash) {
+                    Some(parent) if parent != ROOT_HASH => Ok(()),
+                    _ => Err(OpError::Invalid(format!("{:?} isn't in a branch block", hash))),
+                }
+            },
+            Op::MoveNodes { hashes, parent, .. } => {
+                self.check_nodes(hashes)?;
+                self.check_node(parent)?;
+                if hashes.iter().any(|h| h == parent || self.is_ancestor(h, parent)) {
+                    return Err(OpError::Invalid("can't move a node inside itself".into()));
+                }
+                Ok(())
+            },
+            Op::InsertNodes { parent, .. } => self.check_node(parent),
+            Op::RemoveNodes { hashes } => self.check_nodes(hashes),
+        }
+    }
+    fn check_node(&self, hash: &str) -> Result<(), OpError> {
+        match self.contains(hash) {
+            true => Ok(()),
+            false => Err(OpError::MissingNode(hash.to_string())),
+        }
+    }
+    /// like `check_node` but the root doesn't count. each node can only be
+    /// in there once, and not inside another one, bc it'd be gone (or moved)
+    /// by the time it came up again
+    fn check_nodes(&self, hashes: &[String]) -> Result<(), OpError> {
+        let mut seen = HashSet::new();
+        for hash in hashes {
+            if hash == ROOT_HASH {
+                return Err(OpError::Invalid("can't edit the root node".into()));
+            }
+            self.check_node(hash)?;
+            if !seen.insert(hash.as_str()) {
+                return Err(OpError::Invalid(format!("{:?} is in there twice", hash)));
+            }
+        }
+        for hash in hashes {
+            let mut parent = self.parent(hash);
+            while let Some(p) = parent {
+                if seen.contains(p) {
+                    return Err(OpError::Invalid(format!("{:?} is inside {:?}", hash, p)));
+                }
+                parent = self.parent(p);
+            }
+        }
+        Ok(())
+    }
+    fn check_leaf_block(&self, hash: &str) -> Result<(), OpError> {
+        self.check_node(hash)?;
+        match self.kind(hash).is_leaf_block() {
+            true => Ok(()),
+            false => Err(OpError::Invalid(format!("{:?} isn't a leaf block", hash))),
+        }
+    }
+    fn check_pos(&self, pos: &Pos) -> Result<(), OpError> {
+        self.check_node(&pos.hash)?;
+        let ok = *self.kind(&pos.hash) == PageNodeType::RawText
+            && self.block_of(&pos.hash).is_some()
+            && self.text(&pos.hash).is_char_boundary(pos.offset);
+        match ok {
+            true => Ok(()),
+            false => Err(OpError::BadPos(pos.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PageNodeType::*;
+
+    fn demo_doc() -> Doc {
+        Doc::from_markdown("# title\n\nsome **bold** text\n> quoted\n> more\n", 1)
+    }
+
+    /// apply the tx, check the inverse puts the doc back, then check the
+    /// inverse of the inverse redoes it
+    fn assert_round_trips(doc: &mut Doc, tx: Transaction) {
+        let before = doc.root_tree();
+        let undo = doc.apply(&tx).unwrap().inverse;
+        let after = doc.root_tree();
+        assert_ne!(before, after);
+        let redo = doc.apply(&undo).unwrap().inverse;
+        assert_eq!(doc.root_tree(), before);
+        doc.apply(&redo).unwrap();
+        assert_eq!(doc.root_tree(), after);
+    }
+
+    #[test]
+    fn every_op_inverts() {
+        // same seed, so every copy of the demo doc has the same hashes
+        let doc = demo_doc();
+        let blocks = doc.leaf_blocks();
+        let texts: Vec<_> = blocks.iter().map(|b| doc.first_text(b).unwrap()).collect();
+        let quote = doc.parent(&blocks[2]).unwrap().to_string();
+        let txs: Vec<Transaction> = vec![
+            Op::InsertText { pos: Pos::new(&texts[0], 2), text: "é!".into() }.into(),
+            Op::DeleteRange { start: Pos::new(&texts[0], 1), end: Pos::new(&texts[2], 2) }.into(),
+            Op::SplitBlock { pos: Pos::new(&doc.texts(&blocks[1])[1], 2) }.into(),
+            Op::MergeBlocks { hash: blocks[2].clone() }.into(),
+            Op::SetBlockKind { hash: blocks[1].clone(), kind: H3 }.into(),
+            Op::WrapIn { hashes: blocks[..2].to_vec(), kind: Indent }.into(),
+            Op::Unwrap { hash: quote.clone() }.into(),
+            Op::LiftBlock { hash: blocks[3].clone() }.into(),
+            Op::MoveNodes { hashes: vec![blocks[0].clone(), quote], parent: ROOT_HASH.into(), index: 1 }.into(),
+            Op::InsertNodes { parent: ROOT_HASH.into(), index: 1, nodes: vec![NodeTree::block(Dot, "new")] }.into(),
+            Op::RemoveNodes { hashes: vec![blocks[1].clone()] }.into(),
+            Op::SetText { hash: texts[0].clone(), text: "new title".into() }.into(),
+            Op::SetContent { hash: blocks[0].clone(), key: "pinned".into(), val: Some(String::new()) }.into(),
+        ];
+        for tx in txs {
+            assert_round_trips(&mut demo_doc(), tx);
+        }
+    }
+
+    #[test]
+    fn hashes_survive_undo_redo() {
+        let mut doc = demo_doc();
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let applied = doc.apply(&Op::SplitBlock { pos: Pos::new(&text, 2) }.into()).unwrap();
+        let new_block = doc.leaf_blocks()[1].clone();
+        let redo = doc.apply(&applied.inverse).unwrap().inverse;
+        assert!(!doc.contains(&new_block));
+        doc.apply(&redo).unwrap();
+        assert_eq!(doc.leaf_blocks()[1], new_block);
+    }
+
+    #[test]
+    fn bad_op_applies_nothing() {
+        let mut doc = demo_doc();
+        let before = doc.root_tree();
+        let text = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+        let tx = Transaction::from(vec![
+            Op::InsertText { pos: Pos::new(&text, 0), text: "x".into() },
+            Op::RemoveNodes { hashes: vec!["nope".into()] },
+        ]);
+        assert_eq!(doc.apply(&tx), Err(OpError::MissingNode("nope".into())));
+        assert_eq!(doc.root_tree(), before);
+        // offset inside a multibyte char
+        doc.apply(&Op::SetText { hash: text.clone(), text: "é".into() }.into()).unwrap();
+        let bad = Pos::new(&text, 1);
+        assert_eq!(doc.apply(&Op::SplitBlock { pos: bad.clone() }.into()), Err(OpError::BadPos(bad)));
+    }
+
+    #[test]
+    fn backwards_range_is_an_error() {
+        let mut doc = demo_doc();
+        let before = doc.root_tree();
+        let blocks = doc.leaf_blocks();
+        let (title, text) = (doc.first_text(&blocks[0]).unwrap(), doc.texts(&blocks[1]));
+        // across blocks, and across text nodes in the same block
+        let ranges = [(Pos::new(&text[0], 1), Pos::new(&title, 2)), (Pos::new(&text[2], 0), Pos::new(&text[0], 3))];
+        for (start, end) in ranges {
+            let tx = Transaction::from(Op::DeleteRange { start, end });
+            assert!(matches!(doc.apply(&tx), Err(OpError::Invalid(_))));
+            assert_eq!(doc.root_tree(), before);
+        }
+    }
+
+    #[test]
+    fn repeated_or_nested_nodes_are_errors() {
+        let mut doc = demo_doc();
+        let before = doc.root_tree();
+        let blocks = doc.leaf_blocks();
+        let quote = doc.parent(&blocks[2]).unwrap().to_string();
+        let twice = vec![blocks[0].clone(), blocks[0].clone()];
+        let nested = vec![quote.clone(), blocks[2].clone()];
+        let txs: Vec<Transaction> = vec![
+            Op::RemoveNodes { hashes: twice.clone() }.into(),
+            Op::RemoveNodes { hashes: nested.clone() }.into(),
+            Op::WrapIn { hashes: twice.clone(), kind: Quote }.into(),
+            Op::MoveNodes { hashes: twice, parent: quote.clone(), index: 0 }.into(),
+            Op::MoveNodes { hashes: nested, parent: ROOT_HASH.into(), index: 0 }.into(),
+            // into a node that's being moved
+            Op::MoveNodes { hashes: vec![quote.clone()], parent: quote.clone(), index: 0 }.into(),
+            Op::MoveNodes { hashes: vec![blocks[0].clone(), quote], parent: blocks[2].clone(), index: 0 }.into(),
+        ];
+        for tx in txs {
+            assert!(matches!(doc.apply(&tx), Err(OpError::Invalid(_))), "{:?}", tx);
+            assert_eq!(doc.root_tree(), before);
+        }
+    }
+
+    #[test]
+    fn move_nodes_index_ignores_moved_nodes() {
+        let mut doc = Doc::from_markdown("a\n\nb\n\nc\n\nd\n", 1);
+        let blocks = doc.leaf_blocks();
+        doc.apply(&Op::MoveNodes {
+            hashes: blocks[..2].to_vec(), parent: ROOT_HASH.into(), index: 2,
+        }.into()).unwrap();
+        let texts: Vec<_> = doc.leaf_blocks().iter().map(|b| doc.plain_text(b)).collect();
+        assert_eq!(texts, vec!["c", "d", "a", "b"]);
+    }
+
+    #[test]
+    fn transactions_serialize() {
+        let tx = Transaction::from(Op::InsertText { pos: Pos::new("ab12", 0), text: "x".into() });
+        let json = serde_json::to_string(&tx).unwrap();
+        assert_eq!(serde_json::from_str::<Transaction>(&json).unwrap(), tx);
+    }
+}
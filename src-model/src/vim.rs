@@ -0,0 +1,753 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Doc, NodeTree, PageNodeType, Pos, Op, Selection, ROOT_HASH,
+    prev_grapheme_boundary, next_grapheme_boundary};
+use super::edit::is_word;
+
+// an optional vim layer over the editor. each leaf block w/ text is a "line",
+// so `j`/`k`/`dd` etc go by blocks, and `{`/`}` go by top level blocks (a
+// whole list or quote at once). everything works on the doc rather than the
+// DOM, so motions/edits reach blocks that aren't rendered. positions are
+// carets (between chars) rather than vim's cursor on a char, so e.g. `$` and
+// `e` go after the last char
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+}
+impl VimMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VimMode::Normal => "normal",
+            VimMode::Insert => "insert",
+            VimMode::Visual => "visual",
+        }
+    }
+}
+
+/// what `y`/`d`/`c` saved for `p`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Register {
+    Text(String),
+    /// from `yy`/`dd`/etc, so pasted as blocks
+    Blocks(Vec<NodeTree>),
+}
+
+/// the last change, for `.` to do again
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Recording {
+    keys: Vec<String>,
+    /// typed in insert mode after the keys (e.g. after `cw`)
+    typed: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left, Right, Down, Up,
+    WordStart, WordBack, WordEnd,
+    BlockStart, BlockEnd,
+    FirstBlock, LastBlock,
+    NextTopBlock, PrevTopBlock,
+}
+impl Motion {
+    /// w/ an operator these take whole blocks
+    fn is_blockwise(&self) -> bool {
+        matches!(self, Motion::Down | Motion::Up | Motion::FirstBlock | Motion::LastBlock)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator { Delete, Change, Yank }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Move(Motion),
+    Operate(Operator, Motion),
+    /// `dd`, `cc`, `yy`
+    OperateBlocks(Operator),
+    /// `d`/`c`/`y` in visual mode
+    OperateSelection(Operator),
+    /// everything else (`x`, `p`, `i`, `o`, `.` etc)
+    Other(char),
+}
+
+enum Parsed {
+    Incomplete,
+    Invalid,
+    Done { count: Option<usize>, action: Action },
+}
+
+/// what's being operated on
+enum Range {
+    Text(Pos, Pos),
+    Blocks(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Vim {
+    pub mode: VimMode,
+    /// keys typed so far for a command that isn't finished (e.g. `d2`)
+    pending: Vec<String>,
+    register: Option<Register>,
+    last_change: Option<Recording>,
+    /// the change being typed in insert mode
+    recording: Option<Recording>,
+    /// where visual mode started
+    anchor: Option<Pos>,
+    /// doing a `.`, so nothing gets recorded
+    replaying: bool,
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// whether vim handles the key (a `KeyboardEvent.key`), rather than the
+    /// editor. in insert mode that's just Escape
+    pub fn wants(&self, key: &str) -> bool {
+        match self.mode {
+            VimMode::Insert => key == "Escape",
+            _ => key == "Escape" || key.chars().count() == 1,
+        }
+    }
+    /// text typed in insert mode, so `.` can type it again. `"\n"` for a
+    /// new block
+    pub fn typed(&mut self, text: &str) {
+        if let Some(recording) = &mut self.recording {
+            recording.typed.push_str(text);
+        }
+    }
+    /// a key vim `wants`. edits are applied to the doc straight away, and the
+    /// new selection (if it changed) is returned
+    pub fn key(&mut self, doc: &mut Doc, sel: &Selection, key: &str) -> Option<Selection> {
+        if !doc.selection_is_valid(sel) { return None }
+        if key == "Escape" {
+            self.pending.clear();
+            return match std::mem::take(&mut self.mode) {
+                VimMode::Insert => {
+                    if let Some(recording) = self.recording.take() {
+                        self.last_change = Some(recording);
+                    }
+                    None
+                },
+                VimMode::Visual => {
+                    self.anchor = None;
+                    Some(Selection::caret(sel.head.clone()))
+                },
+                VimMode::Normal => None,
+            };
+        }
+        if self.mode == VimMode::Insert { return None }
+        self.pending.push(key.to_string());
+        match parse(&self.pending, self.mode) {
+            Parsed::Incomplete => None,
+            Parsed::Invalid => {
+                self.pending.clear();
+                None
+            },
+            Parsed::Done { count, action } => {
+                let keys = std::mem::take(&mut self.pending);
+                self.run(doc, sel, count, action, keys)
+            },
+        }
+    }
+
+    fn run(&mut self, doc: &mut Doc, sel: &Selection, count: Option<usize>, action: Action,
+        keys: Vec<String>,
+    ) -> Option<Selection> {
+        let pos = sel.head.clone();
+        let times = count.unwrap_or(1);
+        match action {
+            Action::Move(motion) => {
+                let target = motion_target(doc, &pos, motion, count, false);
+                match (self.mode, &self.anchor) {
+                    (VimMode::Visual, Some(anchor)) => Some(Selection::new(anchor.clone(), target)),
+                    _ => Some(Selection::caret(target)),
+                }
+            },
+            Action::Operate(op, motion) => {
+                let target = motion_target(doc, &pos, motion, count, true);
+                let range = match motion.is_blockwise() {
+                    true => Range::Blocks(blocks_between(doc, &pos, &target)),
+                    false => ordered(doc, pos, target),
+                };
+                self.operate(doc, op, range, keys)
+            },
+            Action::OperateBlocks(op) => {
+                let mut blocks = vec![doc.block_of(&pos.hash)?];
+                while blocks.len() < times {
+                    let Some(next) = doc.next_text_block(blocks.last().unwrap()) else { break };
+                    blocks.push(next);
+                }
+                self.operate(doc, op, Range::Blocks(blocks), keys)
+            },
+            Action::OperateSelection(op) => {
+                self.mode = VimMode::Normal;
+                self.anchor = None;
+                let (start, end) = doc.selection_range(sel);
+                self.operate(doc, op, Range::Text(start, end), keys)
+            },
+            Action::Other('x') => {
+                let end = motion_target(doc, &pos, Motion::Right, count, true);
+                self.operate(doc, Operator::Delete, Range::Text(pos, end), keys)
+            },
+            Action::Other(c @ ('p' | 'P')) => {
+                let caret = self.paste(doc, &pos, c == 'p', times)?;
+                self.record(keys);
+                Some(Selection::caret(caret))
+            },
+            Action::Other(c @ ('i' | 'a' | 'I' | 'A')) => {
+                let caret = match c {
+                    'a' => motion_target(doc, &pos, Motion::Right, None, true),
+                    'I' => motion_target(doc, &pos, Motion::BlockStart, None, true),
+                    'A' => motion_target(doc, &pos, Motion::BlockEnd, None, true),
+                    _ => pos,
+                };
+                self.insert(keys);
+                Some(Selection::caret(caret))
+            },
+            Action::Other(c @ ('o' | 'O')) => {
+                let block = doc.block_of(&pos.hash)?;
+                let (_, text, _) = doc.block_text(&pos);
+                let split_at = doc.pos_in_block(&block, if c == 'o' { text.len() } else { 0 })?;
+                let below = doc.apply(&Op::SplitBlock { pos: split_at }.into()).ok()?.caret?;
+                self.insert(keys);
+                // w/ `O` the block w/ the text moved down, and the caret
+                // stays in the empty one
+                Some(Selection::caret(match c {
+                    'o' => below,
+                    _ => doc.pos_in_block(&block, 0)?,
+                }))
+            },
+            Action::Other('v') => match self.mode {
+                VimMode::Visual => {
+                    self.mode = VimMode::Normal;
+                    self.anchor = None;
+                    Some(Selection::caret(pos))
+                },
+                _ => {
+                    self.mode = VimMode::Visual;
+                    self.anchor = Some(pos.clone());
+                    Some(Selection::caret(pos))
+                },
+            },
+            Action::Other('.') => self.repeat(doc, sel, times),
+            Action::Other(_) => None,
+        }
+    }
+
+    /// start insert mode, recording what's typed if it's part of a change
+    fn insert(&mut self, keys: Vec<String>) {
+        self.mode = VimMode::Insert;
+        if !self.replaying {
+            self.recording = Some(Recording { keys, typed: String::new() });
+        }
+    }
+    /// a change that's done as soon as its keys are
+    fn record(&mut self, keys: Vec<String>) {
+        if !self.replaying {
+            self.last_change = Some(Recording { keys, typed: String::new() });
+        }
+    }
+
+    fn operate(&mut self, doc: &mut Doc, op: Operator, range: Range, keys: Vec<String>,
+    ) -> Option<Selection> {
+        match range {
+            Range::Text(start, end) => {
+                self.register = Some(Register::Text(doc.text_between(&start, &end)));
+                if op == Operator::Yank { return Some(Selection::caret(start)) }
+                if start != end {
+                    doc.apply(&Op::DeleteRange { start: start.clone(), end }.into()).ok()?;
+                }
+                match op {
+                    Operator::Change => self.insert(keys),
+                    _ => self.record(keys),
+                }
+                Some(Selection::caret(start))
+            },
+            Range::Blocks(blocks) => {
+                let (first, last) = (blocks.first()?.clone(), blocks.last()?.clone());
+                self.register = Some(Register::Blocks(blocks.iter().map(|b| doc.tree(b)).collect()));
+                match op {
+                    Operator::Yank => None,
+                    // the blocks become one empty block
+                    Operator::Change => {
+                        let start = doc.pos_in_block(&first, 0)?;
+                        let end = doc.pos_in_block(&last, doc.plain_text(&last).len())?;
+                        if start != end {
+                            doc.apply(&Op::DeleteRange { start: start.clone(), end }.into()).ok()?;
+                        }
+                        self.insert(keys);
+                        Some(Selection::caret(start))
+                    },
+                    Operator::Delete => {
+                        let caret = remove_blocks(doc, &blocks)?;
+                        self.record(keys);
+                        Some(Selection::caret(caret))
+                    },
+                }
+            },
+        }
+    }
+
+    /// `p` pastes after the caret (or below the block), `P` before it (or
+    /// above the block). returns where the caret goes
+    fn paste(&self, doc: &mut Doc, pos: &Pos, after: bool, times: usize) -> Option<Pos> {
+        match self.register.as_ref()? {
+            Register::Text(text) => {
+                let at = match after {
+                    true => motion_target(doc, pos, Motion::Right, None, true),
+                    false => pos.clone(),
+                };
+                insert_text_lines(doc, &at, &text.repeat(times))
+            },
+            Register::Blocks(trees) => {
+                let block = doc.block_of(&pos.hash)?;
+                let parent = doc.parent(&block)?.to_string();
+                let index = doc.index_in_parent(&block)? + usize::from(after);
+                let above = match after {
+                    true => Some(block),
+                    false => doc.prev_text_block(&block),
+                };
+                let nodes = (0..times).flat_map(|_| trees.iter().cloned()).collect();
+                doc.apply(&Op::InsertNodes { parent, index, nodes }.into()).ok()?;
+                // the first pasted block
+                let first = match above {
+                    Some(above) => doc.next_text_block(&above),
+                    None => doc.text_blocks().into_iter().next(),
+                }?;
+                doc.pos_in_block(&first, 0)
+            },
+        }
+    }
+
+    /// `.`: the last change again, incl. anything typed after it
+    fn repeat(&mut self, doc: &mut Doc, sel: &Selection, times: usize) -> Option<Selection> {
+        let change = self.last_change.clone()?;
+        self.replaying = true;
+        let mut sel = sel.clone();
+        for _ in 0..times {
+            for key in &change.keys {
+                if let Some(new_sel) = self.key(doc, &sel, key) {
+                    sel = new_sel;
+                }
+            }
+            if self.mode == VimMode::Insert {
+                if let Some(caret) = insert_text_lines(doc, &sel.head, &change.typed) {
+                    sel = Selection::caret(caret);
+                }
+                self.mode = VimMode::Normal;
+            }
+        }
+        self.replaying = false;
+        Some(sel)
+    }
+}
+
+/// read a command from the keys so far, e.g. `2d3w` or `gg`
+fn parse(keys: &[String], mode: VimMode) -> Parsed {
+    let mut i = 0;
+    let count = read_count(keys, &mut i);
+    let Some(key) = keys.get(i) else { return Parsed::Incomplete };
+    let operator = match key.as_str() {
+        "d" | "x" if mode == VimMode::Visual => Some(Operator::Delete),
+        "d" => Some(Operator::Delete),
+        "c" => Some(Operator::Change),
+        "y" => Some(Operator::Yank),
+        _ => None,
+    };
+    let Some(operator) = operator else {
+        return match parse_motion(&keys[i..]) {
+            Some(Some(motion)) => Parsed::Done { count, action: Action::Move(motion) },
+            Some(None) => Parsed::Incomplete,
+            None => match key.chars().next() {
+                Some(c) if keys.len() == i + 1 && "xpPiaIAoOv.".contains(c) => {
+                    Parsed::Done { count, action: Action::Other(c) }
+                },
+                _ => Parsed::Invalid,
+            },
+        };
+    };
+    if mode == VimMode::Visual {
+        return Parsed::Done { count, action: Action::OperateSelection(operator) };
+    }
+    i += 1;
+    let motion_count = read_count(keys, &mut i);
+    // counts on both sides multiply, like `2d3w`
+    let count = match (count, motion_count) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(1) * b.unwrap_or(1)),
+    };
+    let Some(next) = keys.get(i) else { return Parsed::Incomplete };
+    if next == key {
+        return Parsed::Done { count, action: Action::OperateBlocks(operator) };
+    }
+    match parse_motion(&keys[i..]) {
+        // `cw` is `ce`, like in vim
+        Some(Some(Motion::WordStart)) if operator == Operator::Change => {
+            Parsed::Done { count, action: Action::Operate(operator, Motion::WordEnd) }
+        },
+        Some(Some(motion)) => Parsed::Done { count, action: Action::Operate(operator, motion) },
+        Some(None) => Parsed::Incomplete,
+        None => Parsed::Invalid,
+    }
+}
+
+/// digits at `i`, moving past them. a `0` on its own is a motion
+fn read_count(keys: &[String], i: &mut usize) -> Option<usize> {
+    let mut count: Option<usize> = None;
+    while let Some(digit) = keys.get(*i).and_then(|k| k.parse::<usize>().ok()) {
+        if count.is_none() && digit == 0 { break }
+        count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        *i += 1;
+    }
+    count
+}
+
+/// `Some(None)` if it's the start of a motion (`g`)
+fn parse_motion(keys: &[String]) -> Option<Option<Motion>> {
+    let motion = match keys.first()?.as_str() {
+        "h" => Motion::Left,
+        "l" => Motion::Right,
+        "j" => Motion::Down,
+        "k" => Motion::Up,
+        "w" => Motion::WordStart,
+        "b" => Motion::WordBack,
+        "e" => Motion::WordEnd,
+        "0" => Motion::BlockStart,
+        "$" => Motion::BlockEnd,
+        "G" => Motion::LastBlock,
+        "}" => Motion::NextTopBlock,
+        "{" => Motion::PrevTopBlock,
+        "g" => return match keys.get(1).map(|k| k.as_str()) {
+            None => Some(None),
+            Some("g") if keys.len() == 2 => Some(Some(Motion::FirstBlock)),
+            Some(_) => None,
+        },
+        _ => return None,
+    };
+    (keys.len() == 1).then_some(Some(motion))
+}
+
+/// where the motion goes from `pos`. `for_operator` keeps `w` from running
+/// into the next block, so `dw` on the last word doesn't join the blocks
+fn motion_target(doc: &Doc, pos: &Pos, motion: Motion, count: Option<usize>, for_operator: bool) -> Pos {
+    let times = count.unwrap_or(1);
+    let mut target = pos.clone();
+    match motion {
+        Motion::FirstBlock | Motion::LastBlock => {
+            let blocks = doc.text_blocks();
+            let index = match (motion, count) {
+                (_, Some(n)) => n - 1,
+                (Motion::FirstBlock, None) => 0,
+                _ => usize::MAX,
+            };
+            if let Some(block) = blocks.get(index).or(blocks.last()) {
+                target = doc.pos_in_block(block, 0).unwrap_or(target);
+            }
+        },
+        _ => for _ in 0..times {
+            target = step(doc, &target, motion);
+        },
+    }
+    if for_operator && motion == Motion::WordStart {
+        let (start_block, target_block) = (doc.block_of(&pos.hash), doc.block_of(&target.hash));
+        if start_block != target_block {
+            if let Some(block) = target_block.and_then(|b| doc.prev_text_block(&b)) {
+                target = doc.pos_in_block(&block, doc.plain_text(&block).len()).unwrap_or(target);
+            }
+        }
+    }
+    target
+}
+
+/// one step of a motion
+fn step(doc: &Doc, pos: &Pos, motion: Motion) -> Pos {
+    let (block, text, offset) = doc.block_text(pos);
+    let words: Vec<(usize, &str)> = text.split_word_bound_indices().filter(|(_, w)| is_word(w)).collect();
+    let in_block = |offset| doc.pos_in_block(&block, offset);
+    let target = match motion {
+        Motion::Left => in_block(prev_grapheme_boundary(&text, offset)),
+        Motion::Right => in_block(next_grapheme_boundary(&text, offset)),
+        Motion::BlockStart => in_block(0),
+        Motion::BlockEnd => in_block(text.len()),
+        Motion::Down | Motion::Up => {
+            let next = match motion {
+                Motion::Down => doc.next_text_block(&block),
+                _ => doc.prev_text_block(&block),
+            };
+            let Some(next) = next else { return pos.clone() };
+            // same column, or the end if the block's shorter
+            let column = text[..offset].chars().count();
+            let next_text = doc.plain_text(&next);
+            let offset = next_text.char_indices().nth(column).map_or(next_text.len(), |(i, _)| i);
+            doc.pos_in_block(&next, offset)
+        },
+        Motion::WordStart => match words.iter().find(|(i, _)| *i > offset) {
+            Some((i, _)) => in_block(*i),
+            None => match doc.next_text_block(&block) {
+                Some(next) => doc.pos_in_block(&next, first_word(doc, &next).map_or(0, |(i, _)| i)),
+                None => in_block(text.len()),
+            },
+        },
+        Motion::WordEnd => match words.iter().find(|(i, w)| i + w.len() > offset) {
+            Some((i, w)) => in_block(i + w.len()),
+            None => match doc.next_text_block(&block) {
+                Some(next) => doc.pos_in_block(&next, first_word(doc, &next).map_or(0, |(i, w)| i + w)),
+                None => in_block(text.len()),
+            },
+        },
+        Motion::WordBack => match words.iter().rfind(|(i, _)| *i < offset) {
+            Some((i, _)) => in_block(*i),
+            None => match doc.prev_text_block(&block) {
+                Some(prev) => {
+                    let prev_text = doc.plain_text(&prev);
+                    let last = prev_text.split_word_bound_indices().rfind(|(_, w)| is_word(w));
+                    doc.pos_in_block(&prev, last.map_or(0, |(i, _)| i))
+                },
+                None => in_block(0),
+            },
+        },
+        Motion::NextTopBlock | Motion::PrevTopBlock => {
+            let starts = top_block_starts(doc);
+            let top = top_block(doc, &block);
+            let current = starts.iter().position(|(t, _)| *t == top).unwrap_or(0);
+            let at_start = offset == 0 && starts.get(current).is_some_and(|(_, b)| *b == block);
+            let index = match motion {
+                Motion::NextTopBlock => Some(current + 1),
+                _ if at_start => current.checked_sub(1),
+                _ => Some(current),
+            };
+            match index.and_then(|i| starts.get(i)) {
+                Some((_, start)) => doc.pos_in_block(start, 0),
+                // past the first/last one
+                None => match motion {
+                    Motion::NextTopBlock => {
+                        let last = doc.text_blocks().pop().unwrap_or(block);
+                        doc.pos_in_block(&last, doc.plain_text(&last).len())
+                    },
+                    _ => in_block(0),
+                },
+            }
+        },
+        Motion::FirstBlock | Motion::LastBlock => Some(pos.clone()),
+    };
+    target.unwrap_or_else(|| pos.clone())
+}
+
+/// start and length of the block's first word
+fn first_word(doc: &Doc, block: &str) -> Option<(usize, usize)> {
+    let text = doc.plain_text(block);
+    text.split_word_bound_indices().find(|(_, w)| is_word(w)).map(|(i, w)| (i, w.len()))
+}
+
+/// the child of the page that the block is in (or is)
+fn top_block(doc: &Doc, block: &str) -> String {
+    let mut hash = block;
+    while let Some(parent) = doc.parent(hash) {
+        if parent == ROOT_HASH { break }
+        hash = parent;
+    }
+    hash.to_string()
+}
+/// each top level block w/ text in it, and the first text block in it
+fn top_block_starts(doc: &Doc) -> Vec<(String, String)> {
+    let mut starts: Vec<(String, String)> = Vec::new();
+    for block in doc.text_blocks() {
+        let top = top_block(doc, &block);
+        if starts.last().map(|(t, _)| t) != Some(&top) {
+            starts.push((top, block));
+        }
+    }
+    starts
+}
+
+/// the two positions in doc order
+fn ordered(doc: &Doc, a: Pos, b: Pos) -> Range {
+    match doc.cmp_pos(&a, &b).is_gt() {
+        true => Range::Text(b, a),
+        false => Range::Text(a, b),
+    }
+}
+/// the text blocks from the one w/ `a` to the one w/ `b` (either way round)
+fn blocks_between(doc: &Doc, a: &Pos, b: &Pos) -> Vec<String> {
+    let (start, end) = match doc.cmp_pos(a, b).is_gt() {
+        true => (b, a),
+        false => (a, b),
+    };
+    let (Some(first), Some(last)) = (doc.block_of(&start.hash), doc.block_of(&end.hash)) else {
+        return Vec::new()
+    };
+    let mut blocks = vec![first];
+    while *blocks.last().unwrap() != last {
+        let Some(next) = doc.next_text_block(blocks.last().unwrap()) else { break };
+        blocks.push(next);
+    }
+    blocks
+}
+
+/// remove the blocks, and any branch blocks (quotes etc) they'd leave empty.
+/// returns where the caret goes
+fn remove_blocks(doc: &mut Doc, blocks: &[String]) -> Option<Pos> {
+    let (first, last) = (blocks.first()?, blocks.last()?);
+    let after = doc.next_text_block(last).or_else(|| doc.prev_text_block(first));
+    let leaf_blocks = doc.leaf_blocks();
+    let covered = |branch: &str| leaf_blocks.iter()
+        .filter(|b| doc.is_ancestor(branch, b))
+        .all(|b| blocks.contains(b) || doc.first_text(b).is_none());
+    let mut removed: Vec<String> = Vec::new();
+    for block in blocks {
+        let mut top = block.as_str();
+        while let Some(parent) = doc.parent(top) {
+            if parent == ROOT_HASH || !covered(parent) { break }
+            top = parent;
+        }
+        if !removed.iter().any(|r| r == top) {
+            removed.push(top.to_string());
+        }
+    }
+    let mut ops = Vec::new();
+    // there has to be a block left for the caret
+    if after.is_none() {
+        ops.push(Op::InsertNodes { parent: ROOT_HASH.into(), index: 0,
+            nodes: vec![NodeTree::block(PageNodeType::TextBlock, "")] });
+    }
+    ops.push(Op::RemoveNodes { hashes: removed });
+    doc.apply(&ops.into()).ok()?;
+    let block = after.or_else(|| doc.text_blocks().into_iter().next())?;
+    doc.pos_in_block(&block, 0)
+}
+
+/// type `text` at `pos`, w/ each `\n` splitting the block. returns the end
+fn insert_text_lines(doc: &mut Doc, pos: &Pos, text: &str) -> Option<Pos> {
+    let mut caret = pos.clone();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            caret = doc.apply(&Op::SplitBlock { pos: caret }.into()).ok()?.caret?;
+        }
+        if !line.is_empty() {
+            caret = doc.apply(&Op::InsertText { pos: caret, text: line.into() }.into()).ok()?.caret?;
+        }
+    }
+    Some(caret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Editor {
+        doc: Doc,
+        vim: Vim,
+        sel: Selection,
+    }
+    impl Editor {
+        fn new(md: &str) -> Self {
+            let doc = Doc::from_markdown(md, 1);
+            let first = doc.first_text(&doc.leaf_blocks()[0]).unwrap();
+            Self { doc, vim: Vim::new(), sel: Selection::caret(Pos::new(&first, 0)) }
+        }
+        /// `<` is Escape
+        fn keys(&mut self, keys: &str) -> &mut Self {
+            for key in keys.chars() {
+                let key = if key == '<' { "Escape".to_string() } else { key.to_string() };
+                assert!(self.vim.wants(&key) || self.vim.mode == VimMode::Insert);
+                if let Some(sel) = self.vim.key(&mut self.doc, &self.sel, &key) {
+                    self.sel = sel;
+                }
+            }
+            self
+        }
+        /// typing in insert mode, like the editor would
+        fn type_text(&mut self, text: &str) -> &mut Self {
+            assert_eq!(self.vim.mode, VimMode::Insert);
+            self.sel = Selection::caret(insert_text_lines(&mut self.doc, &self.sel.head, text).unwrap());
+            self.vim.typed(text);
+            self
+        }
+        /// the caret's block, and where it is in the block's text
+        fn caret(&self) -> (String, usize) {
+            let (block, _, offset) = self.doc.block_text(&self.sel.head);
+            (self.doc.plain_text(&block), offset)
+        }
+        fn md(&self) -> String {
+            self.doc.to_markdown()
+        }
+        fn blocks(&self) -> Vec<String> {
+            self.doc.leaf_blocks().iter().map(|b| self.doc.plain_text(b)).collect()
+        }
+    }
+
+    #[test]
+    fn motions() {
+        let mut ed = Editor::new("one two three\n\n> four five\n> six\n\nseven\n");
+        assert_eq!(ed.keys("w").caret(), ("one two three".into(), 4));
+        assert_eq!(ed.keys("2w").caret(), ("four five".into(), 0));
+        assert_eq!(ed.keys("b").caret(), ("one two three".into(), 8));
+        assert_eq!(ed.keys("e").caret(), ("one two three".into(), 13));
+        assert_eq!(ed.keys("$").caret().1, 13);
+        assert_eq!(ed.keys("0").caret().1, 0);
+        assert_eq!(ed.keys("lllllj").caret(), ("four five".into(), 5));
+        assert_eq!(ed.keys("j").caret(), ("six".into(), 3));
+        assert_eq!(ed.keys("k").caret(), ("four five".into(), 3));
+        assert_eq!(ed.keys("G").caret(), ("seven".into(), 0));
+        assert_eq!(ed.keys("gg").caret(), ("one two three".into(), 0));
+        assert_eq!(ed.keys("3G").caret(), ("six".into(), 0));
+        // the quote is one top level block
+        assert_eq!(ed.keys("{").caret(), ("four five".into(), 0));
+        assert_eq!(ed.keys("{").caret(), ("one two three".into(), 0));
+        assert_eq!(ed.keys("}}").caret(), ("seven".into(), 0));
+        assert_eq!(ed.keys("}").caret(), ("seven".into(), 5));
+        // not a command, so it's dropped
+        assert_eq!(ed.keys("gqh").caret(), ("seven".into(), 4));
+    }
+
+    #[test]
+    fn operators() {
+        let mut ed = Editor::new("one two three four\n\nfive\n\nsix\n");
+        ed.keys("dw");
+        assert_eq!(ed.caret(), ("two three four".into(), 0));
+        ed.keys("2x");
+        assert_eq!(ed.caret(), ("o three four".into(), 0));
+        ed.keys("wd$");
+        assert_eq!(ed.blocks(), vec!["o ", "five", "six"]);
+        // `dw` on the last word stays in the block
+        ed.keys("0dw");
+        assert_eq!(ed.blocks(), vec!["", "five", "six"]);
+        ed.keys("jyyjp");
+        assert_eq!(ed.blocks(), vec!["", "five", "six", "five"]);
+        assert_eq!(ed.caret(), ("five".into(), 0));
+        ed.keys("gg2dd");
+        assert_eq!(ed.blocks(), vec!["six", "five"]);
+        assert_eq!(ed.caret(), ("six".into(), 0));
+        ed.keys("cw").type_text("ten");
+        ed.keys("<");
+        assert_eq!(ed.blocks(), vec!["ten", "five"]);
+        ed.keys("j0.");
+        assert_eq!(ed.blocks(), vec!["ten", "ten"]);
+        ed.keys("ggdG");
+        assert_eq!(ed.blocks(), vec![""]);
+    }
+
+    #[test]
+    fn insert_and_visual() {
+        let mut ed = Editor::new("- one\n- two\n");
+        ed.keys("o").type_text("new").keys("<");
+        assert_eq!(ed.md(), "- one\n- new\n- two\n");
+        ed.keys("ggO").type_text("top").keys("<");
+        assert_eq!(ed.md(), "- top\n- one\n- new\n- two\n");
+        ed.keys("jA").type_text("!").keys("<j.");
+        assert_eq!(ed.md(), "- top\n- one!\n- new!\n- two\n");
+        ed.keys("0vly");
+        assert_eq!(ed.vim.mode, VimMode::Normal);
+        ed.keys("$p");
+        assert_eq!(ed.caret(), ("new!n".into(), 5));
+        ed.keys("0ved");
+        assert_eq!(ed.caret(), ("!n".into(), 0));
+        ed.keys("lP");
+        assert_eq!(ed.md(), "- top\n- one!\n- !newn\n- two\n");
+    }
+}
@@ -0,0 +1,179 @@
+use serde::{Serialize, Deserialize};
+
+use super::{Doc, NodeTree, PageNodeType, Pos, Op, Transaction};
+
+// `[[note]]` links between the notes in the workspace. a link is the note's
+// title (its file name w/o `.md`), or a path to it from the workspace folder
+// for when two notes have the same title, and can go to a heading in it w/
+// `[[note#heading]]`. the backend lists the notes (see `notes.rs` there), and
+// this is the text part: what the `[[` menu offers, and which note a link
+// goes to
+
+/// a markdown file in the workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteInfo {
+    pub title: String,
+    pub path: String,
+    /// the text of each heading, in order
+    pub headings: Vec<String>,
+}
+impl NoteInfo {
+    pub fn new(path: &str, markdown: &str) -> Self {
+        let headings = Doc::from_markdown(markdown, 1).outline().into_iter().map(|e| e.text).collect();
+        Self { title: note_title(path), path: path.into(), headings }
+    }
+}
+
+/// the note's file name w/o `.md`
+pub fn note_title(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.strip_suffix(".md").unwrap_or(name).to_string()
+}
+
+/// what a link's `link` is made of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkTarget {
+    pub note: String,
+    pub heading: Option<String>,
+}
+impl LinkTarget {
+    pub fn parse(link: &str) -> Self {
+        match link.split_once('#') {
+            Some((note, heading)) => Self {
+                note: note.trim().into(),
+                heading: Some(heading.trim().to_string()).filter(|h| !h.is_empty()),
+            },
+            None => Self { note: link.trim().into(), heading: None },
+        }
+    }
+}
+
+/// which note the link goes to. the title is matched ignoring case, and a
+/// path (e.g. `ideas/todo`) has to be the end of the note's path
+pub fn resolve_link<'a>(notes: &'a [NoteInfo], link: &str) -> Option<&'a NoteInfo> {
+    let note = LinkTarget::parse(link).note.replace('\\', "/").to_lowercase();
+    let note = note.strip_suffix(".md").unwrap_or(&note);
+    if note.is_empty() { return None }
+    notes.iter().find(|n| n.title.to_lowercase() == note)
+        .or_else(|| notes.iter().find(|n| {
+            let path = n.path.replace('\\', "/").to_lowercase();
+            path.strip_suffix(".md").unwrap_or(&path).ends_with(&format!("/{}", note))
+        }))
+}
+
+/// a row in the `[[` menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkCompletion {
+    /// what goes in the link
+    pub link: String,
+    /// what the menu shows
+    pub label: String,
+}
+
+/// what the `[[` menu offers for what's been typed after the `[[`. the
+/// note titles that have it in them (the ones that start w/ it first), or
+/// once there's a `#`, the headings in that note
+pub fn link_completions(notes: &[NoteInfo], query: &str) -> Vec<LinkCompletion> {
+    if let Some((note, heading)) = query.split_once('#') {
+        let Some(note) = resolve_link(notes, note) else { return Vec::new() };
+        let heading = heading.trim().to_lowercase();
+        return note.headings.iter()
+            .filter(|h| h.to_lowercase().contains(&heading))
+            .map(|h| LinkCompletion { link: format!("{}#{}", note.title, h), label: format!("{} › {}", note.title, h) })
+            .collect();
+    }
+    let query = query.trim().to_lowercase();
+    let mut found: Vec<(bool, &NoteInfo)> = notes.iter().filter_map(|note| {
+        let title = note.title.to_lowercase();
+        title.contains(&query).then_some((title.starts_with(&query), note))
+    }).collect();
+    // stable, so otherwise they stay in the order they're in
+    found.sort_by_key(|(first, _)| !*first);
+    found.into_iter()
+        .map(|(_, note)| LinkCompletion { link: note.title.clone(), label: note.title.clone() })
+        .collect()
+}
+
+impl Doc {
+    /// the user picked `link` from the `[[` menu. `start` is where the `[[`
+    /// is, and `end` the end of what was typed after it. they become the
+    /// link (along w/ a `]]` right after, if there is one), and the caret
+    /// goes just after it
+    pub fn insert_file_link(&self, start: &Pos, end: &Pos, link: &str) -> Option<(Transaction, Pos)> {
+        if start.hash != end.hash { return None }
+        let closed = self.text(&end.hash).get(end.offset..)?.starts_with("]]");
+        let end = end.offset + if closed { 2 } else { 0 };
+        let tx = self.wrap_in_file_link(&start.hash, start.offset, end, link, link)?;
+        Some((tx, Pos::new(&start.hash, 0)))
+    }
+    /// the `start..end` of the text node becomes a link w/ `label` as its
+    /// text. like w/ typed spans, the text after it stays in `hash`
+    pub(crate) fn wrap_in_file_link(&self, hash: &str, start: usize, end: usize, link: &str, label: &str,
+    ) -> Option<Transaction> {
+        if link.is_empty() || label.is_empty() || *self.kind(hash) != PageNodeType::RawText { return None }
+        let parent = self.parent(hash)?.to_string();
+        if matches!(self.kind(&parent), PageNodeType::CodeInline | PageNodeType::FileLink
+            | PageNodeType::UrlLink) { return None }
+        let text = self.text(hash);
+        let (before, after) = (text.get(..start)?, text.get(end..)?);
+        let mut span = NodeTree::new(PageNodeType::FileLink, vec![NodeTree::text(label)]);
+        span.content.insert("link".into(), link.into());
+        let mut nodes = Vec::new();
+        if !before.is_empty() {
+            nodes.push(NodeTree::text(before));
+        }
+        nodes.push(span);
+        Some(Transaction::from(vec![
+            Op::SetText { hash: hash.to_string(), text: after.to_string() },
+            Op::InsertNodes { parent, index: self.index_in_parent(hash)?, nodes },
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notes() -> Vec<NoteInfo> {
+        vec![
+            NoteInfo::new("/w/Ideas.md", "# Ideas\nsome\n## Later\n"),
+            NoteInfo::new("/w/old/ideas.md", ""),
+            NoteInfo::new("/w/Reading list.md", "## Books\n## Ideas for later\n"),
+        ]
+    }
+
+    #[test]
+    fn completes_and_resolves() {
+        let notes = notes();
+        assert_eq!(notes[2].title, "Reading list");
+        assert_eq!(notes[0].headings, vec!["Ideas", "Later"]);
+        let links = |query| link_completions(&notes, query).into_iter().map(|c| c.link).collect::<Vec<_>>();
+        assert_eq!(links("ide"), vec!["Ideas", "ideas"]);
+        // the titles that start w/ it first
+        assert_eq!(links("i"), vec!["Ideas", "ideas", "Reading list"]);
+        assert_eq!(links("reading list#idea"), vec!["Reading list#Ideas for later"]);
+        assert!(links("nope#").is_empty());
+
+        assert_eq!(resolve_link(&notes, "IDEAS#Later").map(|n| n.path.as_str()), Some("/w/Ideas.md"));
+        assert_eq!(resolve_link(&notes, "old/ideas").map(|n| n.path.as_str()), Some("/w/old/ideas.md"));
+        assert!(resolve_link(&notes, "missing").is_none());
+        assert_eq!(LinkTarget::parse("Ideas#Later"),
+            LinkTarget { note: "Ideas".into(), heading: Some("Later".into()) });
+    }
+
+    #[test]
+    fn inserts_link() {
+        // not from markdown, or the `[[rea]]` would already be a link
+        let mut doc = Doc::from_tree(NodeTree::new(PageNodeType::Page, vec![
+            NodeTree::block(PageNodeType::TextBlock, "see [[rea]] here"),
+        ]), 1);
+        let block = doc.leaf_blocks()[0].clone();
+        let text = doc.first_text(&block).unwrap();
+        let (tx, caret) = doc.insert_file_link(&Pos::new(&text, 4), &Pos::new(&text, 9),
+            "Reading list#Books").unwrap();
+        doc.apply(&tx).unwrap();
+        assert_eq!(doc.to_markdown(), "see [[Reading list#Books]] here\n");
+        assert_eq!(caret, Pos::new(&text, 0));
+        assert_eq!(doc.text(&text), " here");
+    }
+}
@@ -0,0 +1,102 @@
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+
+use super::FindOptions;
+
+// find & replace across every markdown file in the workspace (the folder the
+// open file is in). the backend does the files, this is the text part. it
+// goes line by line, so like the find bar (which stays w/in a block) a match
+// can't go across lines
+
+/// what to find and what to replace it w/
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceReplace {
+    pub query: String,
+    pub replacement: String,
+    pub options: FindOptions,
+}
+
+/// a line the replace would change, for the preview
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineChange {
+    /// counting from 1
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// the lines the replace would change in a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChanges {
+    pub path: String,
+    pub lines: Vec<LineChange>,
+}
+
+impl WorkspaceReplace {
+    pub fn finder(&self) -> Result<Regex, String> {
+        if self.query.is_empty() { return Err("nothing to find".into()) }
+        self.options.compile(&self.query)
+    }
+    /// the text w/ every match replaced, and the lines that changed. line
+    /// endings are kept as they were
+    pub fn replace_lines(&self, finder: &Regex, text: &str) -> (String, Vec<LineChange>) {
+        let mut new_text = String::with_capacity(text.len());
+        let mut changes = Vec::new();
+        for (i, line) in text.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let ending = &line[content.len()..];
+            let after = finder.replace_all(content, |caps: &Captures| {
+                let m = caps.get(0).unwrap();
+                // same as the find bar, empty matches don't count
+                if m.as_str().is_empty() { return String::new() }
+                match self.options.regex {
+                    true => {
+                        let mut new = String::new();
+                        caps.expand(&self.replacement, &mut new);
+                        new
+                    },
+                    false => self.replacement.clone(),
+                }
+            });
+            if after != content {
+                changes.push(LineChange { line: i + 1, before: content.to_string(), after: after.to_string() });
+            }
+            new_text.push_str(&after);
+            new_text.push_str(ending);
+        }
+        (new_text, changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_by_line() {
+        let replace = WorkspaceReplace {
+            query: "colour".into(),
+            replacement: "color".into(),
+            options: FindOptions { whole_word: true, ..Default::default() },
+        };
+        let finder = replace.finder().unwrap();
+        let (text, changes) = replace.replace_lines(&finder, "# Colour\r\ncolours\nsome colour, colour\n");
+        assert_eq!(text, "# color\r\ncolours\nsome color, color\n");
+        assert_eq!(changes, vec![
+            LineChange { line: 1, before: "# Colour".into(), after: "# color".into() },
+            LineChange { line: 3, before: "some colour, colour".into(), after: "some color, color".into() },
+        ]);
+
+        let replace = WorkspaceReplace {
+            query: r"\[\[(\w+)\]\]".into(),
+            replacement: "[$1]($1.md)".into(),
+            options: FindOptions { regex: true, ..Default::default() },
+        };
+        let finder = replace.finder().unwrap();
+        assert_eq!(replace.replace_lines(&finder, "see [[notes]]").0, "see [notes](notes.md)");
+        // `$` is only special in a regex replace
+        let literal = WorkspaceReplace { query: "a".into(), replacement: "$1".into(), ..Default::default() };
+        assert_eq!(literal.replace_lines(&literal.finder().unwrap(), "ba").0, "b$1");
+        assert!(WorkspaceReplace::default().finder().is_err());
+    }
+}
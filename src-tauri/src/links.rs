@@ -0,0 +1,60 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::Mutex, time::SystemTime};
+use tauri::{AppHandle, State};
+use src_model::{Doc, SyntaxProfile, IndexedNote, Backlinks, Mention, find_backlinks, note_title,
+    tree_to_markdown_with};
+
+use crate::{load_syntax, seed, workspace::markdown_files};
+
+// the link index: every note's links (and text, for the unlinked mentions),
+// see `backlinks.rs` in the model. it's kept for as long as the app's open,
+// and a file is only read again once it's been changed
+
+/// the notes by path, w/ when each was last changed
+#[derive(Default)]
+pub struct LinkIndex(Mutex<HashMap<PathBuf, (SystemTime, IndexedNote)>>);
+
+impl LinkIndex {
+    /// index the files in `root` that are new or have changed since, and
+    /// forget the ones that are gone. returns the notes in `root`
+    fn update(&self, root: &Path, profile: &SyntaxProfile) -> Vec<IndexedNote> {
+        let mut index = self.0.lock().unwrap();
+        let files = markdown_files(root);
+        index.retain(|path, _| !path.starts_with(root) || files.contains(path));
+        files.iter().filter_map(|path| {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+            if !index.get(path).is_some_and(|(indexed, _)| *indexed == modified) {
+                let md = fs::read_to_string(path).ok()?;
+                let doc = Doc::from_markdown_with(&md, seed(), profile);
+                index.insert(path.clone(), (modified, IndexedNote::new(&path.to_string_lossy(), &doc)));
+            }
+            index.get(path).map(|(_, note)| note.clone())
+        }).collect()
+    }
+}
+
+/// the notes that link to the one at `path`, and the ones that mention it
+/// w/o a link
+#[tauri::command]
+pub fn backlinks(app: AppHandle, index: State<'_, LinkIndex>, root: &str, path: &str,
+) -> Result<Backlinks, String> {
+    let profile = SyntaxProfile::with_user(&load_syntax(app)?);
+    let notes = index.update(Path::new(root), &profile);
+    Ok(find_backlinks(&notes, path))
+}
+
+/// turn a mention of the note at `target` into a link to it
+#[tauri::command]
+pub fn link_mention(app: AppHandle, root: &str, mention: Mention, target: &str) -> Result<(), String> {
+    let file = PathBuf::from(&mention.path);
+    // only files in the workspace can be touched
+    if !markdown_files(Path::new(root)).contains(&file) {
+        return Err(format!("{} isn't in the workspace", file.display()));
+    }
+    let profile = SyntaxProfile::with_user(&load_syntax(app)?);
+    let md = fs::read_to_string(&file).map_err(|e| e.to_string())?;
+    let mut doc = Doc::from_markdown_with(&md, seed(), &profile);
+    let tx = doc.link_mention(&mention, &note_title(target))
+        .ok_or("the note has changed since, so the mention wasn't linked")?;
+    doc.apply(&tx).map_err(|e| e.to_string())?;
+    fs::write(&file, tree_to_markdown_with(&doc.root_tree(), &profile)).map_err(|e| e.to_string())
+}
@@ -3,7 +3,14 @@
     windows_subsystem = "windows"
 )]
 
-use tauri::{Manager, Window};
+use std::{fs, collections::HashMap, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+use tauri::{AppHandle, Manager, Window};
+use src_model::{Doc, NodeTree, Fold, KeyBinding, SyntaxProfile, Template, tree_to_markdown_with};
+
+mod workspace;
+mod notes;
+mod links;
+mod rename;
 
 #[derive(Clone, serde::Serialize)]
 struct GreetEvent {
@@ -46,9 +53,109 @@ fn emit_event(window: Window, num: u16) {
         .unwrap();
 }
 
+/// read a markdown file into a page tree for the UI to render
+#[tauri::command]
+fn open_page(app: AppHandle, path: &str) -> Result<NodeTree, String> {
+    let md = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let profile = SyntaxProfile::with_user(&load_syntax(app)?);
+    // going through a `Doc` so every node comes back with a unique hash
+    Ok(Doc::from_markdown_with(&md, seed(), &profile).root_tree())
+}
+
+#[tauri::command]
+fn save_page(app: AppHandle, path: &str, page: NodeTree) -> Result<(), String> {
+    let profile = SyntaxProfile::with_user(&load_syntax(app)?);
+    fs::write(path, tree_to_markdown_with(&page, &profile)).map_err(|e| e.to_string())
+}
+
+/// the user's shortcuts from `keymap.json` in the app's config dir, e.g.
+/// `[{ "key": "Mod-Shift-7", "command": "select_all" }]`. these go on top of 
+/// the defaults (see `Keymap::with_overrides`), so no file means no changes
+#[tauri::command]
+fn load_keymap(app: AppHandle) -> Result<Vec<KeyBinding>, String> {
+    let Some(dir) = app.path_resolver().app_config_dir() else { return Ok(Vec::new()) };
+    match fs::read_to_string(dir.join("keymap.json")) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| format!("keymap.json: {}", e)),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// the user's own markdown syntax from `syntax.json` in the app's config 
+/// dir, e.g. `{ "spans": [{ "delim": "/", "kind": "Italic" }] }`. this goes on 
+/// top of standard markdown (see `SyntaxProfile::with_user`)
+#[tauri::command]
+fn load_syntax(app: AppHandle) -> Result<SyntaxProfile, String> {
+    let Some(dir) = app.path_resolver().app_config_dir() else { return Ok(SyntaxProfile::default()) };
+    match fs::read_to_string(dir.join("syntax.json")) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| format!("syntax.json: {}", e)),
+        Err(_) => Ok(SyntaxProfile::default()),
+    }
+}
+
+/// the markdown files in the `templates` folder in the app's config dir, for 
+/// the slash menu
+#[tauri::command]
+fn load_templates(app: AppHandle) -> Vec<Template> {
+    let Some(dir) = app.path_resolver().app_config_dir() else { return Vec::new() };
+    let Ok(entries) = fs::read_dir(dir.join("templates")) else { return Vec::new() };
+    let mut templates: Vec<Template> = entries.filter_map(|entry| {
+        let path = entry.ok()?.path();
+        if path.extension()? != "md" { return None }
+        Some(Template {
+            name: path.file_stem()?.to_string_lossy().into_owned(),
+            markdown: fs::read_to_string(&path).ok()?,
+        })
+    }).collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// `folds.json` in the app's data dir, which has the folds for every file 
+/// that's had any, by path
+fn folds_file(app: &AppHandle) -> Option<PathBuf> {
+    Some(app.path_resolver().app_data_dir()?.join("folds.json"))
+}
+fn read_folds(app: &AppHandle) -> HashMap<String, Vec<Fold>> {
+    folds_file(app).and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// the folds saved for a file (see `Fold`)
+#[tauri::command]
+fn load_folds(app: AppHandle, path: &str) -> Vec<Fold> {
+    read_folds(&app).remove(path).unwrap_or_default()
+}
+
+#[tauri::command]
+fn save_folds(app: AppHandle, path: &str, folds: Vec<Fold>) -> Result<(), String> {
+    let Some(file) = folds_file(&app) else { return Err("no app data dir".into()) };
+    let mut all = read_folds(&app);
+    match folds.is_empty() {
+        true => all.remove(path),
+        false => all.insert(path.to_string(), folds),
+    };
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(&all).map_err(|e| e.to_string())?;
+    fs::write(file, json).map_err(|e| e.to_string())
+}
+
+/// seed for the hash generator. doesn't need to be good, just different each 
+/// time
+fn seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
+
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![greet, emit_event])
+        .manage(links::LinkIndex::default())
+        .invoke_handler(tauri::generate_handler![greet, emit_event, open_page, save_page, load_keymap, 
+            load_syntax, load_templates, load_folds, save_folds, workspace::preview_workspace_replace, 
+            workspace::apply_workspace_replace, workspace::undo_workspace_replace, notes::list_notes, 
+            notes::create_note, links::backlinks, links::link_mention, rename::rename_note])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,40 @@
+use std::{fs, io::Write, path::{Component, Path, PathBuf}};
+use src_model::{NoteInfo, LinkTarget};
+
+use crate::workspace::markdown_files;
+
+// the notes in the workspace, for `[[note]]` links (see `wikilink.rs` in the
+// model): listing them for the `[[` menu and for following a link, and making
+// the note a link goes to if it isn't there yet
+
+/// every note in the workspace w/ its headings
+#[tauri::command]
+pub fn list_notes(root: &str) -> Vec<NoteInfo> {
+    markdown_files(Path::new(root)).into_iter().filter_map(|path| {
+        let markdown = fs::read_to_string(&path).ok()?;
+        Some(NoteInfo::new(&path.to_string_lossy(), &markdown))
+    }).collect()
+}
+
+/// make the note a link goes to, w/ its title as a heading. returns its path.
+/// a link w/ a path in it (e.g. `ideas/todo`) makes it in that folder, but
+/// never outside the workspace, and a note that's already there isn't
+/// touched
+#[tauri::command]
+pub fn create_note(root: &str, link: &str) -> Result<String, String> {
+    let note = LinkTarget::parse(link).note;
+    // not `with_extension`, which would take the `.2 notes` off `v1.2 notes`
+    let relative = PathBuf::from(format!("{}.md", note.strip_suffix(".md").unwrap_or(&note)));
+    if note.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(format!("can't make a note called {:?}", note));
+    }
+    let path = Path::new(root).join(&relative);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let title = relative.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    file.write_all(format!("# {}\n", title).as_bytes()).map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().into_owned())
+}
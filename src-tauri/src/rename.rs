@@ -0,0 +1,76 @@
+use std::{fs, io::Write, path::{Component, Path, PathBuf}};
+use src_model::{NoteInfo, NoteRename, RenameReport, note_title};
+
+use crate::workspace::{Rewrite, markdown_files, rewrite_all, paths_of};
+
+// renaming/moving a note w/ every link to it (see `rename.rs` in the model).
+// the notes w/ changed links are all rewritten together first (see
+// `rewrite_all`), then the note is moved. if the move fails the links are
+// put back, so it's all done or none of it is
+
+/// `to` has to be a new markdown file in the workspace
+fn check_new_path(root: &Path, to: &Path) -> Result<(), String> {
+    let bad = || Err(format!("can't move the note to {}", to.display()));
+    let Ok(relative) = to.strip_prefix(root) else { return bad() };
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) { return bad() }
+    if to.extension() != Some("md".as_ref()) { return bad() }
+    if to.exists() { return Err(format!("{} is already there", to.display())) }
+    Ok(())
+}
+
+/// move the note at `from` to `to` and change the links to it. the links
+/// that might be to it but might not (see `AmbiguousLink`) are left as they
+/// are and reported
+#[tauri::command]
+pub fn rename_note(root: &str, from: &str, to: &str) -> Result<RenameReport, String> {
+    let (root_path, from_path, to_path) = (Path::new(root), PathBuf::from(from), PathBuf::from(to));
+    let files = markdown_files(root_path);
+    if !files.contains(&from_path) {
+        return Err(format!("{} isn't a note in the workspace", from_path.display()));
+    }
+    check_new_path(root_path, &to_path)?;
+
+    let notes: Vec<NoteInfo> = files.iter().map(|path| {
+        let path = path.to_string_lossy();
+        NoteInfo { title: note_title(&path), path: path.into_owned(), headings: Vec::new() }
+    }).collect();
+    let rename = NoteRename { root: root.into(), from: from.into(), to: to.into() };
+    let mut report = RenameReport::default();
+    let mut rewrites = Vec::new();
+    let mut moved = None;
+    for path in files {
+        let before = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let (after, ambiguous) = rename.rewrite_links(&notes, &path.to_string_lossy(), &before);
+        report.ambiguous.extend(ambiguous);
+        if path == from_path {
+            moved = Some((before != after, after));
+        } else if before != after {
+            rewrites.push(Rewrite { path, before, after });
+        }
+    }
+    let Some((moved_changed, moved_text)) = moved else { return Err("the note went missing".into()) };
+
+    rewrite_all(&rewrites, false)?;
+    let move_note = || -> Result<(), String> {
+        if let Some(dir) = to_path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let mut file = fs::OpenOptions::new().write(true).create_new(true).open(&to_path)
+            .map_err(|e| format!("{}: {}", to_path.display(), e))?;
+        if let Err(err) = file.write_all(moved_text.as_bytes()).and_then(|_| fs::remove_file(&from_path)) {
+            _ = fs::remove_file(&to_path);
+            return Err(format!("{}: {}", from_path.display(), err));
+        }
+        Ok(())
+    };
+    if let Err(err) = move_note() {
+        // put the links back
+        _ = rewrite_all(&rewrites, true);
+        return Err(err);
+    }
+    report.changed = paths_of(&rewrites);
+    if moved_changed {
+        report.changed.push(to.into());
+    }
+    Ok(report)
+}
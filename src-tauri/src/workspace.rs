@@ -0,0 +1,151 @@
+use std::{fs, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+use serde::{Serialize, Deserialize};
+use tauri::AppHandle;
+use src_model::{WorkspaceReplace, FileChanges};
+
+// find & replace across every markdown file in the workspace (see
+// `WorkspaceReplace`). the preview doesn't touch anything. applying it
+// rewrites the files all together or not at all, and keeps a backup in the
+// app's data dir first so it can be undone
+
+/// every markdown file under `dir`, in order. hidden folders (e.g. `.git`)
+/// are skipped
+pub fn markdown_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else { return files };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') { continue }
+        if path.is_dir() {
+            files.extend(markdown_files(&path));
+        } else if path.extension().is_some_and(|e| e == "md") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+/// a file the replace rewrote, w/ what was in it before and after
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Rewrite {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+pub(crate) fn paths_of(rewrites: &[Rewrite]) -> Vec<String> {
+    rewrites.iter().map(|r| r.path.to_string_lossy().into_owned()).collect()
+}
+
+/// what undoing the last replace did
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoneReplace {
+    pub restored: Vec<String>,
+    /// files that have changed since, so putting the old text back would
+    /// lose that
+    pub skipped: Vec<String>,
+}
+
+/// the backups are `<time>.json` files w/ the `Rewrite`s in them, so the
+/// newest is last by name
+fn backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path_resolver().app_data_dir().ok_or("no app data dir")?;
+    Ok(dir.join("replace_backups"))
+}
+
+/// write each file's `after` (or `before` if `undo`) next to it, then move
+/// them all over the real files, so a failed write leaves them all as they
+/// were
+pub(crate) fn rewrite_all(rewrites: &[Rewrite], undo: bool) -> Result<(), String> {
+    let text = |r: &Rewrite| if undo { r.before.clone() } else { r.after.clone() };
+    let temps: Vec<PathBuf> = rewrites.iter()
+        .map(|r| r.path.with_extension("md.replacing"))
+        .collect();
+    for (rewrite, temp) in rewrites.iter().zip(&temps) {
+        if let Err(err) = fs::write(temp, text(rewrite)) {
+            for temp in &temps {
+                _ = fs::remove_file(temp);
+            }
+            return Err(format!("{}: {}", rewrite.path.display(), err));
+        }
+    }
+    for (i, (rewrite, temp)) in rewrites.iter().zip(&temps).enumerate() {
+        if let Err(err) = fs::rename(temp, &rewrite.path) {
+            // put back the ones already moved
+            for done in &rewrites[..i] {
+                _ = fs::write(&done.path, if undo { &done.after } else { &done.before });
+            }
+            for temp in &temps[i..] {
+                _ = fs::remove_file(temp);
+            }
+            return Err(format!("{}: {}", rewrite.path.display(), err));
+        }
+    }
+    Ok(())
+}
+
+/// the lines that would change in each file. files w/ no matches are left
+/// out
+#[tauri::command]
+pub fn preview_workspace_replace(root: &str, replace: WorkspaceReplace) -> Result<Vec<FileChanges>, String> {
+    let finder = replace.finder()?;
+    Ok(markdown_files(Path::new(root)).into_iter().filter_map(|path| {
+        let text = fs::read_to_string(&path).ok()?;
+        let (_, lines) = replace.replace_lines(&finder, &text);
+        if lines.is_empty() { return None }
+        Some(FileChanges { path: path.to_string_lossy().into_owned(), lines })
+    }).collect())
+}
+
+/// do the replace in the `paths` (the files ticked in the preview). returns
+/// the files that changed
+#[tauri::command]
+pub fn apply_workspace_replace(app: AppHandle, root: &str, replace: WorkspaceReplace, paths: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let finder = replace.finder()?;
+    // the files are read again, so this is right even if one changed since
+    // the preview. only files in the workspace can be touched
+    let rewrites: Vec<Rewrite> = markdown_files(Path::new(root)).into_iter()
+        .filter(|path| paths.iter().any(|p| Path::new(p) == path.as_path()))
+        .map(|path| -> Result<Rewrite, String> {
+            let before = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let (after, _) = replace.replace_lines(&finder, &before);
+            Ok(Rewrite { path, before, after })
+        })
+        .filter(|r| !matches!(r, Ok(r) if r.before == r.after))
+        .collect::<Result<_, String>>()?;
+    if rewrites.is_empty() { return Ok(Vec::new()) }
+
+    let dir = backups_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let backup = dir.join(format!("{:020}.json", time));
+    let json = serde_json::to_string(&rewrites).map_err(|e| e.to_string())?;
+    fs::write(&backup, json).map_err(|e| e.to_string())?;
+    if let Err(err) = rewrite_all(&rewrites, false) {
+        _ = fs::remove_file(&backup);
+        return Err(err);
+    }
+    Ok(paths_of(&rewrites))
+}
+
+/// put back the files from the last replace that hasn't been undone yet
+#[tauri::command]
+pub fn undo_workspace_replace(app: AppHandle) -> Result<UndoneReplace, String> {
+    let dir = backups_dir(&app)?;
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir).map_err(|_| "nothing to undo")?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    backups.sort();
+    let backup = backups.pop().ok_or("nothing to undo")?;
+    let json = fs::read_to_string(&backup).map_err(|e| e.to_string())?;
+    let rewrites: Vec<Rewrite> = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    let (unchanged, changed): (Vec<Rewrite>, Vec<Rewrite>) = rewrites.into_iter()
+        .partition(|r| fs::read_to_string(&r.path).is_ok_and(|text| text == r.after));
+    rewrite_all(&unchanged, true)?;
+    fs::remove_file(&backup).map_err(|e| e.to_string())?;
+    Ok(UndoneReplace { restored: paths_of(&unchanged), skipped: paths_of(&changed) })
+}
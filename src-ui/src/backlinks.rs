@@ -0,0 +1,229 @@
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, create_rw_signal, spawn_local, document, 
+    JsCast, UntrackedGettableSignal};
+use serde::Serialize;
+use tauri_sys::tauri;
+use web_sys::{Element, EventTarget, MouseEvent};
+
+use super::{PageView, Page, Backlinks, Mention, load_file, workspace_dir};
+
+// the backlinks panel (Mod-Shift-B): the notes that link to the open one,
+// each w/ the sentence the link is in, and below them the notes that mention
+// its title w/o a link, each w/ a button to make it one. the backend keeps
+// the links indexed (see `links.rs` there). the panel is filled again when
+// it's opened and whenever another note is
+
+#[derive(Debug, Clone, Default)]
+pub struct BacklinksPanel {
+    pub open: bool,
+    pub found: Backlinks,
+    /// e.g. what went wrong
+    pub status: String,
+}
+
+/// what a click in the panel does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BacklinksClick {
+    /// open the note at the path
+    Open(String),
+    /// link the mention at the index
+    Link(usize),
+    Close,
+}
+
+#[derive(Serialize)]
+struct BacklinksArgs {
+    root: String,
+    path: String,
+}
+#[derive(Serialize)]
+struct LinkMentionArgs {
+    root: String,
+    mention: Mention,
+    target: String,
+}
+
+pub fn toggle_backlinks(panel: RwSignal<BacklinksPanel>, path: RwSignal<Option<String>>,
+    after: impl Fn() + 'static,
+) {
+    panel.update_untracked(|p| p.open = !p.open);
+    after();
+    refresh_backlinks(panel, path, after);
+}
+
+/// ask the backend for the open note's backlinks again, if the panel's open.
+/// `after` runs once they're in
+pub fn refresh_backlinks(panel: RwSignal<BacklinksPanel>, path: RwSignal<Option<String>>,
+    after: impl Fn() + 'static,
+) {
+    if !panel.get_untracked().open { return }
+    let (Some(root), Some(file)) = (workspace_dir(path), path.get_untracked()) else {
+        panel.update_untracked(|p| {
+            p.found = Backlinks::default();
+            p.status = "Open a file first, its folder is the workspace".into();
+        });
+        return after();
+    };
+    spawn_local(async move {
+        let args = BacklinksArgs { root, path: file };
+        let (found, status) = match tauri::invoke::<_, Backlinks>("backlinks", &args).await {
+            Ok(found) => (found, String::new()),
+            Err(err) => (Backlinks::default(), format!("{:?}", err)),
+        };
+        panel.update_untracked(|p| {
+            p.found = found;
+            p.status = status;
+        });
+        after();
+    });
+}
+
+/// turn the mention into a link, then list the backlinks again (so it's
+/// one of the links now)
+pub fn link_mention(panel: RwSignal<BacklinksPanel>, path: RwSignal<Option<String>>, index: usize,
+    after: impl Fn() + 'static,
+) {
+    let Some(mention) = panel.get_untracked().found.mentions.get(index).cloned() else { return };
+    let (Some(root), Some(target)) = (workspace_dir(path), path.get_untracked()) else { return };
+    spawn_local(async move {
+        let args = LinkMentionArgs { root, mention, target };
+        if let Err(err) = tauri::invoke::<_, ()>("link_mention", &args).await {
+            log!("COULDN'T LINK MENTION: {:?}", err);
+            panel.update_untracked(|p| p.status = format!("{:?}", err));
+        }
+        refresh_backlinks(panel, path, after);
+    });
+}
+
+/// open the note a backlink or mention is in. `after` runs once it's open
+pub fn open_backlink(cx: Scope, page_data: RwSignal<Page>, page_elem: Element,
+    path: RwSignal<Option<String>>, file: String, after: impl Fn() + 'static,
+) {
+    spawn_local(async move {
+        load_file(cx, page_data, &page_elem, path, file).await;
+        after();
+    });
+}
+
+/// what was clicked, by the attributes of the row or button `target` is in
+pub fn backlinks_click_at(target: Option<EventTarget>) -> Option<BacklinksClick> {
+    let elem = target.and_then(|t| t.dyn_into::<Element>().ok())?;
+    if let Some(button) = elem.closest("button").ok().flatten() {
+        return match button.get_attribute("action")?.as_str() {
+            "close" => Some(BacklinksClick::Close),
+            "link" => button.get_attribute("data-index")
+                .and_then(|i| i.parse().ok())
+                .map(BacklinksClick::Link),
+            _ => None,
+        };
+    }
+    let row = elem.closest("[data-path]").ok().flatten()?;
+    row.get_attribute("data-path").map(BacklinksClick::Open)
+}
+
+fn create_row(title: &str, context: &str, path: &str) -> Element {
+    let row = document().create_element("div").unwrap();
+    row.set_attribute("data-path", path).unwrap();
+    row.set_attribute("style", "padding: 4px 0; cursor: pointer").unwrap();
+    let title_elem = document().create_element("div").unwrap();
+    title_elem.set_attribute("style", "font-weight: bold").unwrap();
+    title_elem.set_text_content(Some(title));
+    let context_elem = document().create_element("div").unwrap();
+    context_elem.set_attribute("style", "color: #555").unwrap();
+    context_elem.set_text_content(Some(context));
+    row.append_child(&title_elem).unwrap();
+    row.append_child(&context_elem).unwrap();
+    row
+}
+
+fn create_heading(text: &str) -> Element {
+    let heading = document().create_element("div").unwrap();
+    heading.set_attribute("style", "margin-top: 6px; font-size: smaller; color: gray").unwrap();
+    heading.set_text_content(Some(text));
+    heading
+}
+
+/// show/hide the panel, w/ the backlinks and mentions in it
+pub fn show_backlinks(panel: RwSignal<BacklinksPanel>, panel_elem: &Element) {
+    let state = panel.get_untracked();
+    if !state.open {
+        panel_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    }
+    panel_elem.set_attribute("style", "display: block; position: fixed; bottom: 8px; right: 8px; \
+        width: 280px; max-height: 40vh; overflow-y: auto; padding: 6px; background: white; \
+        border: 1px solid #ccc; border-radius: 4px; z-index: 10").unwrap();
+    panel_elem.set_inner_html("");
+    let close = document().create_element("button").unwrap();
+    close.set_attribute("action", "close").unwrap();
+    close.set_attribute("title", "Close").unwrap();
+    close.set_attribute("style", "float: right").unwrap();
+    close.set_text_content(Some("×"));
+    panel_elem.append_child(&close).unwrap();
+    if !state.status.is_empty() {
+        panel_elem.append_child(&create_heading(&state.status)).unwrap();
+    }
+    panel_elem.append_child(&create_heading(&format!("Linked from ({})", state.found.links.len()))).unwrap();
+    for link in &state.found.links {
+        panel_elem.append_child(&create_row(&link.title, &link.context, &link.path)).unwrap();
+    }
+    let mentions = format!("Unlinked mentions ({})", state.found.mentions.len());
+    panel_elem.append_child(&create_heading(&mentions)).unwrap();
+    for (i, mention) in state.found.mentions.iter().enumerate() {
+        let row = create_row(&mention.title, &mention.context, &mention.path);
+        let button = document().create_element("button").unwrap();
+        button.set_attribute("action", "link").unwrap();
+        button.set_attribute("data-index", &i.to_string()).unwrap();
+        button.set_text_content(Some("Link"));
+        row.append_child(&button).unwrap();
+        panel_elem.append_child(&row).unwrap();
+    }
+}
+
+/// the panel's state and elem, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct BacklinksView {
+    pub panel: RwSignal<BacklinksPanel>,
+    pub panel_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl BacklinksView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            panel: create_rw_signal(cx, BacklinksPanel::default()),
+            panel_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self) {
+        if let Some(panel_elem) = self.panel_ref.get() {
+            show_backlinks(self.panel, panel_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn toggle(&self, page: PageView) {
+        let view = *self;
+        toggle_backlinks(self.panel, page.file_path, move || view.redraw());
+    }
+
+    /// once another file is open
+    pub fn refresh(&self, page: PageView) {
+        let view = *self;
+        refresh_backlinks(self.panel, page.file_path, move || view.redraw());
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, after_open: impl Fn() + 'static) {
+        let Some(click) = backlinks_click_at(event.target()) else { return };
+        // keep the focus (and caret) in the page
+        event.prevent_default();
+        let view = *self;
+        match click {
+            BacklinksClick::Open(file) => {
+                let Some(page_elem) = page.page_elem() else { return };
+                open_backlink(page.cx, page.page_data, page_elem, page.file_path, file, after_open);
+            },
+            BacklinksClick::Link(index) => link_mention(self.panel, page.file_path, index, 
+                move || view.redraw()),
+            BacklinksClick::Close => self.toggle(page),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use leptos::{log, Scope, RwSignal, document, JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use web_sys::{Element, KeyboardEvent};
+
+use super::{Page, Pos, BlockSelection, PageNodeType, Command, ReadDoc, EditDoc, HashToNode,
+    selected_range, set_caret, jump_to_block, edit_blocks};
+
+// block selection mode (see `BlockSelection` in the model). Escape (or
+// shift+clicking a block's handle) selects the blocks the text selection is
+// in, then the arrow keys extend it over blocks. the selected blocks get a
+// `block-selected` attribute to highlight them, and the text selection is
+// taken out of the DOM so typing can't edit them. in the mode:
+// - Backspace/Delete deletes them
+// - Tab/Shift+Tab indents/outdents them
+// - 0 turns them into text, 1-5 into headings, `-` bullets, `[` checkboxes
+// - `>` wraps them in a quote
+// - copy/cut gives them as markdown (see `process_copy`)
+// - keys w/ Cmd/Ctrl/Alt go to the keymap as usual, e.g. Alt+Up moves them
+// - Escape or a click goes back to the text selection
+
+fn block_selection_signal(page_data: RwSignal<Page>) -> RwSignal<Option<BlockSelection>> {
+    page_data.update_returning_untracked(|p| p.block_selection).unwrap()
+}
+
+/// the selected blocks, if in block selection mode
+pub fn selected_block_hashes(page_data: RwSignal<Page>) -> Option<Vec<String>> {
+    let sel = block_selection_signal(page_data).get_untracked()?;
+    let blocks = page_data.read_doc(|d| d.block_selection_blocks(&sel));
+    (!blocks.is_empty()).then_some(blocks)
+}
+
+/// select the blocks the text selection is in
+fn start_block_selection(page_data: RwSignal<Page>) -> bool {
+    let Some((start, end)) = selected_range(page_data) else { return false };
+    let blocks = page_data.read_doc(|d| (d.block_of(&start.hash), d.block_of(&end.hash)));
+    let (Some(anchor), Some(head)) = blocks else { return false };
+    block_selection_signal(page_data).set_untracked(Some(BlockSelection { anchor, head }));
+    true
+}
+
+/// shift+click on a block's handle. selects from the block already selected
+/// (or the caret's block) to this one
+pub fn select_to_block(page_data: RwSignal<Page>, hash: &str) {
+    let signal = block_selection_signal(page_data);
+    let anchor = signal.get_untracked().map(|s| s.anchor)
+        .or_else(|| {
+            let (start, _) = selected_range(page_data)?;
+            page_data.read_doc(|d| d.block_of(&start.hash))
+        })
+        .unwrap_or_else(|| hash.to_string());
+    signal.set_untracked(Some(BlockSelection { anchor, head: hash.to_string() }));
+}
+
+/// back to the text selection. w/ `caret` it goes at the start of the block
+/// the head was on, otherwise whatever ends the mode (e.g. a click) puts it
+pub fn end_block_selection(page_data: RwSignal<Page>, caret: bool) {
+    let signal = block_selection_signal(page_data);
+    let Some(sel) = signal.get_untracked() else { return };
+    signal.set_untracked(None);
+    if !caret { return }
+    let pos = page_data.read_doc(|d| {
+        d.contains(&sel.head).then(|| d.first_leaf_block(&sel.head)).flatten()
+            .and_then(|b| d.first_text(&b))
+    });
+    if let Some(pos) = pos {
+        set_caret(page_data, &Pos::new(&pos, 0));
+    }
+}
+
+/// highlight the selected blocks that are rendered. call after anything
+/// that renders blocks or changes the selection
+pub fn show_block_selection(page_data: RwSignal<Page>, page_elem: &Element) {
+    let highlighted = page_elem.query_selector_all("[block-selected]").unwrap();
+    for i in 0..highlighted.length() {
+        if let Some(elem) = highlighted.get(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+            elem.remove_attribute("block-selected").unwrap();
+        }
+    }
+    let Some(blocks) = selected_block_hashes(page_data) else {
+        // e.g. the blocks were deleted
+        block_selection_signal(page_data).set_untracked(None);
+        return
+    };
+    for hash in blocks {
+        if let Some(elem) = page_data.hash_to_node(&hash).and_then(|n| n.get_untracked().elem_ref) {
+            elem.set_attribute("block-selected", "").unwrap();
+        }
+    }
+    if let Ok(Some(dom_selection)) = document().get_selection() {
+        _ = dom_selection.remove_all_ranges();
+    }
+}
+
+/// the kind a key turns the selected blocks into
+fn key_block_kind(key: &str) -> Option<PageNodeType> {
+    Some(match key {
+        "0" => PageNodeType::TextBlock,
+        "1" => PageNodeType::H1,
+        "2" => PageNodeType::H2,
+        "3" => PageNodeType::H3,
+        "4" => PageNodeType::H4,
+        "5" => PageNodeType::H5,
+        "-" => PageNodeType::Dot,
+        "[" => PageNodeType::Check,
+        ">" => PageNodeType::Quote,
+        _ => return None,
+    })
+}
+
+/// returns whether the key was used. `escape_starts` is off in vim mode, bc
+/// vim needs Escape
+pub fn process_block_selection_keydown(cx: Scope, event: &KeyboardEvent, page_data: RwSignal<Page>,
+    page_elem: &Element, escape_starts: bool,
+) -> bool {
+    if event.is_composing() { return false }
+    let key = event.key();
+    let signal = block_selection_signal(page_data);
+    let Some(sel) = signal.get_untracked() else {
+        if !(escape_starts && key == "Escape") { return false }
+        event.prevent_default();
+        return start_block_selection(page_data);
+    };
+    if event.ctrl_key() || event.meta_key() || event.alt_key() { return false }
+    event.prevent_default();
+    let blocks = page_data.read_doc(|d| d.block_selection_blocks(&sel));
+    let tx = match key.as_str() {
+        "Escape" => {
+            end_block_selection(page_data, true);
+            return true;
+        },
+        "ArrowUp" | "ArrowDown" => {
+            let forward = key == "ArrowDown";
+            let sel = page_data.read_doc(|d| d.extend_block_selection(&sel, forward));
+            let rendered = page_data.hash_to_node(&sel.head)
+                .is_some_and(|n| n.get_untracked().elem_ref.is_some());
+            if !rendered {
+                jump_to_block(cx, page_data, page_elem, &sel.head, !forward);
+            }
+            signal.set_untracked(Some(sel));
+            return true;
+        },
+        "Backspace" | "Delete" => {
+            edit_blocks(Command::DeleteBlock, page_data);
+            return true;
+        },
+        "Tab" if event.shift_key() => page_data.read_doc(|d| d.outdent_blocks(&blocks)),
+        "Tab" => page_data.read_doc(|d| d.indent_blocks(&blocks)),
+        _ => key_block_kind(&key).map(|kind| page_data.read_doc(|d| d.set_blocks_kind(&blocks, kind))),
+    };
+    if let Some(tx) = tx {
+        if let Err(err) = page_data.edit_doc(|d| d.apply(&tx)) {
+            log!("EDIT FAILED: {}", err);
+        }
+    }
+    true
+}
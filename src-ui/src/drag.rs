@@ -0,0 +1,217 @@
+use leptos::{Scope, RwSignal, NodeRef, HtmlElement, Div, create_rw_signal, document, JsCast, 
+    UntrackedGettableSignal, UntrackedSettableSignal};
+use leptos::wasm_bindgen::closure::Closure;
+use web_sys::{Element, EventTarget, MouseEvent};
+
+use super::{PageView, Page, ROOT_HASH, ReadDoc, EditDoc, HashToNode, rerender_view, 
+    reshow_selection, select_to_block};
+
+// every block gets a handle on its left while the mouse is over it. dragging 
+// the handle moves the block (w/ everything in it) to the drop line (see 
+// `Doc::drop_position`). the move goes through the doc, then the view gets 
+// rendered again so the padding is right, even if the block came from/went 
+// somewhere that wasn't rendered
+
+/// how far left of a block to drag to drop it a level out of its branch
+const INDENT: f64 = 24.0;
+
+/// a block being dragged
+#[derive(Debug, Clone)]
+pub struct BlockDrag {
+    pub hash: String,
+    /// where it'd go if dropped now, as `(parent, index)`
+    pub drop: Option<(String, usize)>,
+}
+
+/// the innermost block elem `target` is in, and its hash
+fn block_at(page_data: RwSignal<Page>, target: Option<EventTarget>) -> Option<(Element, String)> {
+    let mut elem = target?.dyn_into::<Element>().ok()?.closest("[hash]").ok()??;
+    loop {
+        let hash = elem.get_attribute("hash")?;
+        let is_block = page_data.read_doc(|d| {
+            hash != ROOT_HASH && d.contains(&hash) && d.kind(&hash).is_block()
+        });
+        if is_block { return Some((elem, hash)) }
+        elem = elem.parent_element()?.closest("[hash]").ok()??;
+    }
+}
+
+/// show the handle next to the block under the mouse
+pub fn process_block_hover(event: MouseEvent, page_data: RwSignal<Page>, 
+    drag: RwSignal<Option<BlockDrag>>, hovered: RwSignal<Option<String>>, handle_elem: &Element,
+) {
+    if drag.get_untracked().is_some() { return }
+    let Some((elem, hash)) = block_at(page_data, event.target()) else { return };
+    let rect = elem.get_bounding_client_rect();
+    handle_elem.set_attribute("style", &format!("display: block; position: fixed; \
+        left: {}px; top: {}px; cursor: grab; user-select: none; color: #aaa", 
+        rect.left() - 16.0, rect.top())).unwrap();
+    hovered.set_untracked(Some(hash));
+}
+
+/// e.g. while scrolling, when the handle would be left behind
+pub fn hide_block_handle(hovered: RwSignal<Option<String>>, handle_elem: &Element) {
+    hovered.set_untracked(None);
+    handle_elem.set_attribute("style", "display: none").unwrap();
+}
+
+/// the handle was grabbed
+pub fn start_block_drag(event: MouseEvent, drag: RwSignal<Option<BlockDrag>>, 
+    hovered: RwSignal<Option<String>>,
+) {
+    if event.button() != 0 { return }
+    // don't let the browser start a selection
+    event.prevent_default();
+    if let Some(hash) = hovered.get_untracked() {
+        drag.set_untracked(Some(BlockDrag { hash, drop: None }));
+    }
+}
+
+/// work out where the block would drop, and draw the line there
+fn process_drag_move(event: MouseEvent, page_data: RwSignal<Page>, 
+    drag: RwSignal<Option<BlockDrag>>, scroll_window: &Element, indicator_elem: &Element,
+) {
+    let Some(mut dragging) = drag.get_untracked() else { return };
+    // the button was let go somewhere we didn't get the `mouseup`
+    if event.buttons() & 1 == 0 {
+        drag.set_untracked(None);
+        indicator_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    }
+    // dragging near the top/bottom scrolls, which renders more blocks
+    let window_rect = scroll_window.get_bounding_client_rect();
+    let (x, y) = (event.client_x() as f64, event.client_y() as f64);
+    if y < window_rect.top() + 20.0 {
+        scroll_window.scroll_by_with_x_and_y(0.0, -20.0);
+    } else if y > window_rect.bottom() - 20.0 {
+        scroll_window.scroll_by_with_x_and_y(0.0, 20.0);
+    }
+    let point_x = x.clamp(window_rect.left() + 1.0, window_rect.right() - 1.0);
+    let point_y = y.clamp(window_rect.top() + 1.0, window_rect.bottom() - 1.0);
+    let under = document().element_from_point(point_x as f32, point_y as f32);
+    let target = block_at(page_data, under.map(|e| e.into()))
+        .filter(|(_, hash)| {
+            *hash != dragging.hash && !page_data.read_doc(|d| d.is_ancestor(&dragging.hash, hash))
+        });
+    let after = target.as_ref().is_some_and(|(elem, _)| {
+        let rect = elem.get_bounding_client_rect();
+        y > rect.top() + rect.height() / 2.0
+    });
+    dragging.drop = target.as_ref().and_then(|(elem, hash)| {
+        let outdent = ((elem.get_bounding_client_rect().left() - x) / INDENT).max(0.0) as usize;
+        page_data.read_doc(|d| d.drop_position(hash, after, outdent))
+    });
+    // the line goes along the edge of the block it's dropping next to
+    let line = dragging.drop.as_ref().and_then(|(parent, index)| {
+        let edge = page_data.read_doc(|d| {
+            d.children(parent).get(if after { index.checked_sub(1)? } else { *index }).cloned()
+        })?;
+        let rect = page_data.hash_to_node(&edge)?.get_untracked().elem_ref?.get_bounding_client_rect();
+        Some((rect.left(), if after { rect.bottom() } else { rect.top() }, rect.width()))
+    });
+    match line {
+        Some((left, top, width)) => indicator_elem.set_attribute("style", &format!("display: block; \
+            position: fixed; left: {}px; top: {}px; width: {}px; height: 2px; \
+            background: #4a90e2; pointer-events: none", left, top - 1.0, width)).unwrap(),
+        None => indicator_elem.set_attribute("style", "display: none").unwrap(),
+    }
+    drag.set_untracked(Some(dragging));
+}
+
+/// move the block to where it was dropped
+fn process_drop(cx: Scope, page_data: RwSignal<Page>, drag: RwSignal<Option<BlockDrag>>, 
+    page_elem: &Element, indicator_elem: &Element,
+) {
+    let Some(dragging) = drag.get_untracked() else { return };
+    drag.set_untracked(None);
+    indicator_elem.set_attribute("style", "display: none").unwrap();
+    let Some((parent, index)) = dragging.drop else { return };
+    let Some(tx) = page_data.read_doc(|d| d.move_block(&dragging.hash, &parent, index)) else { return };
+    if let Err(err) = page_data.edit_doc(|d| d.apply(&tx)) {
+        return leptos::log!("COULDN'T MOVE BLOCK: {}", err);
+    }
+    // the padding is made up from the heights of the unrendered blocks, 
+    // which have moved
+    rerender_view(cx, page_data, page_elem);
+    reshow_selection(page_data);
+}
+
+/// follow block drags, even when the mouse leaves the page. `after` runs 
+/// after a drop
+pub fn track_block_drag(cx: Scope, page_data: RwSignal<Page>, drag: RwSignal<Option<BlockDrag>>, 
+    page_elem: &Element, indicator_elem: Element, after: impl Fn() + 'static,
+) {
+    let scroll_window = page_elem.clone();
+    let move_indicator = indicator_elem.clone();
+    let on_mousemove = Closure::<dyn FnMut(_)>::new(move |event: MouseEvent| {
+        process_drag_move(event, page_data, drag, &scroll_window, &move_indicator);
+    });
+    let page_elem = page_elem.clone();
+    let on_mouseup = Closure::<dyn FnMut(_)>::new(move |_: MouseEvent| {
+        if drag.get_untracked().is_none() { return }
+        process_drop(cx, page_data, drag, &page_elem, &indicator_elem);
+        after();
+    });
+    document().add_event_listener_with_callback("mousemove", 
+        on_mousemove.as_ref().unchecked_ref()).unwrap();
+    document().add_event_listener_with_callback("mouseup", 
+        on_mouseup.as_ref().unchecked_ref()).unwrap();
+    // the page lives as long as the app, so the listeners do too
+    on_mousemove.forget();
+    on_mouseup.forget();
+}
+
+/// the handle's state and elems, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct DragView {
+    pub drag: RwSignal<Option<BlockDrag>>,
+    /// the block the mouse is over
+    pub hovered: RwSignal<Option<String>>,
+    pub handle_ref: NodeRef<HtmlElement<Div>>,
+    pub indicator_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl DragView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            drag: create_rw_signal(cx, None),
+            hovered: create_rw_signal(cx, None),
+            handle_ref: NodeRef::new(cx),
+            indicator_ref: NodeRef::new(cx),
+        }
+    }
+
+    /// once the page is rendered
+    pub fn track(&self, page: PageView, page_elem: &Element, after: impl Fn() + 'static) {
+        if let Some(indicator_elem) = self.indicator_ref.get() {
+            track_block_drag(page.cx, page.page_data, self.drag, page_elem, 
+                indicator_elem.unchecked_ref::<Element>().clone(), after);
+        }
+    }
+
+    pub fn hover(&self, event: MouseEvent, page: PageView) {
+        if let Some(handle_elem) = self.handle_ref.get() {
+            process_block_hover(event, page.page_data, self.drag, self.hovered, 
+                handle_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn hide_handle(&self) {
+        if let Some(handle_elem) = self.handle_ref.get() {
+            hide_block_handle(self.hovered, handle_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn()) {
+        // shift+click selects blocks rather than dragging one
+        if event.shift_key() {
+            event.prevent_default();
+            if let Some(hash) = self.hovered.get_untracked() {
+                select_to_block(page.page_data, &hash);
+                refresh_view();
+            }
+            return;
+        }
+        start_block_drag(event, self.drag, self.hovered);
+    }
+}
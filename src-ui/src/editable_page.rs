@@ -4,19 +4,25 @@ use leptos::*;
 // use tauri_sys::{event, tauri};
 use web_sys::{Element, CharacterData};
 
-use crate::page_data::{ChangeBlockKind, PrevChild, RemoveThisBlockShell, InsertNodes, RemoveChild};
+use crate::page_data::{ChangeBlockKind, PrevChild, InsertNodes, RemoveChild};
 
 // use src_ui::*;
 use super::{
     Page, PageNode, PageNodeType, init_demo_page_data, IsFirstChild, IsBlock,
+    display_text,
     HashToLocation, HashToNode, update_hash_locations,
     update_dom_nodes_in_view, update_top_padding, update_bot_padding, get_prev_block_node,
-    process_keypress,
+    process_keydown, process_beforeinput, process_input, process_copy,
+    track_selection, scroll_to_selection, process_mousedown, track_mouse_drag, process_compositionstart, process_compositionend,
+    observe_dom, discard_own_mutations, load_keymap, load_syntax_profile,
+    Command, run_command, reshow_selection, open_file, export_markdown,
+    load_vim, toggle_vim, show_vim_mode, process_vim_keydown, vim_typed,
+    process_block_selection_keydown, end_block_selection, show_block_selection,
+    refresh_folds, show_folds,
+    SlashMenuView, LinkMenuView, PaletteView, DragView, FoldView, OutlineView, FindView, 
+    WorkspaceFindView, BacklinksView, RenameView,
 };
 
-// TODO: CUSTOMIZABLE MARKDOWN SYNTAX. E.G. IF YOU WANT `/` FOR ITALICS YOU CAN 
-// JUST PICK IT
-
 // seems EXTREMELY complex/janky to get the top/bottom/height of each element 
 // bc it must be rendered to the DOM to have those attributes, and there is no 
 // easy way to trigger a callback as soon as the element loads
@@ -53,13 +59,81 @@ use super::{
 // }
 
 
+/// what the features' handlers need from the page. it's all signals and refs, 
+/// so it's `Copy` and each handler can take its own
+#[derive(Clone, Copy)]
+pub struct PageView {
+    pub cx: Scope,
+    pub page_data: RwSignal<Page>,
+    pub page_elem_ref: NodeRef<HtmlElement<Div>>,
+    /// the file the page was opened from, if any
+    pub file_path: RwSignal<Option<String>>,
+}
+
+impl PageView {
+    pub fn page_elem(&self) -> Option<Element> {
+        self.page_elem_ref.get().map(|e| e.unchecked_ref::<Element>().clone())
+    }
+
+    /// put the focus (and the selection as it was) back in the page, e.g. 
+    /// once a panel closes
+    pub fn refocus(&self) {
+        if let Some(page_elem) = self.page_elem_ref.get() {
+            _ = page_elem.focus();
+            reshow_selection(self.page_data);
+        }
+    }
+}
+
 // TODO: CAN USE page_data.update_returning_untracked(f) TO GET STUFF IN SIGNAL WITHOUT HAVING TO COPY THE ENTIRE OBJECT EVERY TIME !!!
 
 #[component]
 pub fn EditablePage(cx: Scope) -> impl IntoView {
 
     let page_data: RwSignal<Page> = init_demo_page_data(cx);
-    let page_elem_ref: NodeRef<HtmlElement<Div>> = NodeRef::new(cx);
+    let keymap = load_keymap(cx);
+    let syntax = load_syntax_profile(cx);
+    // `None` while vim mode is off (see `vim.rs`)
+    let vim = load_vim(cx);
+    track_selection(page_data);
+    // where a mouse drag started, while the button is down
+    let drag_anchor = create_rw_signal(cx, None);
+    track_mouse_drag(page_data, drag_anchor);
+    // watches for DOM changes the browser made on its own (see `reconcile.rs`)
+    let dom_observer = create_rw_signal(cx, None::<web_sys::MutationObserver>);
+    let composing = create_rw_signal(cx, false);
+    let discard_own_changes = move || {
+        if let Some(observer) = dom_observer.get_untracked() {
+            discard_own_mutations(&observer);
+        }
+    };
+    let page = PageView {
+        cx,
+        page_data,
+        page_elem_ref: NodeRef::new(cx),
+        file_path: create_rw_signal(cx, None),
+    };
+    let page_elem_ref = page.page_elem_ref;
+    // the menu that opens when typing `/` (see `slash_menu.rs`)
+    let slash_menu = SlashMenuView::new(cx, syntax);
+    // the menu that opens when typing `[[` (see `wikilink.rs`)
+    let link_menu = LinkMenuView::new(cx);
+    // the command palette (see `palette.rs`)
+    let palette = PaletteView::new(cx, keymap);
+    // dragging blocks around by their handles (see `drag.rs`)
+    let drag = DragView::new(cx);
+    // the chevron for folding the hovered block (see `folding.rs`)
+    let fold = FoldView::new(cx);
+    // the headings sidebar (see `outline.rs`)
+    let outline = OutlineView::new(cx);
+    // the find bar, and the boxes over the matches on screen (see `find.rs`)
+    let find = FindView::new(cx);
+    // find & replace across files (see `workspace_find.rs`)
+    let workspace_find = WorkspaceFindView::new(cx);
+    // the notes that link to this one (see `backlinks.rs`)
+    let backlinks = BacklinksView::new(cx);
+    // renaming/moving the note (see `rename.rs`)
+    let rename = RenameView::new(cx);
 
     // TODO: MAYBE HAVE A SETTING IN THE APP TO INCREASE REFRESH RATE?
     // let scroll_throttle = store_value(cx, 0.0);
@@ -70,9 +144,16 @@ pub fn EditablePage(cx: Scope) -> impl IntoView {
         // if now > scroll_throttle.get() + 100.0 {
         //     scroll_throttle.set(now);
         // } else { return }
-        if let Some(page_elem) = &page_elem_ref.get() {
-            let page_elem = page_elem.unchecked_ref::<web_sys::Element>();
-            update_dom_nodes_in_view(cx, page_data, &page_elem.clone());
+        if let Some(page_elem) = &page.page_elem() {
+            update_dom_nodes_in_view(cx, page_data, page_elem);
+            show_block_selection(page_data, page_elem);
+            show_folds(page_data, page_elem);
+            discard_own_changes();
+            outline.highlight(page);
+            find.redraw(page);
+            // the handle would be left where the block was
+            drag.hide_handle();
+            fold.hide_chevron();
 
             // TODO: FINISH THIS
             // let page_top = (&page_elem).get_bounding_client_rect().top();
@@ -84,15 +165,170 @@ pub fn EditablePage(cx: Scope) -> impl IntoView {
         };
     };
 
-    let handle_keypress = move |event: web_sys::KeyboardEvent| {
-        event.prevent_default();
-        process_keypress(cx, event, page_data.clone());
+    // after an edit, blocks may have been added/removed, so the hash 
+    // locations and the rendered blocks need updating
+    let refresh_view = move || {
         update_hash_locations(&page_data);
-        // refresh view
-        if let Some(page_elem) = &page_elem_ref.get() {
-            let page_elem = page_elem.unchecked_ref::<web_sys::Element>();
-            update_dom_nodes_in_view(cx, page_data, &page_elem.clone());
+        if let Some(page_elem) = &page.page_elem() {
+            refresh_folds(cx, page_data, page_elem);
+            update_dom_nodes_in_view(cx, page_data, page_elem);
+            scroll_to_selection(cx, page_data, page_elem);
+            show_block_selection(page_data, page_elem);
+            show_folds(page_data, page_elem);
+        };
+        outline.show(page);
+        // the matches moved/changed
+        find.refresh(page);
+        discard_own_changes();
+    };
+    // once another file is open
+    let after_open = move || {
+        refresh_view();
+        backlinks.refresh(page);
+    };
+    // every command goes through here, whether from a shortcut or the palette
+    let run = move |command: Command| {
+        let Some(page_elem) = page.page_elem() else { return };
+        match command {
+            Command::CommandPalette => {
+                slash_menu.close();
+                palette.open();
+            },
+            Command::OpenFile => open_file(cx, page_data, page_elem, page.file_path, after_open),
+            Command::ExportMarkdown => export_markdown(page_data, page.file_path),
+            Command::ToggleVim => toggle_vim(vim, &page_elem),
+            Command::Find => find.open(page),
+            Command::FindInWorkspace => workspace_find.open(),
+            Command::Backlinks => backlinks.toggle(page),
+            Command::RenameNote => rename.open(page),
+            _ => {
+                run_command(cx, command, page_data, &page_elem);
+                refresh_view();
+            },
+        }
+    };
+    // text input is handled w/ `beforeinput` so IME/dictation/etc work. this 
+    // is just for keys that don't input anything
+    let handle_keydown = move |event: web_sys::KeyboardEvent| {
+        if link_menu.handle_keydown(&event, page) || slash_menu.handle_keydown(&event, page) {
+            refresh_view();
+            return;
+        }
+        if let Some(page_elem) = &page.page_elem() {
+            // vim has its own use for Escape
+            let vim_on = vim.update_returning_untracked(|v| v.is_some()) == Some(true);
+            if process_block_selection_keydown(cx, &event, page_data, page_elem, !vim_on) {
+                refresh_view();
+                return;
+            }
+            if process_vim_keydown(cx, &event, page_data, page_elem, vim) {
+                refresh_view();
+                return;
+            }
+            if let Some(command) = process_keydown(cx, event, page_data, page_elem, keymap) {
+                run(command);
+            }
         };
+        discard_own_changes();
+    };
+    let handle_beforeinput = move |event: web_sys::InputEvent| {
+        let typed = (event.input_type() == "insertText").then(|| event.data()).flatten();
+        match event.input_type().as_str() {
+            "insertParagraph" | "insertLineBreak" => vim_typed(vim, "\n"),
+            _ => if let Some(typed) = &typed { vim_typed(vim, typed) },
+        }
+        process_beforeinput(event, page_data, syntax);
+        slash_menu.update(page, typed.as_deref());
+        link_menu.update(page, typed.as_deref());
+        refresh_view();
+        slash_menu.redraw();
+        link_menu.redraw();
+    };
+    let handle_input = move |event: web_sys::Event| {
+        process_input(event, page_data);
+        refresh_view();
+    };
+    let handle_mousedown = move |event: web_sys::MouseEvent| {
+        slash_menu.close();
+        link_menu.close();
+        if link_menu.follow(&event, page, after_open) { return }
+        end_block_selection(page_data, false);
+        if let Some(page_elem) = page.page_elem() {
+            show_block_selection(page_data, &page_elem);
+        }
+        process_mousedown(event, page_data, drag_anchor);
+    };
+    let handle_mousemove = move |event: web_sys::MouseEvent| {
+        drag.hover(event, page);
+        fold.show_chevron(page, drag.hovered.get_untracked());
+    };
+    let handle_copy = move |event: web_sys::ClipboardEvent| {
+        process_copy(event, page_data, syntax, false);
+    };
+    let handle_cut = move |event: web_sys::ClipboardEvent| {
+        process_copy(event, page_data, syntax, true);
+        refresh_view();
+    };
+    let handle_compositionstart = move |_: web_sys::CompositionEvent| {
+        process_compositionstart(page_data);
+        composing.set_untracked(true);
+        refresh_view();
+    };
+    let handle_compositionend = move |_: web_sys::CompositionEvent| {
+        composing.set_untracked(false);
+        process_compositionend(page_data);
+        refresh_view();
+    };
+    // the rest just hand the events to the features (see their modules)
+    let handle_palette_input = move |event: web_sys::Event| palette.handle_input(event);
+    let handle_palette_keydown = move |event: web_sys::KeyboardEvent| {
+        palette.handle_keydown(event, page, run);
+    };
+    let handle_palette_mousedown = move |event: web_sys::MouseEvent| {
+        palette.handle_mousedown(event, page, run);
+    };
+    let handle_palette_blur = move |_: web_sys::FocusEvent| palette.handle_blur();
+    let handle_find_input = move |event: web_sys::Event| find.handle_input(event, page);
+    let handle_find_keydown = move |event: web_sys::KeyboardEvent| {
+        find.handle_keydown(event, false, page, refresh_view);
+    };
+    let handle_replace_keydown = move |event: web_sys::KeyboardEvent| {
+        find.handle_keydown(event, true, page, refresh_view);
+    };
+    let handle_find_mousedown = move |event: web_sys::MouseEvent| {
+        find.handle_mousedown(event, page, refresh_view);
+    };
+    let handle_workspace_find_keydown = move |event: web_sys::KeyboardEvent| {
+        workspace_find.handle_keydown(event, page, refresh_view);
+    };
+    let handle_workspace_find_mousedown = move |event: web_sys::MouseEvent| {
+        workspace_find.handle_mousedown(event, page, refresh_view);
+    };
+    let handle_rename_keydown = move |event: web_sys::KeyboardEvent| {
+        rename.handle_keydown(event, page, after_open);
+    };
+    let handle_rename_mousedown = move |event: web_sys::MouseEvent| {
+        rename.handle_mousedown(event, page, after_open);
+    };
+    let handle_backlinks_mousedown = move |event: web_sys::MouseEvent| {
+        backlinks.handle_mousedown(event, page, after_open);
+    };
+    let handle_fold_click = move |event: web_sys::MouseEvent| {
+        fold.handle_click(event, page, refresh_view);
+    };
+    let handle_drag_start = move |event: web_sys::MouseEvent| {
+        drag.handle_mousedown(event, page, refresh_view);
+    };
+    let handle_outline_mousedown = move |event: web_sys::MouseEvent| outline.handle_mousedown(event);
+    let handle_outline_mousemove = move |event: web_sys::MouseEvent| outline.handle_mousemove(event);
+    let handle_outline_mouseup = move |event: web_sys::MouseEvent| {
+        outline.handle_mouseup(event, page, refresh_view);
+    };
+    let handle_slash_menu_mousedown = move |event: web_sys::MouseEvent| {
+        slash_menu.handle_mousedown(event, page, refresh_view);
+    };
+    let handle_link_menu_mousedown = move |event: web_sys::MouseEvent| {
+        link_menu.handle_mousedown(event, page, refresh_view);
     };
 
     // TODO: CAN CONVERT MOST SIGNALS TO STORES OR BOXES OR SOMETHING THAT IS 
@@ -183,18 +419,165 @@ pub fn EditablePage(cx: Scope) -> impl IntoView {
                 top_elem.scroll_into_view();
 
                 update_dom_nodes_in_view(cx, page_data, &page_elem);
+                // only start watching once the init render is done
+                dom_observer.set_untracked(Some(observe_dom(cx, page_data, &page_elem, composing)));
+                show_vim_mode(vim, &page_elem);
+                outline.show(page);
+                drag.track(page, &page_elem, discard_own_changes);
             })
         }
     });
 
     view! {cx,
+        <div>
+        <div
+        type="outline"
+        style="position: fixed; left: 0; top: 0; width: 160px; max-height: 100vh; overflow-y: auto; user-select: none"
+        on:mousedown=handle_outline_mousedown
+        on:mousemove=handle_outline_mousemove
+        on:mouseup=handle_outline_mouseup
+        _ref=outline.outline_ref
+        />
         <div
         style="overflow-y: auto; height: 150px; width: 200px; margin: auto;"
         type="scroll-window"
         on:scroll=handle_scroll
-        on:keydown=handle_keypress
+        on:keydown=handle_keydown
+        on:beforeinput=handle_beforeinput
+        on:input=handle_input
+        on:compositionstart=handle_compositionstart
+        on:compositionend=handle_compositionend
+        on:mousedown=handle_mousedown
+        on:mousemove=handle_mousemove
+        on:copy=handle_copy
+        on:cut=handle_cut
         _ref=page_elem_ref
         />
+        <div
+        type="slash-menu"
+        style="display: none"
+        on:mousedown=handle_slash_menu_mousedown
+        _ref=slash_menu.menu_ref
+        />
+        <div
+        type="link-menu"
+        style="display: none"
+        on:mousedown=handle_link_menu_mousedown
+        _ref=link_menu.menu_ref
+        />
+        <div
+        type="drag-handle"
+        style="display: none"
+        on:mousedown=handle_drag_start
+        _ref=drag.handle_ref
+        >"⠿"</div>
+        <div type="drop-indicator" style="display: none" _ref=drag.indicator_ref />
+        <div
+        type="fold-chevron"
+        style="display: none"
+        on:mousedown=handle_fold_click
+        _ref=fold.chevron_ref
+        />
+        <div
+        type="command-palette"
+        style="display: none"
+        on:mousedown=handle_palette_mousedown
+        _ref=palette.palette_ref
+        >
+            <input
+            type="text"
+            placeholder="Type a command"
+            style="width: 100%; box-sizing: border-box"
+            on:input=handle_palette_input
+            on:keydown=handle_palette_keydown
+            on:blur=handle_palette_blur
+            _ref=palette.input_ref
+            />
+            <div style="max-height: 250px; overflow-y: auto" _ref=palette.list_ref />
+        </div>
+        <div
+        type="backlinks"
+        style="display: none"
+        on:mousedown=handle_backlinks_mousedown
+        _ref=backlinks.panel_ref
+        />
+        <div type="find-highlights" _ref=find.highlights_ref />
+        <div
+        type="find-bar"
+        style="display: none"
+        on:mousedown=handle_find_mousedown
+        _ref=find.bar_ref
+        >
+            <input
+            type="text"
+            placeholder="Find"
+            on:input=handle_find_input
+            on:keydown=handle_find_keydown
+            _ref=find.query_ref
+            />
+            <span type="find-count" />
+            <button option="case" title="Match case">"Aa"</button>
+            <button option="word" title="Whole word">"W"</button>
+            <button option="regex" title="Regex">".*"</button>
+            <button action="prev" title="Previous match">"↑"</button>
+            <button action="next" title="Next match">"↓"</button>
+            <button action="close" title="Close">"×"</button>
+            <input
+            type="text"
+            placeholder="Replace"
+            on:keydown=handle_replace_keydown
+            _ref=find.replace_ref
+            />
+            <button action="replace">"Replace"</button>
+            <button action="replace-all">"All"</button>
+        </div>
+        <div
+        type="workspace-find"
+        style="display: none"
+        on:mousedown=handle_workspace_find_mousedown
+        _ref=workspace_find.panel_ref
+        >
+            <input
+            type="text"
+            placeholder="Find in workspace"
+            on:keydown=handle_workspace_find_keydown
+            _ref=workspace_find.query_ref
+            />
+            <button option="case" title="Match case">"Aa"</button>
+            <button option="word" title="Whole word">"W"</button>
+            <button option="regex" title="Regex">".*"</button>
+            <button action="close" title="Close">"×"</button>
+            <input
+            type="text"
+            placeholder="Replace"
+            on:keydown=handle_workspace_find_keydown
+            _ref=workspace_find.replacement_ref
+            />
+            <button action="preview">"Preview"</button>
+            <button action="replace">"Replace"</button>
+            <button action="undo" title="Undo the last replace">"Undo"</button>
+            <span type="workspace-status" style="flex-basis: 100%" />
+            <div style="flex-basis: 100%; max-height: 300px; overflow-y: auto" _ref=workspace_find.preview_ref />
+        </div>
+        <div
+        type="rename"
+        style="display: none"
+        on:mousedown=handle_rename_mousedown
+        _ref=rename.panel_ref
+        >
+            <input
+            type="text"
+            placeholder="New name, e.g. folder/note"
+            style="flex: 1"
+            on:keydown=handle_rename_keydown
+            _ref=rename.input_ref
+            />
+            <button action="rename">"Rename"</button>
+            <button action="close" title="Close">"×"</button>
+            <span type="rename-status" style="flex-basis: 100%" />
+            <div type="rename-ambiguous" style="flex-basis: 100%; max-height: 200px; overflow-y: auto" />
+        </div>
+        </div>
     }
 }
 
@@ -219,11 +602,13 @@ pub fn init_page_nodes(
             PageNodeType::H1 => create_h1_elem(node),
             PageNodeType::Dot => create_dot_elem(node),
             PageNodeType::TextBlock => create_text_block_elem(node),
+            PageNodeType::Divider => create_divider_elem(node),
             _ => create_unknown_block_elem(node),
         };
     } else {
         elem = match kind {
             PageNodeType::RawText => create_raw_text_elem(node),
+            PageNodeType::FileLink => create_file_link_elem(node),
             _ => create_unknown_span_elem(node),
         };
     }
@@ -309,11 +694,13 @@ impl CreateElem for RwSignal<PageNode> {
                 PageNodeType::H1 => create_h1_elem(node),
                 PageNodeType::Dot => create_dot_elem(node),
                 PageNodeType::TextBlock => create_text_block_elem(node),
+            PageNodeType::Divider => create_divider_elem(node),
                 _ => create_unknown_block_elem(node),
             };
         } else {
             elem = match node.kind {
                 PageNodeType::RawText => create_raw_text_elem(node),
+                PageNodeType::FileLink => create_file_link_elem(node),
                 _ => create_unknown_span_elem(node),
             };
             // FIXME: CURRENTLY NOT ADDING REF BC IDK IF USEFUL, AND ONLY 
@@ -374,6 +761,16 @@ fn create_text_block_elem(node: PageNode) -> Element {
     elem
 }
 
+fn create_divider_elem(node: PageNode) -> Element {
+    let elem = document().create_element("div").unwrap();
+    elem.set_attribute("type", PageNodeType::Divider.value()).unwrap();
+    elem.set_attribute("hash", &node.hash).unwrap();
+    // no text to put the caret in
+    elem.set_attribute("contenteditable", "false").unwrap();
+    elem.set_attribute("style", "border-top: 1px solid #ccc; margin: 8px 0").unwrap();
+    elem
+}
+
 fn create_unknown_block_elem(node: PageNode) -> Element {
     let elem = document().create_element("div").unwrap();
     elem.set_attribute("hash", &node.hash).unwrap();
@@ -385,7 +782,18 @@ fn create_raw_text_elem(node: PageNode) -> Element {
     let elem = document().create_element("span").unwrap();
     elem.set_attribute("type", PageNodeType::RawText.value()).unwrap();
     elem.set_attribute("hash", &node.hash).unwrap();
-    elem.set_inner_html(&spaces_to_nbsp(node.content.get("text").unwrap()));
+    // not `set_inner_html` bc the text would be parsed as html
+    elem.set_text_content(Some(&display_text(node.content.get("text").unwrap())));
+    elem
+}
+
+/// a `[[note]]` link. its label is the text in it. what it links to is read 
+/// from the doc when it's clicked (see `wikilink.rs`), so it isn't kept here
+fn create_file_link_elem(node: PageNode) -> Element {
+    let elem = document().create_element("span").unwrap();
+    elem.set_attribute("type", PageNodeType::FileLink.value()).unwrap();
+    elem.set_attribute("hash", &node.hash).unwrap();
+    elem.set_attribute("title", "Cmd/Ctrl+click to open").unwrap();
     elem
 }
 
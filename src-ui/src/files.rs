@@ -0,0 +1,96 @@
+use leptos::{log, Scope, RwSignal, spawn_local, UntrackedGettableSignal, UntrackedSettableSignal};
+use serde::Serialize;
+use tauri_sys::{tauri, dialog::FileDialogBuilder};
+use web_sys::Element;
+
+use super::{Page, Pos, NodeTree, EditDoc, ReadDoc, rerender_view, set_caret, load_folds, 
+    clear_history};
+
+// the commands that go through the backend: reading a markdown file into the 
+// page, and writing the page out as one
+
+#[derive(Serialize)]
+struct OpenPageArgs {
+    path: String,
+}
+#[derive(Serialize)]
+struct SavePageArgs {
+    path: String,
+    page: NodeTree,
+}
+
+/// pick a markdown file and swap the page's blocks for the file's. `path` is 
+/// the file the page came from. `after` runs once the page is redrawn
+pub fn open_file(cx: Scope, page_data: RwSignal<Page>, page_elem: Element, 
+    path: RwSignal<Option<String>>, after: impl Fn() + 'static,
+) {
+    spawn_local(async move {
+        let picked = FileDialogBuilder::new().add_filter("Markdown", &["md"]).pick_file().await;
+        let file = match picked {
+            Ok(Some(file)) => file.to_string_lossy().into_owned(),
+            Ok(None) => return,
+            Err(err) => return log!("COULDN'T PICK A FILE: {:?}", err),
+        };
+        load_file(cx, page_data, &page_elem, path, file).await;
+        after();
+    });
+}
+
+/// swap the page's blocks for the file's, e.g. once it's been picked or 
+/// changed on disk
+pub async fn load_file(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, 
+    path: RwSignal<Option<String>>, file: String,
+) {
+    let args = OpenPageArgs { path: file.clone() };
+    let tree = match tauri::invoke::<_, NodeTree>("open_page", &args).await {
+        Ok(tree) => tree,
+        Err(err) => return log!("COULDN'T OPEN {}: {:?}", file, err),
+    };
+    let tx = page_data.read_doc(|d| d.replace_page(tree));
+    if let Err(err) = page_data.edit_doc(|d| d.apply(&tx)) {
+        return log!("COULDN'T OPEN {}: {}", file, err);
+    }
+    // the old file's edits can't be undone in this one
+    clear_history(page_data);
+    load_folds(page_data, &file).await;
+    path.set_untracked(Some(file));
+    // none of the old blocks' heights/padding apply anymore
+    rerender_view(cx, page_data, page_elem);
+    let start = page_data.read_doc(|d| {
+        d.text_blocks().first().and_then(|b| d.first_text(b))
+    });
+    if let Some(start) = start {
+        set_caret(page_data, &Pos::new(&start, 0));
+    }
+}
+
+/// the folder the file the page came from is in. every markdown file under 
+/// it is part of the same workspace
+pub fn workspace_dir(path: RwSignal<Option<String>>) -> Option<String> {
+    let path = path.get_untracked()?;
+    let dir = std::path::Path::new(&path).parent()?;
+    Some(dir.to_string_lossy().into_owned())
+}
+
+/// write the page to a markdown file, picked in a save dialog that starts at 
+/// the file the page came from
+pub fn export_markdown(page_data: RwSignal<Page>, path: RwSignal<Option<String>>) {
+    spawn_local(async move {
+        let current = path.get_untracked().map(std::path::PathBuf::from);
+        let mut dialog = FileDialogBuilder::new();
+        dialog.add_filter("Markdown", &["md"]);
+        if let Some(current) = &current {
+            dialog.set_default_path(current);
+        }
+        let file = match dialog.save_file().await {
+            Ok(Some(file)) => file.to_string_lossy().into_owned(),
+            Ok(None) => return,
+            Err(err) => return log!("COULDN'T PICK A FILE: {:?}", err),
+        };
+        let args = SavePageArgs { path: file.clone(), page: page_data.read_doc(|d| d.root_tree()) };
+        match tauri::invoke::<_, ()>("save_page", &args).await {
+            Ok(()) => path.set_untracked(Some(file)),
+            Err(err) => log!("COULDN'T SAVE {}: {:?}", file, err),
+        }
+    });
+}
@@ -0,0 +1,379 @@
+use leptos::{Scope, RwSignal, NodeRef, HtmlElement, Div, Input, create_rw_signal, document, 
+    event_target_value, JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use web_sys::{Element, Event, EventTarget, HtmlInputElement, KeyboardEvent, MouseEvent};
+
+use super::{PageView, Page, Selection, FindOptions, FindMatch, ReadDoc, HashToNode,
+    selected_range, set_selection, jump_to_block, pos_to_dom, apply_edit};
+
+// the find bar (Mod-F). the browser's own find only sees the blocks that are
+// rendered, so this searches the doc instead (see `Doc::find_all`). going to
+// a match renders it if it isn't on screen, then selects it. the matches on
+// screen are highlighted w/ boxes drawn over the page, bc wrapping them in
+// elems would change the DOM the page is built from. Replace All is one
+// transaction, so Mod-Z undoes all of it at once (see `history.rs`)
+
+#[derive(Debug, Clone, Default)]
+pub struct Find {
+    pub open: bool,
+    pub options: FindOptions,
+    pub matches: Vec<FindMatch>,
+    /// the match that's selected, or would be by Enter
+    pub current: Option<usize>,
+    /// what's wrong w/ the query if it's not a valid regex
+    pub error: Option<String>,
+}
+
+/// what a key or button in the bar does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindAction {
+    Next,
+    Prev,
+    Replace,
+    ReplaceAll,
+    Close,
+}
+impl FindAction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "next" => Self::Next,
+            "prev" => Self::Prev,
+            "replace" => Self::Replace,
+            "replace-all" => Self::ReplaceAll,
+            "close" => Self::Close,
+            _ => return None,
+        })
+    }
+}
+
+/// open the bar. text selected w/in one block becomes the query
+pub fn open_find(page_data: RwSignal<Page>, find: RwSignal<Find>, query_input: &HtmlInputElement) {
+    let selected = selected_range(page_data).and_then(|(start, end)| page_data.read_doc(|d| {
+        (start.hash == end.hash && start.offset < end.offset)
+            .then(|| d.text(&start.hash)[start.offset..end.offset].to_string())
+    }));
+    if let Some(text) = selected {
+        query_input.set_value(&text);
+    }
+    find.update_untracked(|f| f.open = true);
+    _ = query_input.focus();
+    query_input.select();
+}
+
+pub fn close_find(find: RwSignal<Find>) {
+    find.update_untracked(|f| {
+        f.open = false;
+        f.matches.clear();
+        f.current = None;
+    });
+}
+
+/// search again, e.g. after the query or the doc changed. the current match
+/// is the first one from the start of the selection
+pub fn update_find(page_data: RwSignal<Page>, find: RwSignal<Find>, query: &str) {
+    let mut state = find.get_untracked();
+    if !state.open { return }
+    state.matches.clear();
+    state.error = None;
+    if !query.is_empty() {
+        match state.options.compile(query) {
+            Ok(finder) => state.matches = page_data.read_doc(|d| d.find_all(&finder)),
+            Err(err) => state.error = Some(err),
+        }
+    }
+    state.current = match selected_range(page_data) {
+        Some((start, _)) => page_data.read_doc(|d| d.match_from(&state.matches, &start)),
+        None => (!state.matches.is_empty()).then_some(0),
+    };
+    find.set_untracked(state);
+}
+
+/// flip one of the options, by the `option` attribute of its button
+pub fn toggle_find_option(options: &mut FindOptions, name: &str) {
+    match name {
+        "case" => options.case_sensitive = !options.case_sensitive,
+        "word" => options.whole_word = !options.whole_word,
+        "regex" => options.regex = !options.regex,
+        _ => {},
+    }
+}
+
+/// mark the buttons of the options that are on
+pub fn show_find_options(options: &FindOptions, elem: &Element) {
+    let buttons = [
+        ("case", options.case_sensitive),
+        ("word", options.whole_word),
+        ("regex", options.regex),
+    ];
+    for (name, on) in buttons {
+        let Ok(Some(button)) = elem.query_selector(&format!("[option={}]", name)) else { continue };
+        match on {
+            true => button.set_attribute("on", "").unwrap(),
+            false => button.remove_attribute("on").unwrap(),
+        }
+    }
+}
+
+/// whether the doc selection is exactly the match
+fn is_selected(page_data: RwSignal<Page>, m: &FindMatch) -> bool {
+    let Some(range) = selected_range(page_data) else { return false };
+    page_data.read_doc(|d| d.contains(&m.block) && d.match_range(m) == Some(range))
+}
+
+/// whether the block is rendered and all of it is in the scroll window
+fn is_in_view(page_data: RwSignal<Page>, page_elem: &Element, hash: &str) -> bool {
+    let Some(elem) = page_data.hash_to_node(hash).and_then(|n| n.get_untracked().elem_ref) else {
+        return false
+    };
+    let (rect, view) = (elem.get_bounding_client_rect(), page_elem.get_bounding_client_rect());
+    rect.top() >= view.top() && rect.bottom() <= view.bottom()
+}
+
+/// select the current match, or the next/previous one if it's already
+/// selected. it's rendered and scrolled to first if it's not on screen
+pub fn go_to_match(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, find: RwSignal<Find>,
+    forward: bool,
+) {
+    let mut state = find.get_untracked();
+    let len = state.matches.len();
+    if len == 0 { return }
+    let idx = match state.current {
+        Some(i) if !is_selected(page_data, &state.matches[i]) => i,
+        Some(i) if forward => (i + 1) % len,
+        Some(i) => (i + len - 1) % len,
+        None => 0,
+    };
+    state.current = Some(idx);
+    let m = state.matches[idx].clone();
+    find.set_untracked(state);
+    if !is_in_view(page_data, page_elem, &m.block) {
+        jump_to_block(cx, page_data, page_elem, &m.block, false);
+    }
+    let Some((start, end)) = page_data.read_doc(|d| d.match_range(&m)) else { return };
+    set_selection(page_data, Selection::new(start, end));
+}
+
+/// replace the current match if it's selected (so nothing is replaced w/o
+/// being seen first). the caret ends up after what replaced it, so going to
+/// the match from there goes to the next one
+pub fn replace_match(page_data: RwSignal<Page>, find: RwSignal<Find>, query: &str, replacement: &str) {
+    let state = find.get_untracked();
+    let Some(m) = state.current.and_then(|i| state.matches.get(i)).cloned() else { return };
+    if !is_selected(page_data, &m) { return }
+    replace(page_data, &state, query, &[m], replacement);
+}
+
+pub fn replace_all_matches(page_data: RwSignal<Page>, find: RwSignal<Find>, query: &str,
+    replacement: &str,
+) {
+    let state = find.get_untracked();
+    replace(page_data, &state, query, &state.matches, replacement);
+}
+
+fn replace(page_data: RwSignal<Page>, state: &Find, query: &str, matches: &[FindMatch],
+    replacement: &str,
+) {
+    let Ok(finder) = state.options.compile(query) else { return };
+    let (tx, end) = page_data.read_doc(|d| {
+        let tx = d.replace_matches(&finder, &state.options, matches, replacement);
+        let end = matches.first().filter(|m| d.contains(&m.block)).and_then(|m| d.match_range(m)).map(|(_, end)| end);
+        (tx, end)
+    });
+    let Some(end) = end else { return };
+    if tx.is_empty() { return }
+    apply_edit(page_data, &tx, end);
+}
+
+/// keys in the bar's inputs. Enter goes to the next match (Shift+Enter the
+/// previous one) in the query, and replaces in the replacement (w/ Cmd/Ctrl
+/// it replaces all)
+pub fn process_find_keydown(event: &KeyboardEvent, in_replacement: bool) -> Option<FindAction> {
+    let action = match event.key().as_str() {
+        "Escape" => FindAction::Close,
+        "Enter" if in_replacement && (event.ctrl_key() || event.meta_key()) => FindAction::ReplaceAll,
+        "Enter" if in_replacement => FindAction::Replace,
+        "Enter" if event.shift_key() => FindAction::Prev,
+        "Enter" => FindAction::Next,
+        _ => return None,
+    };
+    event.prevent_default();
+    Some(action)
+}
+
+/// the `action` and `option` attributes of the button `target` is in. a
+/// button has one or the other
+pub fn find_button_at(target: Option<EventTarget>) -> (Option<String>, Option<String>) {
+    let Some(button) = target.and_then(|t| t.dyn_into::<Element>().ok())
+        .and_then(|e| e.closest("button").ok().flatten()) else { return (None, None) };
+    (button.get_attribute("action"), button.get_attribute("option"))
+}
+
+/// show/hide the bar, and update the count and the option buttons
+pub fn show_find_bar(find: RwSignal<Find>, bar_elem: &Element) {
+    let state = find.get_untracked();
+    if !state.open {
+        bar_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    }
+    bar_elem.set_attribute("style", "display: flex; flex-wrap: wrap; gap: 4px; position: fixed; \
+        top: 8px; right: 8px; width: 280px; padding: 6px; background: white; \
+        border: 1px solid #ccc; border-radius: 4px; z-index: 10").unwrap();
+    show_find_options(&state.options, bar_elem);
+    if let Ok(Some(count_elem)) = bar_elem.query_selector("[type=find-count]") {
+        let count = match (&state.error, state.current) {
+            (Some(_), _) => "Bad regex".to_string(),
+            _ if state.matches.is_empty() => "No results".to_string(),
+            (None, Some(i)) => format!("{} of {}", i + 1, state.matches.len()),
+            (None, None) => format!("{} results", state.matches.len()),
+        };
+        count_elem.set_text_content(Some(&count));
+        match &state.error {
+            Some(err) => count_elem.set_attribute("title", err).unwrap(),
+            None => count_elem.remove_attribute("title").unwrap(),
+        }
+    }
+}
+
+/// draw a box over each match that's on screen. call after anything that
+/// renders blocks or scrolls
+pub fn show_find_highlights(page_data: RwSignal<Page>, find: RwSignal<Find>, page_elem: &Element,
+    layer_elem: &Element,
+) {
+    layer_elem.set_inner_html("");
+    let state = find.get_untracked();
+    if !state.open { return }
+    let view = page_elem.get_bounding_client_rect();
+    for (i, m) in state.matches.iter().enumerate() {
+        let Some((start, end)) = page_data.read_doc(|d| d.match_range(m)) else { continue };
+        // not rendered
+        let (Some((start_node, start_offset)), Some((end_node, end_offset))) = (
+            pos_to_dom(page_data, &start),
+            pos_to_dom(page_data, &end),
+        ) else { continue };
+        let range = document().create_range().unwrap();
+        if range.set_start(&start_node, start_offset).is_err()
+            || range.set_end(&end_node, end_offset).is_err() { continue }
+        let Some(rects) = range.get_client_rects() else { continue };
+        let color = match state.current == Some(i) {
+            true => "rgba(255, 150, 0, 0.5)",
+            false => "rgba(255, 220, 0, 0.4)",
+        };
+        for j in 0..rects.length() {
+            let Some(rect) = rects.get(j) else { continue };
+            // rendered but scrolled out of the window
+            if rect.bottom() < view.top() || rect.top() > view.bottom() { continue }
+            let top = rect.top().max(view.top());
+            let bottom = rect.bottom().min(view.bottom());
+            let highlight = document().create_element("div").unwrap();
+            highlight.set_attribute("style", &format!("position: fixed; left: {}px; top: {}px; \
+                width: {}px; height: {}px; background: {}; pointer-events: none",
+                rect.left(), top, rect.width(), bottom - top, color)).unwrap();
+            layer_elem.append_child(&highlight).unwrap();
+        }
+    }
+}
+
+/// the bar's state and elems, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct FindView {
+    pub find: RwSignal<Find>,
+    pub bar_ref: NodeRef<HtmlElement<Div>>,
+    pub query_ref: NodeRef<HtmlElement<Input>>,
+    pub replace_ref: NodeRef<HtmlElement<Input>>,
+    /// the boxes over the matches on screen
+    pub highlights_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl FindView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            find: create_rw_signal(cx, Find::default()),
+            bar_ref: NodeRef::new(cx),
+            query_ref: NodeRef::new(cx),
+            replace_ref: NodeRef::new(cx),
+            highlights_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self, page: PageView) {
+        if let Some(bar_elem) = self.bar_ref.get() {
+            show_find_bar(self.find, bar_elem.unchecked_ref::<Element>());
+        }
+        if let (Some(page_elem), Some(layer_elem)) = (page.page_elem(), self.highlights_ref.get()) {
+            show_find_highlights(page.page_data, self.find, &page_elem, 
+                layer_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    /// after an edit the matches may have moved/changed
+    pub fn refresh(&self, page: PageView) {
+        if let Some(input) = self.query_ref.get() {
+            update_find(page.page_data, self.find, &input.value());
+        }
+        self.redraw(page);
+    }
+
+    pub fn open(&self, page: PageView) {
+        let Some(input) = self.query_ref.get() else { return };
+        open_find(page.page_data, self.find, &input);
+        update_find(page.page_data, self.find, &input.value());
+        self.redraw(page);
+    }
+
+    pub fn run(&self, action: FindAction, page: PageView, refresh_view: impl Fn()) {
+        let (Some(page_elem), Some(query_input), Some(replace_input)) = (
+            page.page_elem(), self.query_ref.get(), self.replace_ref.get(),
+        ) else { return };
+        let query = query_input.value();
+        match action {
+            FindAction::Next | FindAction::Prev => {
+                go_to_match(page.cx, page.page_data, &page_elem, self.find, action == FindAction::Next);
+                refresh_view();
+            },
+            FindAction::Replace => {
+                replace_match(page.page_data, self.find, &query, &replace_input.value());
+                // this finds the matches again, w/ the one after the 
+                // replaced one as the current one
+                refresh_view();
+                go_to_match(page.cx, page.page_data, &page_elem, self.find, true);
+                refresh_view();
+            },
+            FindAction::ReplaceAll => {
+                replace_all_matches(page.page_data, self.find, &query, &replace_input.value());
+                refresh_view();
+            },
+            FindAction::Close => {
+                close_find(self.find);
+                page.refocus();
+            },
+        }
+        self.redraw(page);
+    }
+
+    pub fn handle_input(&self, event: Event, page: PageView) {
+        update_find(page.page_data, self.find, &event_target_value(&event));
+        self.redraw(page);
+    }
+
+    pub fn handle_keydown(&self, event: KeyboardEvent, in_replacement: bool, page: PageView, 
+        refresh_view: impl Fn(),
+    ) {
+        if let Some(action) = process_find_keydown(&event, in_replacement) {
+            self.run(action, page, refresh_view);
+        }
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn()) {
+        let (action, option) = find_button_at(event.target());
+        if action.is_none() && option.is_none() { return }
+        // keep the focus in the input
+        event.prevent_default();
+        if let Some(action) = action.and_then(|a| FindAction::from_name(&a)) {
+            self.run(action, page, refresh_view);
+        }
+        if let (Some(option), Some(input)) = (option, self.query_ref.get()) {
+            self.find.update_untracked(|f| toggle_find_option(&mut f.options, &option));
+            update_find(page.page_data, self.find, &input.value());
+            self.redraw(page);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, create_rw_signal, spawn_local, JsCast, 
+    UntrackedGettableSignal, UntrackedSettableSignal};
+use serde::Serialize;
+use tauri_sys::tauri;
+use web_sys::{Element, MouseEvent};
+
+use super::{PageView, Page, Pos, Fold, ReadDoc, HashToNode, rerender_view, set_caret};
+
+// folding (see `Doc::fold_hides`). a chevron shows next to the block under the
+// mouse if it can be folded, and clicking it hides/shows the blocks the fold
+// covers. hidden blocks are never rendered: `get_next_block_node` and
+// `get_prev_block_node` skip them, and they take up no room in the padding.
+// the folds are saved per file by the backend
+
+#[derive(Serialize)]
+struct LoadFoldsArgs {
+    path: String,
+}
+#[derive(Serialize)]
+struct SaveFoldsArgs {
+    path: String,
+    folds: Vec<Fold>,
+}
+
+fn fold_signals(page_data: RwSignal<Page>
+) -> (RwSignal<HashSet<String>>, RwSignal<HashSet<String>>) {
+    page_data.update_returning_untracked(|p| (p.folds, p.hidden)).unwrap()
+}
+
+/// whether a fold is hiding the block
+pub fn is_hidden(page_data: RwSignal<Page>, hash: &str) -> bool {
+    let (_, hidden) = fold_signals(page_data);
+    hidden.update_returning_untracked(|h| h.contains(hash)).unwrap()
+}
+
+/// work out which blocks are hidden again, e.g. after an edit. folds that
+/// have nothing left to hide are dropped. returns whether anything changed
+fn update_hidden(page_data: RwSignal<Page>) -> bool {
+    let (folds, hidden) = fold_signals(page_data);
+    let kept: HashSet<String> = page_data.read_doc(|d| {
+        folds.get_untracked().into_iter().filter(|f| !d.fold_hides(f).is_empty()).collect()
+    });
+    let new_hidden = page_data.read_doc(|d| d.folded_away(&kept));
+    folds.set_untracked(kept);
+    if hidden.update_returning_untracked(|h| *h == new_hidden).unwrap() { return false }
+    hidden.set_untracked(new_hidden);
+    true
+}
+
+/// open any folds that hide the block. returns whether there were any
+pub fn reveal_block(page_data: RwSignal<Page>, hash: &str) -> bool {
+    let (folds, _) = fold_signals(page_data);
+    let hiding = page_data.read_doc(|d| d.folds_hiding(hash, &folds.get_untracked()));
+    if hiding.is_empty() { return false }
+    folds.update_untracked(|f| {
+        for fold in &hiding {
+            f.remove(fold);
+        }
+    });
+    update_hidden(page_data);
+    true
+}
+
+/// after an edit the folds might hide different blocks. the caret can't be
+/// in a hidden one either (e.g. after Enter at the end of a folded heading),
+/// so that fold gets opened
+pub fn refresh_folds(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element) {
+    let caret_block = page_data.update_returning_untracked(|p| p.selection.get_untracked()).flatten()
+        .and_then(|sel| page_data.read_doc(|d| {
+            d.contains(&sel.head.hash).then(|| d.block_of(&sel.head.hash)).flatten()
+        }));
+    let revealed = caret_block.is_some_and(|b| reveal_block(page_data, &b));
+    if update_hidden(page_data) || revealed {
+        rerender_view(cx, page_data, page_elem);
+    }
+}
+
+/// fold/unfold the block
+pub fn toggle_fold(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, hash: &str,
+    path: RwSignal<Option<String>>,
+) {
+    let (folds, _) = fold_signals(page_data);
+    folds.update_untracked(|f| {
+        if !f.remove(hash) {
+            f.insert(hash.to_string());
+        }
+    });
+    update_hidden(page_data);
+    // the caret was in what just got hidden, so it goes on the folded block
+    let caret_block = page_data.update_returning_untracked(|p| p.selection.get_untracked()).flatten()
+        .and_then(|sel| page_data.read_doc(|d| d.block_of(&sel.head.hash)));
+    if caret_block.is_some_and(|b| is_hidden(page_data, &b)) {
+        let start = page_data.read_doc(|d| d.first_leaf_block(hash).and_then(|b| d.first_text(&b)));
+        if let Some(start) = start {
+            set_caret(page_data, &Pos::new(&start, 0));
+        }
+    }
+    rerender_view(cx, page_data, page_elem);
+    save_folds(page_data, path);
+}
+
+/// mark the rendered blocks that are folded, so they can be styled. call
+/// after anything that renders blocks
+pub fn show_folds(page_data: RwSignal<Page>, page_elem: &Element) {
+    let marked = page_elem.query_selector_all("[folded]").unwrap();
+    for i in 0..marked.length() {
+        if let Some(elem) = marked.get(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+            elem.remove_attribute("folded").unwrap();
+        }
+    }
+    let (folds, _) = fold_signals(page_data);
+    for fold in folds.get_untracked() {
+        if let Some(elem) = page_data.hash_to_node(&fold).and_then(|n| n.get_untracked().elem_ref) {
+            elem.set_attribute("folded", "").unwrap();
+        }
+    }
+}
+
+/// show the chevron next to the hovered block, if it (or the branch block it
+/// starts) can be folded
+pub fn show_fold_chevron(page_data: RwSignal<Page>, hovered: Option<String>,
+    target: RwSignal<Option<String>>, chevron_elem: &Element,
+) {
+    let block = hovered.and_then(|h| page_data.read_doc(|d| {
+        d.contains(&h).then(|| d.fold_target(&h)).flatten()
+    }));
+    let elem = block.as_ref().and_then(|b| page_data.hash_to_node(b))
+        .and_then(|n| n.get_untracked().elem_ref);
+    let (Some(block), Some(elem)) = (block, elem) else {
+        return hide_fold_chevron(target, chevron_elem);
+    };
+    let (folds, _) = fold_signals(page_data);
+    let folded = folds.update_returning_untracked(|f| f.contains(&block)).unwrap();
+    chevron_elem.set_text_content(Some(if folded { "▸" } else { "▾" }));
+    let rect = elem.get_bounding_client_rect();
+    chevron_elem.set_attribute("style", &format!("display: block; position: fixed; \
+        left: {}px; top: {}px; cursor: pointer; user-select: none; color: #aaa",
+        rect.left() - 30.0, rect.top())).unwrap();
+    target.set_untracked(Some(block));
+}
+
+pub fn hide_fold_chevron(target: RwSignal<Option<String>>, chevron_elem: &Element) {
+    target.set_untracked(None);
+    chevron_elem.set_attribute("style", "display: none").unwrap();
+}
+
+/// get the folds saved for the file, once its page is in the doc
+pub async fn load_folds(page_data: RwSignal<Page>, path: &str) {
+    let args = LoadFoldsArgs { path: path.to_string() };
+    let keys = match tauri::invoke::<_, Vec<Fold>>("load_folds", &args).await {
+        Ok(keys) => keys,
+        Err(err) => return log!("COULDN'T LOAD FOLDS: {:?}", err),
+    };
+    let (folds, _) = fold_signals(page_data);
+    folds.set_untracked(page_data.read_doc(|d| d.folds_from_keys(&keys)));
+    update_hidden(page_data);
+}
+
+fn save_folds(page_data: RwSignal<Page>, path: RwSignal<Option<String>>) {
+    // the page isn't from a file, so there's nowhere to keep them
+    let Some(path) = path.get_untracked() else { return };
+    let (folds, _) = fold_signals(page_data);
+    let keys = page_data.read_doc(|d| d.fold_keys(&folds.get_untracked()));
+    spawn_local(async move {
+        let args = SaveFoldsArgs { path, folds: keys };
+        if let Err(err) = tauri::invoke::<_, ()>("save_folds", &args).await {
+            log!("COULDN'T SAVE FOLDS: {:?}", err);
+        }
+    });
+}
+
+/// the chevron's state and elem, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct FoldView {
+    /// the block the chevron folds
+    pub target: RwSignal<Option<String>>,
+    pub chevron_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl FoldView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            target: create_rw_signal(cx, None),
+            chevron_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn show_chevron(&self, page: PageView, hovered: Option<String>) {
+        if let Some(chevron_elem) = self.chevron_ref.get() {
+            show_fold_chevron(page.page_data, hovered, self.target, chevron_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn hide_chevron(&self) {
+        if let Some(chevron_elem) = self.chevron_ref.get() {
+            hide_fold_chevron(self.target, chevron_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn handle_click(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn()) {
+        // keep the focus (and caret) in the page
+        event.prevent_default();
+        let (Some(hash), Some(page_elem)) = (self.target.get_untracked(), page.page_elem()) else { return };
+        toggle_fold(page.cx, page.page_data, &page_elem, &hash, page.file_path);
+        refresh_view();
+        // the block moved if what was above it got (un)rendered
+        self.show_chevron(page, Some(hash));
+    }
+}
@@ -0,0 +1,46 @@
+use leptos::{log, RwSignal, UntrackedGettableSignal};
+
+use super::{Page, History, Doc, Selection, end_block_selection, sync_selection_from_dom, set_selection,
+    sync_edge_elems};
+
+// undo/redo (Mod-Z, Mod-Shift-Z, or the browser's own undo). every edit made
+// through `edit_doc` is kept in the page's history (see `history.rs` in the
+// model), so this just applies the last one back
+
+fn history_signals(page_data: RwSignal<Page>
+) -> (RwSignal<History>, RwSignal<Doc>, RwSignal<Option<Selection>>) {
+    page_data.update_returning_untracked(|p| (p.history, p.doc, p.selection)).unwrap()
+}
+
+/// undo the last edit, or redo the last undone one. the selection goes back
+/// to where it was
+pub fn undo_edit(page_data: RwSignal<Page>, redo: bool) {
+    end_block_selection(page_data, false);
+    sync_selection_from_dom(page_data);
+    let (history, doc, selection) = history_signals(page_data);
+    let current = selection.get_untracked();
+    let had_step = history.update_returning_untracked(|h| match redo {
+        true => h.can_redo(),
+        false => h.can_undo(),
+    }).unwrap();
+    if !had_step { return }
+    // not through `edit_doc`, bc that would put the undo in the history too
+    let sel = history.update_returning_untracked(|h| {
+        doc.update_returning_untracked(|d| match redo {
+            true => h.redo(d, current),
+            false => h.undo(d, current),
+        }).flatten()
+    }).flatten();
+    sync_edge_elems(page_data);
+    match sel {
+        Some(sel) => set_selection(page_data, sel),
+        // the doc didn't match the history anymore, so it was dropped
+        None => log!("COULDN'T UNDO, HISTORY CLEARED"),
+    }
+}
+
+/// forget the history, e.g. once a different file is loaded
+pub fn clear_history(page_data: RwSignal<Page>) {
+    let (history, _, _) = history_signals(page_data);
+    history.update_untracked(|h| h.clear());
+}
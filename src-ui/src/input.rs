@@ -0,0 +1,421 @@
+use leptos::{log, Scope, RwSignal, document, JsCast, UntrackedGettableSignal};
+use web_sys::{Element, InputEvent, KeyboardEvent, ClipboardEvent};
+
+use super::{Page, Pos, Op, Transaction, HashToNode, EditDoc, ReadDoc, 
+    Command, Keymap, SyntaxProfile, event_chord, render_block, jump_to_block, selected_range, set_caret, set_selection,
+    selected_block_hashes, end_block_selection, is_hidden, undo_edit, est_leaf_height, UNMEASURED_HEIGHT};
+
+pub const INVIS_CHAR: &str = "\u{a0}"; // currently space char so don't have to deal with cleanup
+// const INVIS_: &str = "\u{feff}"; //  "&#65279;"
+
+/// typing/pasting over a selection replaces it
+fn replace_range(start: &Pos, end: &Pos, op: Op) -> Transaction {
+    let mut tx = Transaction::new();
+    if start != end {
+        tx.push(Op::DeleteRange { start: start.clone(), end: end.clone() });
+    }
+    tx.push(op);
+    tx
+}
+
+/// apply the edit to the doc (which patches the DOM for us), then put the 
+/// caret back. returns where the caret went
+pub fn apply_edit(page_data: RwSignal<Page>, tx: &Transaction, caret: Pos) -> Option<Pos> {
+    match page_data.edit_doc(|d| d.apply(tx)) {
+        Ok(applied) => {
+            let caret = applied.caret.unwrap_or(caret);
+            set_caret(page_data, &caret);
+            Some(caret)
+        },
+        Err(err) => {
+            log!("EDIT FAILED: {}", err);
+            None
+        },
+    }
+}
+
+/// paste/drop text w/ several lines in it (see `Doc::paste_markdown`). it's
+/// a few applies, but `edit_doc` keeps them as one edit to undo
+fn paste_lines(page_data: RwSignal<Page>, start: &Pos, end: &Pos, text: &str,
+    profile: &SyntaxProfile,
+) {
+    match page_data.edit_doc(|d| d.paste_markdown(start, end, text, profile)) {
+        Ok(caret) => set_caret(page_data, &caret),
+        Err(err) => log!("PASTE FAILED: {}", err),
+    }
+}
+
+/// handle the `beforeinput` event. we cancel the browser's edit and make it 
+/// to the doc instead, so the doc is always the source of truth. the input 
+/// type says what the user is trying to do, so this works the same for 
+/// keyboards, dictation, autocorrect, etc
+pub fn process_beforeinput(event: InputEvent, page_data: RwSignal<Page>, 
+    syntax: RwSignal<SyntaxProfile>,
+) {
+    let input_type = event.input_type();
+
+    // while composing (IME, dead keys) the browser has to edit the DOM 
+    // itself, and these events can't be cancelled anyway. the doc catches 
+    // up once it's done (see `process_compositionend`)
+    if event.is_composing() || input_type.contains("Composition") { return }
+    event.prevent_default();
+
+    let Some((start, end)) = selected_range(page_data) else {
+        log!("SELECTION NOT IN TEXT");
+        return;
+    };
+    let collapsed = start == end;
+    let profile = syntax.get_untracked();
+    let mut typed_char = false;
+    let tx = match input_type.as_str() {
+        "insertText" | "insertReplacementText" | "insertFromPaste" | "insertFromDrop" => {
+            let text = event.data().or_else(|| {
+                event.data_transfer().and_then(|dt| dt.get_data("text/plain").ok())
+            });
+            let Some(text) = text else { return };
+            let text = text.replace("\r\n", "\n").replace('\r', "\n");
+            // several lines are parsed as markdown and go in as blocks
+            if text.contains('\n') {
+                return paste_lines(page_data, &start, &end, &text, &profile);
+            }
+            typed_char = input_type == "insertText" && text.chars().count() == 1;
+            // SPACE typed after a block prefix (e.g. "#")
+            let prefix_tx = (collapsed && text == " ")
+                .then(|| page_data.read_doc(|d| d.apply_block_prefix(&start, &profile)))
+                .flatten();
+            match prefix_tx {
+                Some(tx) => tx,
+                None => replace_range(&start, &end, 
+                    Op::InsertText { pos: start.clone(), text }),
+            }
+        },
+        "insertParagraph" | "insertLineBreak" => {
+            replace_range(&start, &end, Op::SplitBlock { pos: start.clone() })
+        },
+        "deleteContentBackward" if collapsed => page_data.read_doc(|d| d.backspace(&start)),
+        "deleteContentForward" if collapsed => page_data.read_doc(|d| d.forward_delete(&start)),
+        "deleteWordBackward" if collapsed => {
+            page_data.read_doc(|d| d.delete_word_backward(&start))
+        },
+        "deleteWordForward" if collapsed => {
+            page_data.read_doc(|d| d.delete_word_forward(&start))
+        },
+        "deleteHardLineBackward" if collapsed => {
+            page_data.read_doc(|d| d.delete_to_block_start(&start))
+        },
+        "deleteHardLineForward" if collapsed => {
+            page_data.read_doc(|d| d.delete_to_block_end(&start))
+        },
+        "deleteSoftLineBackward" if collapsed => delete_to_line_edge(page_data, &start, false),
+        "deleteSoftLineForward" if collapsed => delete_to_line_edge(page_data, &start, true),
+        // any delete removes the selection
+        t if t.starts_with("delete") && !collapsed => {
+            Op::DeleteRange { start: start.clone(), end }.into()
+        },
+        // the browser's undo (e.g. from the edit menu) would only know about 
+        // its own DOM edits, so use the page's history instead
+        "historyUndo" => return undo_edit(page_data, false),
+        "historyRedo" => return undo_edit(page_data, true),
+        // cancelled on purpose, formatting goes through the keymap
+        t if t.starts_with("format") => return,
+        _ => {
+            log!("UNHANDLED INPUT TYPE: {:?}", input_type);
+            return;
+        },
+    };
+    let Some(caret) = apply_edit(page_data, &tx, start) else { return };
+    // e.g. the closing `*` of `*word*`
+    if typed_char {
+        if let Some(tx) = page_data.read_doc(|d| d.apply_span_syntax(&caret, &profile)) {
+            apply_edit(page_data, &tx, Pos::new(&caret.hash, 0));
+        }
+    }
+}
+
+/// Cmd + Backspace/Delete. only the browser knows where the text wraps, so 
+/// get it to find the edge of the line
+fn delete_to_line_edge(page_data: RwSignal<Page>, pos: &Pos, forward: bool) -> Transaction {
+    let selection = document().get_selection().unwrap().unwrap();
+    let direction = if forward { "forward" } else { "backward" };
+    selection.modify("extend", direction, "lineboundary").unwrap();
+    match selected_range(page_data) {
+        Some((start, end)) if start != end => Op::DeleteRange { start, end }.into(),
+        // already at the edge of the line, so join it w/ the one next to it
+        _ => page_data.read_doc(|d| match forward {
+            true => d.forward_delete(pos),
+            false => d.backspace(pos),
+        }),
+    }
+}
+
+/// handle the `copy`/`cut` events. uses the doc selection, so it works even 
+/// if some of the selection isn't rendered
+pub fn process_copy(event: ClipboardEvent, page_data: RwSignal<Page>, 
+    syntax: RwSignal<SyntaxProfile>, cut: bool,
+) {
+    // whole blocks go as markdown
+    if let Some(blocks) = selected_block_hashes(page_data) {
+        event.prevent_default();
+        let profile = syntax.get_untracked();
+        let md = page_data.read_doc(|d| d.blocks_markdown(&blocks, &profile));
+        if let Some(clipboard) = event.clipboard_data() {
+            clipboard.set_data("text/plain", &md).unwrap();
+        }
+        if cut {
+            edit_blocks(Command::DeleteBlock, page_data);
+        }
+        return;
+    }
+    let Some((start, end)) = selected_range(page_data) else { return };
+    if start == end { return }
+    event.prevent_default();
+    let text = page_data.read_doc(|d| d.text_between(&start, &end));
+    if let Some(clipboard) = event.clipboard_data() {
+        clipboard.set_data("text/plain", &text).unwrap();
+    }
+    if cut {
+        apply_edit(page_data, &Op::DeleteRange { start: start.clone(), end }.into(), start);
+    }
+}
+
+/// the browser deletes the selection itself when composing starts, so do it 
+/// through the doc first
+pub fn process_compositionstart(page_data: RwSignal<Page>) {
+    let Some((start, end)) = selected_range(page_data) else { return };
+    if start == end { return }
+    apply_edit(page_data, &Op::DeleteRange { start: start.clone(), end }.into(), start);
+}
+
+/// the composed text is already in the DOM, so copy it into the doc
+pub fn process_compositionend(page_data: RwSignal<Page>) {
+    let Some((caret, _)) = selected_range(page_data) else { return };
+    sync_text_from_dom(page_data, &caret.hash);
+}
+
+/// handle the `input` event. the edits we make ourselves cancel the event, 
+/// so this only fires for edits the browser made to the DOM (composing, 
+/// and in safari the final text of a composition comes after 
+/// `compositionend`)
+pub fn process_input(event: web_sys::Event, page_data: RwSignal<Page>) {
+    let composing = event.dyn_ref::<InputEvent>().is_some_and(|e| e.is_composing());
+    if composing { return }
+    let Some((caret, _)) = selected_range(page_data) else { return };
+    sync_text_from_dom(page_data, &caret.hash);
+}
+
+/// the text in a `RawText` elem, as it would be in the doc
+pub fn text_from_elem(elem: &Element, doc_text: &str) -> String {
+    let mut text = elem.text_content().unwrap_or_default();
+    // empty text is shown as an `INVIS_CHAR`, which is still in the DOM
+    if doc_text.is_empty() { text = text.replacen(INVIS_CHAR, "", 1) }
+    // spaces are shown as nbsp (see `spaces_to_nbsp`)
+    text.replace('\u{a0}', " ")
+}
+
+/// make the doc's text match what the browser put in the text node. only 
+/// the part that changed is replaced
+pub fn sync_text_from_dom(page_data: RwSignal<Page>, hash: &String) {
+    let Some(node_sig) = page_data.hash_to_node(hash) else { return };
+    let Some(elem) = node_sig.get_untracked().elem_ref else { return };
+    let tx = page_data.read_doc(|d| d.diff_text(hash, &text_from_elem(&elem, d.text(hash))));
+    let Some(caret) = tx.ops.first().map(|op| match op {
+        Op::DeleteRange { start, .. } => start.clone(),
+        Op::InsertText { pos, .. } => pos.clone(),
+        _ => unreachable!(),
+    }) else { return };
+    apply_edit(page_data, &tx, caret);
+}
+
+/// handle the `keydown` event. text input is handled in `process_beforeinput` 
+/// so this is just shortcuts (see `keymap.rs`) and caret movement. the 
+/// browser can only move the caret to blocks in the DOM, so the block it's 
+/// moving to gets rendered first. moving within a block (Left/Right/Home/End) 
+/// is left to the browser, bc the caret's block is always rendered
+/// returns the command the key is bound to, for the page to run (see 
+/// `run_command` for the ones that only touch the doc)
+pub fn process_keydown(cx: Scope, event: KeyboardEvent, page_data: RwSignal<Page>, 
+    page_elem: &Element, keymap: RwSignal<Keymap>,
+) -> Option<Command> {
+    let command = event_chord(&event)
+        .and_then(|chord| keymap.update_returning_untracked(|k| k.command(&chord)).flatten());
+    if let Some(command) = command {
+        event.prevent_default();
+        return Some(command);
+    }
+    let key = event.key();
+    if key == "ArrowUp" || key == "ArrowDown" {
+        let forward = key == "ArrowDown";
+        let pos = caret_edge(page_data, forward)?;
+        // the block the caret goes to, so not a divider
+        let step = |block: &str| page_data.read_doc(|d| match forward {
+            true => d.next_text_block(block),
+            false => d.prev_text_block(block),
+        });
+        let mut next = page_data.read_doc(|d| d.block_of(&pos.hash)).and_then(|b| step(&b));
+        // the caret skips over folded blocks, same as the rendering does
+        while let Some(hidden) = next.clone().filter(|b| is_hidden(page_data, b)) {
+            next = step(&hidden);
+        }
+        // if it's already rendered this does nothing, and the browser 
+        // moves the caret as usual
+        if let Some(node_sig) = next.and_then(|hash| page_data.hash_to_node(&hash)) {
+            render_block(cx, page_data, page_elem, node_sig);
+        }
+    }
+    None
+}
+
+pub fn run_command(cx: Scope, command: Command, page_data: RwSignal<Page>, page_elem: &Element) {
+    match command {
+        Command::MoveToPageStart => move_to_page_edge(cx, page_data, page_elem, false),
+        Command::MoveToPageEnd => move_to_page_edge(cx, page_data, page_elem, true),
+        Command::PageUp => move_by_page(cx, page_data, page_elem, false),
+        Command::PageDown => move_by_page(cx, page_data, page_elem, true),
+        // select the whole page, not just what's rendered
+        Command::SelectAll => {
+            if let Some(sel) = page_data.read_doc(|d| d.select_all()) {
+                set_selection(page_data, sel);
+            }
+        },
+        Command::ToggleHeading => {
+            let Some((start, _)) = selected_range(page_data) else { return };
+            if let Some(tx) = page_data.read_doc(|d| d.toggle_heading(&start)) {
+                apply_edit(page_data, &tx, start);
+            }
+        },
+        Command::InsertTable => {
+            let Some((start, _)) = selected_range(page_data) else { return };
+            let Some(tx) = page_data.read_doc(|d| d.insert_table(&start)) else { return };
+            if apply_edit(page_data, &tx, start.clone()).is_none() { return }
+            // into the first cell
+            let cell = page_data.read_doc(|d| d.block_of(&start.hash)
+                .and_then(|b| d.next_leaf_block(&b)).and_then(|t| d.first_text(&t)));
+            if let Some(cell) = cell {
+                set_caret(page_data, &Pos::new(&cell, 2));
+            }
+        },
+        Command::Undo => undo_edit(page_data, false),
+        Command::Redo => undo_edit(page_data, true),
+        Command::Indent | Command::Outdent if code_tab(page_data, command == Command::Outdent) => {},
+        Command::MoveBlockUp | Command::MoveBlockDown | Command::DuplicateBlock 
+            | Command::DeleteBlock | Command::Indent | Command::Outdent => {
+            edit_blocks(command, page_data)
+        },
+        // these aren't about the doc, so the page runs them itself
+        Command::CommandPalette | Command::OpenFile | Command::ExportMarkdown 
+            | Command::ToggleVim | Command::Find | Command::FindInWorkspace 
+            | Command::Backlinks | Command::RenameNote => {},
+    }
+}
+
+/// Tab/Shift-Tab in a code block type/remove a tab instead of indenting it. 
+/// returns whether the caret was in code, so it was handled
+fn code_tab(page_data: RwSignal<Page>, outdent: bool) -> bool {
+    if selected_block_hashes(page_data).is_some() { return false }
+    let Some((start, end)) = selected_range(page_data) else { return false };
+    let Some((tx, caret)) = page_data.read_doc(|d| d.code_tab(&start, &end, outdent)) else { return false };
+    if tx.is_empty() { return true }
+    match page_data.edit_doc(|d| d.apply(&tx)) {
+        Ok(_) => set_caret(page_data, &caret),
+        Err(err) => log!("EDIT FAILED: {}", err),
+    }
+    true
+}
+
+/// the block commands. they act on the selected blocks in block selection 
+/// mode, otherwise all the blocks the text selection touches
+pub fn edit_blocks(command: Command, page_data: RwSignal<Page>) {
+    let blocks = match selected_block_hashes(page_data) {
+        Some(blocks) => blocks,
+        None => {
+            let Some((start, end)) = selected_range(page_data) else { return };
+            page_data.read_doc(|d| d.selected_blocks(&start, &end))
+        },
+    };
+    if command == Command::DeleteBlock {
+        end_block_selection(page_data, false);
+        let (tx, after) = page_data.read_doc(|d| d.delete_blocks(&blocks));
+        if let Err(err) = page_data.edit_doc(|d| d.apply(&tx)) {
+            log!("EDIT FAILED: {}", err);
+            return;
+        }
+        // the block after (or before) the deleted ones, or the empty block 
+        // that replaced them
+        let caret = page_data.read_doc(|d| after.or_else(|| d.text_blocks().into_iter().next())
+            .and_then(|b| d.first_text(&b)));
+        if let Some(caret) = caret {
+            set_caret(page_data, &Pos::new(&caret, 0));
+        }
+        return;
+    }
+    let tx = page_data.read_doc(|d| match command {
+        Command::MoveBlockUp => d.move_blocks(&blocks, true),
+        Command::MoveBlockDown => d.move_blocks(&blocks, false),
+        Command::Indent => d.indent_blocks(&blocks),
+        Command::Outdent => d.outdent_blocks(&blocks),
+        _ => d.duplicate_blocks(&blocks),
+    });
+    let Some(tx) = tx else { return };
+    let sel = page_data.update_returning_untracked(|p| p.selection.get_untracked()).flatten();
+    if let Err(err) = page_data.edit_doc(|d| d.apply(&tx)) {
+        log!("EDIT FAILED: {}", err);
+        return;
+    }
+    // the blocks keep their hashes, so the selection is still good. it just 
+    // has to be put back in the new elems
+    if let Some(sel) = sel {
+        set_selection(page_data, sel);
+    }
+}
+
+/// the end of the selection in the direction the caret is moving
+fn caret_edge(page_data: RwSignal<Page>, forward: bool) -> Option<Pos> {
+    let (start, end) = selected_range(page_data)?;
+    Some(if forward { end } else { start })
+}
+
+/// put the caret at the start/end of the page
+fn move_to_page_edge(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, end: bool) {
+    // the page can start/end w/ a divider, which has no text, or a fold
+    let blocks = page_data.read_doc(|d| d.text_blocks());
+    let mut shown = blocks.into_iter().filter(|b| !is_hidden(page_data, b));
+    let Some(block) = (if end { shown.last() } else { shown.next() }) else { return };
+    let pos = page_data.read_doc(|d| match end {
+        true => d.last_text(&block).map(|t| Pos::new(&t, d.text(&t).len())),
+        false => d.first_text(&block).map(|t| Pos::new(&t, 0)),
+    });
+    let Some(pos) = pos else { return };
+    jump_to_block(cx, page_data, page_elem, &block, !end);
+    set_caret(page_data, &pos);
+}
+
+/// move the caret about a screen's height up/down, going by the heights of 
+/// the blocks so it works for blocks that aren't rendered
+fn move_by_page(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, forward: bool) {
+    let Some(pos) = caret_edge(page_data, forward) else { return };
+    let Some(mut block) = page_data.read_doc(|d| d.block_of(&pos.hash)) else { return };
+    let view_height = page_elem.client_height().max(0) as u32;
+    let step = |block: &str| page_data.read_doc(|d| match forward {
+        true => d.next_leaf_block(block),
+        false => d.prev_leaf_block(block),
+    });
+    let mut moved = 0;
+    // only a block w/ text (not a divider) can have the caret
+    let mut target = None;
+    loop {
+        let mut next = step(&block);
+        // folded blocks take up no room, and the caret can't go in them
+        while let Some(hidden) = next.clone().filter(|b| is_hidden(page_data, b)) {
+            next = step(&hidden);
+        }
+        let Some(next) = next else { break };
+        moved += page_data.hash_to_node(&block).map_or(UNMEASURED_HEIGHT, est_leaf_height);
+        block = next;
+        let Some(text) = page_data.read_doc(|d| d.first_text(&block)) else { continue };
+        target = Some((block.clone(), text));
+        if moved >= view_height { break }
+    }
+    let Some((block, text)) = target else { return };
+    jump_to_block(cx, page_data, page_elem, &block, !forward);
+    set_caret(page_data, &Pos::new(&text, 0));
+}
@@ -0,0 +1,50 @@
+use leptos::{log, Scope, RwSignal, create_rw_signal, spawn_local, window, UntrackedSettableSignal};
+use tauri_sys::tauri;
+use web_sys::KeyboardEvent;
+
+use super::{Chord, Keymap, KeyBinding, Platform};
+
+/// mac gets Cmd shortcuts, everything else gets Ctrl ones
+pub fn current_platform() -> Platform {
+    let platform = window().navigator().platform().unwrap_or_default();
+    match platform.starts_with("Mac") {
+        true => Platform::Mac,
+        false => Platform::Other,
+    }
+}
+
+/// starts as the default keymap, then the user's bindings get put on top once 
+/// their config file is read
+pub fn load_keymap(cx: Scope) -> RwSignal<Keymap> {
+    let platform = current_platform();
+    let keymap = create_rw_signal(cx, Keymap::defaults(platform));
+    spawn_local(async move {
+        let overrides = match tauri::invoke::<_, Vec<KeyBinding>>("load_keymap", &()).await {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                log!("COULDN'T LOAD KEYMAP: {:?}", err);
+                return;
+            },
+        };
+        let (user_keymap, errors) = Keymap::with_overrides(platform, &overrides);
+        for err in errors {
+            log!("KEYMAP: {}", err);
+        }
+        keymap.set_untracked(user_keymap);
+    });
+    keymap
+}
+
+/// the chord for a keydown, or `None` for a modifier on its own
+pub fn event_chord(event: &KeyboardEvent) -> Option<Chord> {
+    let mut key = event.key();
+    // w/ Shift/Alt held the key is whatever char they type (e.g. Shift+7 is 
+    // "&"), but the binding is for the key itself
+    if (event.shift_key() || event.alt_key()) && key.chars().count() == 1 {
+        let code = event.code();
+        if let Some(k) = code.strip_prefix("Key").or_else(|| code.strip_prefix("Digit")) {
+            key = k.to_string();
+        }
+    }
+    Chord::from_event(&key, event.ctrl_key(), event.alt_key(), event.shift_key(), event.meta_key())
+}
@@ -4,11 +4,31 @@
 
 use leptos::*;
 // use src_ui::*;
+use src_model::*;
 
 mod editable_page; use editable_page::*;
 mod render_in_view; use render_in_view::*;
 mod page_data; use page_data::*;
-mod keypress; use keypress::*;
+mod input; use input::*;
+mod selection; use selection::*;
+mod mouse; use mouse::*;
+mod reconcile; use reconcile::*;
+mod keymap; use keymap::*;
+mod syntax; use syntax::*;
+mod slash_menu; use slash_menu::*;
+mod palette; use palette::*;
+mod files; use files::*;
+mod vim; use vim::*;
+mod drag; use drag::*;
+mod block_selection; use block_selection::*;
+mod folding; use folding::*;
+mod outline; use outline::*;
+mod find; use find::*;
+mod workspace_find; use workspace_find::*;
+mod wikilink; use wikilink::*;
+mod backlinks; use backlinks::*;
+mod rename; use rename::*;
+mod history; use history::*;
 
 pub fn main() {
     _ = console_log::init_with_level(log::Level::Debug);
@@ -0,0 +1,95 @@
+use leptos::{RwSignal, document, JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use leptos::wasm_bindgen::closure::Closure;
+use web_sys::{Element, HtmlElement, MouseEvent};
+
+use super::{Page, Pos, Selection, ReadDoc, dom_to_pos, set_selection};
+
+// the browser's own mouse selection can only reach blocks in the DOM, and 
+// loses its anchor when the block it's in is unrendered mid-drag (see 
+// `render_in_view.rs`). so clicks/drags set the doc selection instead, and the 
+// DOM selection just shows it
+
+/// the scroll window, which the page elem is in
+fn scroll_window(page_data: RwSignal<Page>) -> Option<Element> {
+    let page = page_data.get_untracked().nodes.get_untracked().elem_ref?;
+    page.parent_element()
+}
+
+/// the doc position under the mouse. the point is kept inside the scroll 
+/// window so dragging above/below it selects to the edge of what's rendered
+fn pos_at_point(page_data: RwSignal<Page>, event: &MouseEvent) -> Option<Pos> {
+    let rect = scroll_window(page_data)?.get_bounding_client_rect();
+    let x = (event.client_x() as f64).clamp(rect.left() + 1.0, rect.right() - 1.0);
+    let y = (event.client_y() as f64).clamp(rect.top() + 1.0, rect.bottom() - 1.0);
+    let range = document().caret_range_from_point(x as f32, y as f32)?;
+    dom_to_pos(page_data, &range.start_container().ok()?, range.start_offset().ok()?)
+}
+
+/// handle `mousedown`. a single click puts the caret down (or extends the 
+/// selection w/ shift), a double click selects the word, a triple click the 
+/// block
+pub fn process_mousedown(event: MouseEvent, page_data: RwSignal<Page>, 
+    drag_anchor: RwSignal<Option<Pos>>,
+) {
+    // right click is for the context menu
+    if event.button() != 0 { return }
+    let Some(pos) = pos_at_point(page_data, &event) else { return };
+    // stop the browser making its own selection. this also stops it focusing 
+    // the page, so do that ourselves
+    event.prevent_default();
+    if let Some(page) = page_data.get_untracked().nodes.get_untracked().elem_ref {
+        if let Some(page) = page.dyn_ref::<HtmlElement>() { page.focus().unwrap() }
+    }
+    let sel = match event.detail() {
+        2 => page_data.read_doc(|d| d.select_word(&pos)),
+        3.. => page_data.read_doc(|d| d.select_block(&pos)),
+        _ if event.shift_key() => {
+            let sel = page_data.get_untracked().selection.get_untracked()
+                .filter(|sel| page_data.read_doc(|d| d.selection_is_valid(sel)));
+            Selection::new(sel.map_or(pos.clone(), |sel| sel.anchor), pos)
+        },
+        _ => Selection::caret(pos),
+    };
+    drag_anchor.set_untracked(Some(sel.anchor.clone()));
+    set_selection(page_data, sel);
+}
+
+fn process_mousemove(event: MouseEvent, page_data: RwSignal<Page>, 
+    drag_anchor: RwSignal<Option<Pos>>,
+) {
+    let Some(anchor) = drag_anchor.get_untracked() else { return };
+    // the button was let go somewhere we didn't get the `mouseup`
+    if event.buttons() & 1 == 0 {
+        drag_anchor.set_untracked(None);
+        return;
+    }
+    // dragging above/below the scroll window scrolls it (which renders more 
+    // blocks)
+    let Some(scroll_window) = scroll_window(page_data) else { return };
+    let rect = scroll_window.get_bounding_client_rect();
+    let y = event.client_y() as f64;
+    if y < rect.top() {
+        scroll_window.scroll_by_with_x_and_y(0.0, y - rect.top());
+    } else if y > rect.bottom() {
+        scroll_window.scroll_by_with_x_and_y(0.0, y - rect.bottom());
+    }
+    let Some(head) = pos_at_point(page_data, &event) else { return };
+    set_selection(page_data, Selection::new(anchor, head));
+}
+
+/// follow drags, even when the mouse leaves the page
+pub fn track_mouse_drag(page_data: RwSignal<Page>, drag_anchor: RwSignal<Option<Pos>>) {
+    let on_mousemove = Closure::<dyn FnMut(_)>::new(move |event: MouseEvent| {
+        process_mousemove(event, page_data, drag_anchor);
+    });
+    let on_mouseup = Closure::<dyn FnMut(_)>::new(move |_: MouseEvent| {
+        drag_anchor.set_untracked(None);
+    });
+    document().add_event_listener_with_callback("mousemove", 
+        on_mousemove.as_ref().unchecked_ref()).unwrap();
+    document().add_event_listener_with_callback("mouseup", 
+        on_mouseup.as_ref().unchecked_ref()).unwrap();
+    // the page lives as long as the app, so the listeners do too
+    on_mousemove.forget();
+    on_mouseup.forget();
+}
@@ -0,0 +1,154 @@
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, create_rw_signal, document, JsCast, 
+    UntrackedGettableSignal, UntrackedSettableSignal};
+use web_sys::{Element, EventTarget, MouseEvent};
+
+use super::{PageView, Page, Pos, OutlineEntry, ReadDoc, EditDoc, rerender_view,
+    jump_to_block, set_caret};
+
+// the outline sidebar: the page's headings, indented by how they nest. the
+// one for the section at the top of the view is highlighted. clicking one
+// jumps to it, and dragging one onto another moves its whole section there
+// (or to the end of the page if dropped below the last one)
+
+/// the entry elem `target` is in, and its heading's hash
+fn entry_at(target: Option<EventTarget>) -> Option<(Element, String)> {
+    let elem = target?.dyn_into::<Element>().ok()?.closest("[heading]").ok()??;
+    let hash = elem.get_attribute("heading")?;
+    Some((elem, hash))
+}
+
+/// redraw the outline if the headings changed. call after any edit
+pub fn show_outline(page_data: RwSignal<Page>, outline: RwSignal<Vec<OutlineEntry>>,
+    outline_elem: &Element,
+) {
+    let entries = page_data.read_doc(|d| d.outline());
+    if outline.update_returning_untracked(|o| *o == entries).unwrap() { return }
+    outline_elem.set_inner_html("");
+    for entry in &entries {
+        let entry_elem = document().create_element("div").unwrap();
+        entry_elem.set_attribute("heading", &entry.hash).unwrap();
+        entry_elem.set_attribute("style", &format!("padding-left: {}px; cursor: pointer; \
+            white-space: nowrap; overflow: hidden; text-overflow: ellipsis", entry.depth * 12)).unwrap();
+        entry_elem.set_text_content(Some(&entry.text));
+        outline_elem.append_child(&entry_elem).unwrap();
+    }
+    outline.set_untracked(entries);
+}
+
+/// highlight the entry for the section the top of the view is in
+pub fn highlight_outline(page_data: RwSignal<Page>, outline_elem: &Element) {
+    let top = page_data.update_returning_untracked(|p| p.top_elem.get_untracked().hash).unwrap();
+    let current = page_data.read_doc(|d| d.contains(&top).then(|| d.current_heading(&top)).flatten());
+    let entries = outline_elem.children();
+    for i in 0..entries.length() {
+        let Some(entry) = entries.item(i) else { continue };
+        let is_current = current.is_some() && entry.get_attribute("heading") == current;
+        match is_current {
+            true => entry.set_attribute("current", "").unwrap(),
+            false => entry.remove_attribute("current").unwrap(),
+        }
+    }
+}
+
+pub fn process_outline_mousedown(event: MouseEvent, dragging: RwSignal<Option<String>>) {
+    if event.button() != 0 { return }
+    // don't let the browser start a selection
+    event.prevent_default();
+    dragging.set_untracked(entry_at(event.target()).map(|(_, hash)| hash));
+}
+
+/// mark where the dragged section would go
+pub fn process_outline_mousemove(event: MouseEvent, dragging: RwSignal<Option<String>>,
+    outline_elem: &Element,
+) {
+    let marked = outline_elem.query_selector_all("[drop-before]").unwrap();
+    for i in 0..marked.length() {
+        if let Some(elem) = marked.get(i).and_then(|n| n.dyn_into::<Element>().ok()) {
+            elem.remove_attribute("drop-before").unwrap();
+        }
+    }
+    let Some(dragged) = dragging.get_untracked() else { return };
+    if event.buttons() & 1 == 0 {
+        dragging.set_untracked(None);
+        return;
+    }
+    if let Some((elem, hash)) = entry_at(event.target()) {
+        if hash != dragged {
+            elem.set_attribute("drop-before", "").unwrap();
+        }
+    }
+}
+
+/// a click jumps to the heading, a drag moves its section
+pub fn process_outline_mouseup(cx: Scope, event: MouseEvent, page_data: RwSignal<Page>,
+    page_elem: &Element, dragging: RwSignal<Option<String>>,
+) {
+    let Some(dragged) = dragging.get_untracked() else { return };
+    dragging.set_untracked(None);
+    let target = entry_at(event.target()).map(|(_, hash)| hash);
+    if target.as_ref() == Some(&dragged) {
+        jump_to_block(cx, page_data, page_elem, &dragged, true);
+        if let Some(text) = page_data.read_doc(|d| d.first_text(&dragged)) {
+            set_caret(page_data, &Pos::new(&text, 0));
+        }
+        return;
+    }
+    let Some(tx) = page_data.read_doc(|d| d.move_section(&dragged, target.as_deref())) else { return };
+    if let Err(err) = page_data.edit_doc(|d| d.apply(&tx)) {
+        return log!("MOVE FAILED: {}", err);
+    }
+    // the section may have come from/gone somewhere that wasn't rendered
+    rerender_view(cx, page_data, page_elem);
+}
+
+/// the sidebar's state and elem, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct OutlineView {
+    pub outline: RwSignal<Vec<OutlineEntry>>,
+    /// the heading being dragged, while the button is down
+    pub dragging: RwSignal<Option<String>>,
+    pub outline_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl OutlineView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            outline: create_rw_signal(cx, Vec::new()),
+            dragging: create_rw_signal(cx, None),
+            outline_ref: NodeRef::new(cx),
+        }
+    }
+
+    /// list the headings again, after an edit
+    pub fn show(&self, page: PageView) {
+        if let Some(outline_elem) = self.outline_ref.get() {
+            let outline_elem = outline_elem.unchecked_ref::<Element>();
+            show_outline(page.page_data, self.outline, outline_elem);
+            highlight_outline(page.page_data, outline_elem);
+        }
+    }
+
+    /// mark the heading on screen, after a scroll
+    pub fn highlight(&self, page: PageView) {
+        if let Some(outline_elem) = self.outline_ref.get() {
+            highlight_outline(page.page_data, outline_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent) {
+        process_outline_mousedown(event, self.dragging);
+    }
+
+    pub fn handle_mousemove(&self, event: MouseEvent) {
+        if let Some(outline_elem) = self.outline_ref.get() {
+            process_outline_mousemove(event, self.dragging, outline_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn handle_mouseup(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn()) {
+        if let Some(page_elem) = page.page_elem() {
+            process_outline_mouseup(page.cx, event, page.page_data, &page_elem, self.dragging);
+            refresh_view();
+        }
+    }
+}
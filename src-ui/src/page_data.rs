@@ -1,10 +1,10 @@
 use leptos::{log, Scope, RwSignal, create_rw_signal, js_sys::Math, 
     UntrackedSettableSignal, UntrackedGettableSignal, JsCast};
-use web_sys::{Node, Element, CharacterData, Selection};
-use std::{hash::{Hash, Hasher}, collections::{HashMap, hash_map::DefaultHasher}};
+use web_sys::{Node, Element};
+use std::collections::{HashMap, HashSet};
 
-use super::{get_node_from_location, ElemIsInView, CreateElem, 
-    new_cursor_position, spaces_to_nbsp};
+use super::{Doc, Change, NodeTree, Op, Selection, BlockSelection, History, PageNodeType, ROOT_HASH, 
+    get_node_from_location, ElemIsInView, CreateElem, spaces_to_nbsp, INVIS_CHAR};
 
 // tried doing `struct PageSignal(RwSignal<Page>)` wrapper but it introduced 
 // waaaaaaaaay too much complexity that i cbf solving
@@ -15,18 +15,25 @@ pub struct Page {
     pub top_elem: RwSignal<EdgeElem>,
     pub bot_elem: RwSignal<EdgeElem>,
     pub locations: RwSignal<HashMap<String, Vec<usize>>>,
-    // pub undo_hist: RwSignal<Vec<UndoEvent>>,
-}
-// /// this also covers redo evvents
-// struct UndoEvent {
-//     start_pos: Vec<usize>,
-//     end_pos: Vec<usize>,
-//     kind: UndoEventKind,
-//     content: String, // this might need to be `PageNode`
-// }
-// enum UndoEventKind {
-//     Insert, Remove
-// }
+    /// the source of truth for the page. `nodes` is just a copy of it with 
+    /// the DOM attached, kept in sync by `patch_page_nodes`
+    pub doc: RwSignal<Doc>,
+    /// the selection in doc positions, so it isn't lost when the blocks it's 
+    /// in get unrendered (see `selection.rs`)
+    pub selection: RwSignal<Option<Selection>>,
+    /// the selection last put in the DOM. it can differ from `selection` if 
+    /// one end isn't rendered
+    pub shown_selection: RwSignal<Option<Selection>>,
+    /// whole blocks selected in block selection mode. while it's set, the 
+    /// text selection isn't shown (see `block_selection.rs`)
+    pub block_selection: RwSignal<Option<BlockSelection>>,
+    /// the folded blocks, and the blocks they hide (see `folding.rs`)
+    pub folds: RwSignal<HashSet<String>>,
+    pub hidden: RwSignal<HashSet<String>>,
+    /// every edit made through `edit_doc`, so it can be undone (see 
+    /// `history.rs`)
+    pub history: RwSignal<History>,
+}
 pub trait InsertHash {
     fn insert_hash(&self, hash: String, location: Vec<usize>);
 }
@@ -84,19 +91,62 @@ pub trait HashToNode {
 }
 impl HashToNode for RwSignal<Page> {
     fn hash_to_node(&self, hash: &String) -> Option<RwSignal<PageNode>> {
-        let loc = self.hash_to_location(hash);
-        let nodes = self.update_returning_untracked(|p| {
-            p.nodes.get().children
+        let (nodes, locations) = self.update_returning_untracked(|p| {
+            (p.nodes, p.locations)
         }).unwrap();
-        get_node_from_location(&loc, &nodes)
+        // the root isn't in `locations` bc it has no location
+        if hash == ROOT_HASH { return Some(nodes) }
+        let loc = locations.update_returning_untracked(|ls| {
+            ls.get(hash).cloned()
+        }).unwrap()?;
+        get_node_from_location(&loc, &nodes.get_untracked().children)
     }
 }
 impl Page {
     pub fn signal_from(cx: Scope, nodes: RwSignal<PageNode>, 
         top_elem: RwSignal<EdgeElem>, bot_elem: RwSignal<EdgeElem>, 
-        locations: RwSignal<HashMap<String, Vec<usize>>>,
+        locations: RwSignal<HashMap<String, Vec<usize>>>, 
+        doc: RwSignal<Doc>,
     ) -> RwSignal<Self> {
-        create_rw_signal(cx, Self {nodes, top_elem, bot_elem, locations}) 
+        let selection = create_rw_signal(cx, None);
+        let shown_selection = create_rw_signal(cx, None);
+        let block_selection = create_rw_signal(cx, None);
+        let folds = create_rw_signal(cx, HashSet::new());
+        let hidden = create_rw_signal(cx, HashSet::new());
+        let history = create_rw_signal(cx, History::default());
+        create_rw_signal(cx, Self {nodes, top_elem, bot_elem, locations, doc, 
+            selection, shown_selection, block_selection, folds, hidden, history}) 
+    }
+}
+pub trait EditDoc {
+    fn edit_doc<T>(&self, f: impl FnOnce(&mut Doc) -> T) -> T;
+}
+impl EditDoc for RwSignal<Page> {
+    /// make changes to the doc. the `PageNode`s and DOM get patched to match 
+    /// as each change is made (see `patch_page_nodes`). whatever `f` applies 
+    /// is undone as one step
+    fn edit_doc<T>(&self, f: impl FnOnce(&mut Doc) -> T) -> T {
+        let (doc, selection, history) = self.update_returning_untracked(|p| {
+            (p.doc, p.selection, p.history)
+        }).unwrap();
+        let before = selection.get_untracked();
+        let (res, inverse) = doc.update_returning_untracked(|d| {
+            d.start_journal();
+            let res = f(d);
+            (res, d.end_journal())
+        }).unwrap();
+        history.update_untracked(|h| h.record(inverse, before));
+        sync_edge_elems(*self);
+        res
+    }
+}
+pub trait ReadDoc {
+    fn read_doc<T>(&self, f: impl FnOnce(&Doc) -> T) -> T;
+}
+impl ReadDoc for RwSignal<Page> {
+    fn read_doc<T>(&self, f: impl FnOnce(&Doc) -> T) -> T {
+        let doc = self.update_returning_untracked(|p| p.doc).unwrap();
+        doc.update_returning_untracked(|d| f(d)).unwrap()
     }
 }
 impl Page {
@@ -195,6 +245,18 @@ impl PageNode {
     ) -> RwSignal<Self> {
         create_rw_signal(cx, Self {hash, kind, content, children, parent, elem_ref: None, height})
     }
+    /// create the node (and its children) from a doc tree. nothing is rendered
+    pub fn signal_from_tree(cx: Scope, tree: &NodeTree, 
+        parent: Option<RwSignal<PageNode>>,
+    ) -> RwSignal<Self> {
+        let node_sig = Self::signal_from(cx, tree.hash.clone(), tree.kind.clone(), 
+            tree.content.clone(), Vec::new(), parent, 0);
+        let children = tree.children.iter()
+            .map(|child| Self::signal_from_tree(cx, child, Some(node_sig)))
+            .collect();
+        node_sig.update_untracked(|n| n.children = children);
+        node_sig
+    }
     pub fn is_block(&self) -> bool {
         self.kind.is_block()
     }
@@ -361,7 +423,15 @@ pub trait AppendNode {
 impl AppendNode for RwSignal<PageNode> {
     fn append_node(&self, node_sig: RwSignal<PageNode>) {
         self.update_untracked(|parent| {
-            let parent_elem = parent.elem_ref.clone().unwrap();
+            // parent isn't rendered, so neither is the node
+            let Some(parent_elem) = parent.elem_ref.clone() else {
+                node_sig.update_untracked(|n| {
+                    n.elem_ref = None;
+                    n.parent = Some(self.clone());
+                });
+                parent.children.push(node_sig);
+                return
+            };
             // if the node we're moving is already rendered, use it
             let node_elem = match node_sig.get().elem_ref {
                 Some(v) => v,
@@ -408,7 +478,17 @@ impl InsertNodes for RwSignal<PageNode> {
                     None => None,
                 };
 
-                let parent_elem = parent.elem_ref.clone().unwrap();
+                // parent isn't rendered, so neither are the nodes
+                let Some(parent_elem) = parent.elem_ref.clone() else {
+                    for (i, node_sig) in nodes_to_insert.iter().enumerate() {
+                        node_sig.update_untracked(|n| {
+                            n.elem_ref = None;
+                            n.parent = Some(self.clone());
+                        });
+                        parent.children.insert(idx+i, node_sig.to_owned());
+                    }
+                    return
+                };
                 // must check EACH elem is in view to then remove if not bc 
                 // even if you track the first elem that is not in view, the 
                 // first elems might not be in view, but the later ones might be
@@ -450,85 +530,25 @@ impl InsertNodes for RwSignal<PageNode> {
     }
 }
 
-pub trait RemoveThisBlockShell {
-    fn remove_this_block_shell(&self);
-}
-impl RemoveThisBlockShell for RwSignal<PageNode> {
-    /// remove this block, while placing the contents on the same level as 
-    /// this block
-    fn remove_this_block_shell(&self) {
-        let curr_block = self.get_untracked();
-        // remove each child, using this block as the node to insert before
-        let parent_sig = curr_block.parent.unwrap();
-        // log!("pre-len: {:?}", parent_sig.get().children.len());
-        parent_sig.insert_nodes(&curr_block.children, Some(self));
-        // log!("post-len: {:?}", parent_sig.get().children.len());
-        // remove this block
-        parent_sig.remove_child(self);
-    }
+pub trait SetText {
+    fn set_text(&self, text: &str);
 }
-
-pub trait InsertChar {
-    fn insert_char(&self, char: &str, text_node: &CharacterData, offset: u32, selection: &Selection);
-}
-impl InsertChar for RwSignal<PageNode> {
-    fn insert_char(&self, char: &str, text_node: &CharacterData, offset: u32, selection: &Selection) {
-        // there is an indexing mismatch between rust and javascript for 
-        // unicode text, so the easiest way to modify the rust data is to 
-        // simply copy the javascript text
-
-        let char_len = char.len();
-
-        // originally did `text_node.insert_data(offset, dom_char).unwrap();`, 
-        // but can't add 2 spaces in a row, and trying to add `&nbsp;` doesn't 
-        // convert it to a space when displayed
-        let txt_len = text_node.length();
-        let start_txt = text_node.substring_data(0, offset).unwrap();
-        let end_txt = text_node.substring_data(offset, txt_len - offset).unwrap();
-        let new_txt = format!("{}{}{}", start_txt, char, end_txt);
-
+impl SetText for RwSignal<PageNode> {
+    /// set the text of a `RawText` node (and its DOM elem if rendered)
+    fn set_text(&self, text: &str) {
         self.update_untracked(|n| {
-            let elem = n.elem_ref.clone().unwrap();
-            elem.set_inner_html(&spaces_to_nbsp(&new_txt));
-
-            let txt_node: CharacterData = elem.first_child().unwrap().dyn_into().unwrap();
-            new_cursor_position(selection, &txt_node, offset + char_len as u32);
-
-            n.content.insert("text".into(), new_txt).unwrap();
+            if let Some(elem) = &n.elem_ref {
+                elem.set_text_content(Some(&display_text(text)));
+            }
+            n.content.insert("text".into(), text.into());
         });
     }
 }
-
-pub trait RemoveChar {
-    fn remove_char(&self, text_node: &CharacterData, offset: u32, selection: &Selection);
-}
-impl RemoveChar for RwSignal<PageNode> {
-    fn remove_char(&self, text_node: &CharacterData, offset: u32, selection: &Selection) {
-        // there is an indexing mismatch between rust and javascript for 
-        // unicode text, so the easiest way to modify the rust data is to 
-        // simply copy the javascript text
-        let txt_len = text_node.length();
-        let start_txt = text_node.substring_data(0, offset - 1).unwrap();
-        let end_txt = text_node.substring_data(offset, txt_len - offset).unwrap();
-        let mut new_txt = format!("{}{}", start_txt, end_txt);
-        // if line/span is empty, you can no longer place cursor on than , 
-        // thus, we need to place a zero width invisible char instead, and 
-        // handle delete/select/arrow/add-first-char so it behaves like there 
-        // is no char rather than behave like there is a zero width char
-        if new_txt == "" { new_txt = "\u{a0}".to_string() } // FIXME: convert from space to INVIS_CHAR
-
-        // TODO: need to set span to raw text ndoe type if `new_txt == ""`
-
-        self.update_untracked(|n| {
-            let elem = n.elem_ref.clone().unwrap();
-            elem.set_inner_html(&spaces_to_nbsp(&new_txt));
-
-            let txt_node: CharacterData = elem.first_child().unwrap().dyn_into().unwrap();
-            new_cursor_position(selection, &txt_node, offset - 1);
-
-            n.content.insert("text".into(), new_txt).unwrap();
-        });
-    }
+/// the text as it's shown in the DOM. an empty text node can't hold the 
+/// caret, so it gets an invisible char instead
+pub fn display_text(text: &str) -> String {
+    if text.is_empty() { return INVIS_CHAR.into() }
+    spaces_to_nbsp(text)
 }
 
 // struct RwSignal<T>()
@@ -538,119 +558,6 @@ impl RemoveChar for RwSignal<PageNode> {
 //     }
 // }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum PageNodeType {
-    // root
-    Page, // using Page as root so able to attach a method to index node (as 
-          // otherwise you would have to first index the root RwSignal<Vec<PageNode>> 
-          // before runniing PageNode index method)
-          //
-          // also it allows you to insert root level elements without needing 
-          // to have access to elem_ref, nor account for the spacers at top 
-          // and bottom
-    // block-branch
-    Indent, Quote, 
-    // block-leaf
-    TextBlock, H1, H2, H3, H4, H5, CodeBlock, Dot, Num, Check, Table, // tbh table could be a branch too ???
-    // text-branch
-    Bold, Italic, Highlight, CodeInline, FileLink, UrlLink,
-    // text-leaf
-    RawText,
-}
-#[derive(Clone, Copy)]
-struct PageNodeTypeInfo {
-    val: &'static str,
-    /// true = block, false = span
-    block: bool,
-    /// true = branch, false = leaf
-    branch: bool,
-    innate_height: u32,
-}
-const NUM_TYPES: usize = 21;
-// NOTE: THIS MUST BE IN SAME ORDER AS THE ENUM  FOR THE INDEXING TO WORK
-const PAGE_NODE_TYPE_INFO: [PageNodeTypeInfo; NUM_TYPES] = [
-    // Page
-    PageNodeTypeInfo { val: "p", block: true, branch: true, innate_height: 0 },
-
-    // Indent
-    PageNodeTypeInfo { val: "in", block: true, branch: true, innate_height: 0 },
-    // Quote
-    PageNodeTypeInfo { val: "q", block: true, branch: true, innate_height: 0 },
-
-    // TextBlock
-    PageNodeTypeInfo { val: "tb", block: true, branch: false, innate_height: 0 },
-    // H1
-    PageNodeTypeInfo { val: "h1", block: true, branch: false, innate_height: 0 },
-    // H2
-    PageNodeTypeInfo { val: "h2", block: true, branch: false, innate_height: 0 },
-    // H3
-    PageNodeTypeInfo { val: "h3", block: true, branch: false, innate_height: 0 },
-    // H4
-    PageNodeTypeInfo { val: "h4", block: true, branch: false, innate_height: 0 },
-    // H5
-    PageNodeTypeInfo { val: "h5", block: true, branch: false, innate_height: 0 },
-    // CodeBlock
-    PageNodeTypeInfo { val: "cd", block: true, branch: false, innate_height: 0 },
-    // Dot
-    PageNodeTypeInfo { val: "d", block: true, branch: false, innate_height: 0 },
-    // Num
-    PageNodeTypeInfo { val: "n", block: true, branch: false, innate_height: 0 },
-    // Check
-    PageNodeTypeInfo { val: "ch", block: true, branch: false, innate_height: 0 },
-    // Table
-    PageNodeTypeInfo { val: "tl", block: true, branch: false, innate_height: 0 },
-
-    // Bold
-    PageNodeTypeInfo { val: "b", block: false, branch: true, innate_height: 0 },
-    // Italic
-    PageNodeTypeInfo { val: "i", block: false, branch: true, innate_height: 0 },
-    // Highlight
-    PageNodeTypeInfo { val: "h", block: false, branch: true, innate_height: 0 },
-    // CodeInline
-    PageNodeTypeInfo { val: "ci", block: false, branch: true, innate_height: 0 },
-    // FileLink
-    PageNodeTypeInfo { val: "fl", block: false, branch: true, innate_height: 0 },
-    // UrlLink
-    PageNodeTypeInfo { val: "ul", block: false, branch: true, innate_height: 0 },
-
-    // RawText
-    PageNodeTypeInfo { val: "t", block: false, branch: false, innate_height: 0 },
-];
-
-impl PageNodeType {
-    pub fn value(&self) -> &str {
-        PAGE_NODE_TYPE_INFO[self.clone() as usize].val
-    }
-    pub fn is_block(&self) -> bool {
-        PAGE_NODE_TYPE_INFO[self.clone() as usize].block
-    }
-    pub fn is_branch(&self) -> bool {
-        PAGE_NODE_TYPE_INFO[self.clone() as usize].branch
-    }
-    /// get the height of the node without any contents (e.g. if the quote 
-    /// block had top/bottom padding, get the sum height of the padding)
-    pub fn innate_height(&self) -> u32 {
-        PAGE_NODE_TYPE_INFO[self.clone() as usize].innate_height
-    }
-}
-
-pub fn add_hashes(nodes: &Vec<RwSignal<PageNode>>, location: Vec<usize>, 
-    locations: RwSignal<HashMap<String, Vec<usize>>>) {
-    for (i, node) in nodes.iter().enumerate() {
-        let mut location = location.clone();
-        location.push(i);
-        // create & add hash/location
-        let mut hash = rand_utf8_hash();
-        loop { // keep generating until find hash not already used
-            if !locations.contains_hash(&hash) { break }
-            hash = rand_utf8_hash();
-        }
-        locations.insert_hash(hash.clone(), location.clone());
-        node.update_untracked(|e| e.hash = hash.clone());
-        // if children present in node, update those too
-        add_hashes(&node.get().children, location, locations);
-    }
-}
 /// overwrite each hash location in `locations` with its current location
 pub fn update_hash_locations(page_data: &RwSignal<Page>) {
     let locations = page_data.get_untracked().locations;
@@ -672,134 +579,170 @@ pub fn update_hash_locations_recursive(nodes: &Vec<RwSignal<PageNode>>, location
     }
 }
 
-/// generate a utf-8 hash string of length 3
-pub fn rand_utf8_hash() -> String {
-    // chars used: 256 utf-8
-    // generated random number: u64 = 2^64 = 18.4 quintillion
-    // 256^3 = 16.777 million
-    // 2^24 = 16.777 million <-- steel-manning max number of hashes needed lets assume each word has its own hash. at 17 million words, the text file would be approx 100mb. these seems extremely unlikely. https://www.quora.com/If-I-would-have-a-1GB-text-file-txt-approximately-how-many-words-would-it-include
-    // 256^4 = 4.29 billion
-    // 2^32 = 4.29 billion
-    let gen_rand_num = || {
-        let mut hasher = DefaultHasher::new();
-        Math::random().to_bits().hash(&mut hasher);
-        let bits_32 = hasher.finish() as u32;
-        // 32 - 24 = 8
-        let clipper = u32::MAX >> 8;
-        let clipped = bits_32 & clipper;
-        clipped
+/// the children of `parent_sig` from `from` on (and everything in them) 
+/// moved along, so put their new locations in `locations`
+fn relocate_children(page_data: RwSignal<Page>, parent_sig: RwSignal<PageNode>, from: usize) {
+    let locations = page_data.get_untracked().locations;
+    let parent = parent_sig.get_untracked();
+    let location = match parent.hash == ROOT_HASH {
+        true => Vec::new(),
+        false => page_data.hash_to_location(&parent.hash),
     };
-    // we're treating each char in the hash as a number with base 256 (like 
-    // how the decimal system uses a number with base 10)
-    const BASE: u32 = 256_u32;
-    let mut hash_str = String::new();
-    let mut carry = gen_rand_num();
-    loop { // loop to generate each of the 3 chars of the hash
-        let rem = carry % BASE;
-        hash_str.push(char::from_u32(rem).unwrap());
-        if carry == rem { return hash_str }
-        carry = (carry - rem) / BASE;
+    for (i, child) in parent.children.iter().enumerate().skip(from) {
+        let mut location = location.clone();
+        location.push(i);
+        let child = child.get_untracked();
+        locations.insert_hash(child.hash, location.clone());
+        update_hash_locations_recursive(&child.children, location, locations);
+    }
+}
+/// drop the locations of the node and everything in it, once it's removed
+fn forget_locations(page_data: RwSignal<Page>, node_sig: RwSignal<PageNode>) {
+    let node = node_sig.get_untracked();
+    page_data.get_untracked().locations.remove_hash(&node.hash);
+    for child in node.children {
+        forget_locations(page_data, child);
+    }
+}
+fn index_in_parent(parent_sig: RwSignal<PageNode>, node_sig: RwSignal<PageNode>) -> usize {
+    parent_sig.get_untracked().children.iter().position(|c| *c == node_sig).unwrap()
+}
+
+/// keep the `PageNode` tree (and the DOM) in sync with the doc. this is the 
+/// doc's subscriber, so it gets called for every change to the doc. only the 
+/// locations that moved are updated, and the edge elems are synced once the 
+/// whole edit is done (see `edit_doc`)
+/// 
+/// NOTE: this runs while the doc is being edited, so it must not read the doc
+pub fn patch_page_nodes(cx: Scope, page_data: RwSignal<Page>, change: &Change) {
+    match change {
+        Change::Inserted { parent, index, node } => {
+            let parent_sig = page_data.hash_to_node(parent).unwrap();
+            let node_sig = PageNode::signal_from_tree(cx, node, Some(parent_sig));
+            let before = parent_sig.get_untracked().children.get(*index).cloned();
+            parent_sig.insert_nodes(&vec![node_sig], before.as_ref());
+            relocate_children(page_data, parent_sig, *index);
+        },
+        Change::Removed { hash } => {
+            let node_sig = page_data.hash_to_node(hash).unwrap();
+            let parent_sig = node_sig.get_untracked().parent.unwrap();
+            let old_index = index_in_parent(parent_sig, node_sig);
+            parent_sig.remove_child(&node_sig);
+            forget_locations(page_data, node_sig);
+            relocate_children(page_data, parent_sig, old_index);
+        },
+        Change::Moved { hash, parent, index } => {
+            let node_sig = page_data.hash_to_node(hash).unwrap();
+            let old_parent_sig = node_sig.get_untracked().parent.unwrap();
+            let old_index = index_in_parent(old_parent_sig, node_sig);
+            // removing the elem from the DOM keeps `elem_ref`, so 
+            // `insert_nodes` moves the same elem rather than creating a new one
+            old_parent_sig.remove_child(&node_sig);
+            // (the new parent might be one of the siblings that moved up)
+            relocate_children(page_data, old_parent_sig, old_index);
+            let parent_sig = page_data.hash_to_node(parent).unwrap();
+            let before = parent_sig.get_untracked().children.get(*index).cloned();
+            parent_sig.insert_nodes(&vec![node_sig], before.as_ref());
+            relocate_children(page_data, parent_sig, *index);
+        },
+        Change::KindChanged { hash, kind } => {
+            page_data.hash_to_node(hash).unwrap().change_block_kind(kind.clone());
+        },
+        Change::TextChanged { hash, text } => {
+            page_data.hash_to_node(hash).unwrap().set_text(text);
+        },
+        Change::ContentChanged { hash, key, val } => {
+            page_data.hash_to_node(hash).unwrap().update_untracked(|n| {
+                match val {
+                    Some(val) => n.content.insert(key.clone(), val.clone()),
+                    None => n.content.remove(key),
+                };
+            });
+        },
+    }
+}
+
+/// after an edit the top/bot elem might have been removed, or a new block 
+/// rendered above/below it, so reset them to the first/last rendered blocks
+pub fn sync_edge_elems(page_data: RwSignal<Page>) {
+    let page = page_data.get_untracked();
+    let mut rendered = Vec::new();
+    rendered_leaf_blocks(page.nodes, &mut rendered);
+    let (Some(top), Some(bot)) = (rendered.first(), rendered.last()) else { return };
+    for (edge, node_sig) in [(page.top_elem, *top), (page.bot_elem, *bot)] {
+        let hash = node_sig.get_untracked().hash;
+        if edge.get_untracked().hash == hash { continue }
+        edge.update_untracked(|e| {
+            e.hash = hash;
+            e.node_sig = node_sig;
+        });
+    }
+}
+fn rendered_leaf_blocks(node_sig: RwSignal<PageNode>, blocks: &mut Vec<RwSignal<PageNode>>) {
+    let node = node_sig.get_untracked();
+    if node.elem_ref.is_none() || !node.is_block() { return }
+    if node.kind.is_leaf_block() {
+        blocks.push(node_sig);
+        return
+    }
+    for child in node.children {
+        rendered_leaf_blocks(child, blocks);
     }
 }
 
-pub fn init_demo_page_data(cx: Scope) -> RwSignal<Page> {
-    let page = PageNode::signal_from(cx, 
-        "".into(), PageNodeType::Page,
-        HashMap::new(), Vec::new(), None, 0
-    );
-    let mut nodes = Vec::new();
-    let raw_text_template = PageNode::from(
-        "".into(), PageNodeType::RawText,
-        HashMap::from([
-            ("text".to_string(), "some text".to_string())
-        ]), Vec::new(), None, 0
-    );
-    let h1_template = PageNode::from(
-        "".into(), PageNodeType::H1,HashMap::new(),
-        Vec::new(), None, 0
-    );
-    let dot_block_template = PageNode::from(
-        "".into(), PageNodeType::Dot, HashMap::new(),
-        Vec::new(), None, 0
-    );
-    let quote_block_template = PageNode::from(
-        "".into(), PageNodeType::Quote, HashMap::new(),
-        Vec::new(), None, 0
-    );
-    let text_block_template = PageNode::from(
-        "".into(), PageNodeType::TextBlock, HashMap::new(),
-        Vec::new(), None, 0
-    );
-    for i in 0..5 {
-        {
-            let h1_node_sig = create_rw_signal(cx, h1_template.clone());
-            let mut new_child = raw_text_template.clone();
-            new_child.content.get_mut("text").unwrap().push_str(&format!(" {}", i));
-            new_child.parent = Some(h1_node_sig.clone());
-            h1_node_sig.update(|n| {
-                n.children.push(create_rw_signal(cx, new_child));
-                n.parent = Some(page);
-            });
-            nodes.push(h1_node_sig);
-        }
-
-        {
-            let parent = create_rw_signal(cx, dot_block_template.clone());
-            let mut text_child = raw_text_template.clone();
-            text_child.content.get_mut("text").unwrap().push_str(&format!(" {}", i));
-            text_child.parent = Some(parent.clone());
-            parent.update(|n| {
-                n.children.push(create_rw_signal(cx, text_child));
-                n.parent = Some(page);
-            });
-            nodes.push(parent);
-        }
-        {
-            let parent = create_rw_signal(cx, quote_block_template.clone());
-            let child_1 = create_rw_signal(cx, text_block_template.clone());
-            let mut text_child_1 = raw_text_template.clone();
-            text_child_1.content.get_mut("text").unwrap().push_str(&format!(" {}", i));
-            text_child_1.parent = Some(child_1.clone());
-            child_1.update(|n| {
-                n.children.push(create_rw_signal(cx, text_child_1));
-                n.parent = Some(parent.clone());
-            });
-            let child_2 = create_rw_signal(cx, text_block_template.clone());
-            let mut text_child_2 = raw_text_template.clone();
-            text_child_2.parent = Some(child_2.clone());
-            child_2.update(|n| {
-                n.children.push(create_rw_signal(cx, text_child_2));
-                n.parent = Some(parent.clone());
-            });
-            parent.update(|n| {
-                n.children = Vec::from([child_1, child_2]);
-                n.parent = Some(page);
-            });
-            nodes.push(parent);
-        }
+pub fn init_page_data(cx: Scope, mut doc: Doc) -> RwSignal<Page> {
+    // the page needs at least one block w/ text for the caret to go in (a
+    // file w/ just `---` has a block, but not one the caret can go in)
+    if doc.text_blocks().is_empty() {
+        let op = Op::InsertNodes {
+            parent: ROOT_HASH.to_string(),
+            index: doc.children(ROOT_HASH).len(),
+            nodes: vec![NodeTree::block(PageNodeType::TextBlock, "")],
+        };
+        doc.apply(&op.into()).unwrap();
     }
-    page.update(|p| {
-        p.children = nodes.clone();
-    });
+    let page = PageNode::signal_from_tree(cx, &doc.root_tree(), None);
+    let nodes = page.get_untracked().children;
     let locations = create_rw_signal(cx, HashMap::new());
-    add_hashes(&nodes, Vec::new(), locations);
+    update_hash_locations_recursive(&nodes, Vec::new(), locations);
 
     // BC SCREEN WIDTH IS VARIABLE, SET TOP AND BOTTOM ELEM TO THE TOP_ELEM, 
     // THEN TRIGGER THE IN-VIEW THING TO RENDER TO BOTTOM OF VIEW
+    let top_hash = doc.leaf_blocks()[0].clone();
+    let top_node = get_node_from_location(
+        &locations.get_untracked()[&top_hash], &nodes).unwrap();
 
-    // let top_node = get_top_block_node(&nodes);
-    // let top_hash = top_node.get().hash;
-    // log!("{}", top_hash);
-    // let bot_node = get_bot_block_node(&nodes);
-    // let bot_hash = top_node.get().hash;
-    // get lower top_node to test scroll to top_node on init
-    let top_node = nodes[3];
-    let top_hash = top_node.get().hash;
-
-    Page::signal_from(cx,
+    let doc = create_rw_signal(cx, doc);
+    let page_data = Page::signal_from(cx,
         page,
         EdgeElem::signal_from(cx, top_hash.clone(), top_node.clone(), 0, 0),
         EdgeElem::signal_from(cx, top_hash, top_node, 0, 0),
         locations,
-    )
-}
\ No newline at end of file
+        doc,
+    );
+    doc.update_untracked(|d| {
+        d.subscribe(move |change| patch_page_nodes(cx, page_data, change));
+    });
+    page_data
+}
+
+pub fn init_demo_page_data(cx: Scope) -> RwSignal<Page> {
+    let mut md = String::new();
+    for i in 0..5 {
+        md.push_str(&format!("# some text {i}\n- some text {i}\n> some text {i}\n> some text\n"));
+    }
+    let seed = Math::random().to_bits();
+    let page_data = init_page_data(cx, Doc::from_markdown(&md, seed));
+    // get lower top_node to test scroll to top_node on init
+    let top_node = page_data.get_untracked().nodes.get_untracked().children[3];
+    let top_hash = top_node.get_untracked().hash;
+    page_data.update_untracked(|p| {
+        for edge in [p.top_elem, p.bot_elem] {
+            edge.update_untracked(|e| {
+                e.hash = top_hash.clone();
+                e.node_sig = top_node;
+            });
+        }
+    });
+    page_data
+}
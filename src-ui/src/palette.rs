@@ -0,0 +1,205 @@
+use leptos::{Scope, RwSignal, NodeRef, HtmlElement, Div, Input, create_rw_signal, document, window, 
+    event_target_value, JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use web_sys::{Element, Event, KeyboardEvent, MouseEvent};
+
+use super::{PageView, Command, Keymap, PaletteEntry, palette_entries, push_recent};
+
+// Mod-Shift-P lists every command, w/ its shortcuts. typing filters the list 
+// (see `palette_entries`) and recently used commands come first. unlike the 
+// slash menu, the palette has its own input, so the focus leaves the page 
+// while it's open and goes back (w/ the selection as it was) once it closes
+
+/// the palette while it's open
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub entries: Vec<PaletteEntry>,
+    pub selected: usize,
+}
+
+/// where the recent commands are kept between sessions
+const RECENT_KEY: &str = "recent_commands";
+
+/// the commands run from the palette, most recent first
+pub fn load_recent_commands(cx: Scope) -> RwSignal<Vec<Command>> {
+    let recent = window().local_storage().ok().flatten()
+        .and_then(|s| s.get_item(RECENT_KEY).ok().flatten())
+        .map(|names| names.split(',').filter_map(Command::from_name).collect())
+        .unwrap_or_default();
+    create_rw_signal(cx, recent)
+}
+
+fn remember_command(recent: RwSignal<Vec<Command>>, command: Command) {
+    let mut commands = recent.get_untracked();
+    push_recent(&mut commands, command);
+    if let Some(storage) = window().local_storage().ok().flatten() {
+        let names: Vec<_> = commands.iter().map(|c| c.name()).collect();
+        _ = storage.set_item(RECENT_KEY, &names.join(","));
+    }
+    recent.set_untracked(commands);
+}
+
+/// open the palette (or refilter it once the query changes)
+pub fn filter_palette(palette: RwSignal<Option<Palette>>, keymap: RwSignal<Keymap>, 
+    recent: RwSignal<Vec<Command>>, query: &str,
+) {
+    let entries = keymap.update_returning_untracked(|k| {
+        palette_entries(k, query, &recent.get_untracked())
+    }).unwrap();
+    palette.set_untracked(Some(Palette { entries, selected: 0 }));
+}
+
+/// keys in the palette's input. returns the command picked, if one was
+pub fn process_palette_keydown(event: &KeyboardEvent, palette: RwSignal<Option<Palette>>, 
+    recent: RwSignal<Vec<Command>>,
+) -> Option<Command> {
+    let mut open = palette.get_untracked()?;
+    let len = open.entries.len();
+    match event.key().as_str() {
+        "ArrowDown" if len > 0 => open.selected = (open.selected + 1) % len,
+        "ArrowUp" if len > 0 => open.selected = (open.selected + len - 1) % len,
+        "Enter" => {
+            event.prevent_default();
+            return choose_palette_entry(palette, recent, open.selected);
+        },
+        "Escape" => {
+            event.prevent_default();
+            palette.set_untracked(None);
+            return None;
+        },
+        _ => return None,
+    }
+    event.prevent_default();
+    palette.set_untracked(Some(open));
+    None
+}
+
+/// close the palette and return the entry's command
+pub fn choose_palette_entry(palette: RwSignal<Option<Palette>>, recent: RwSignal<Vec<Command>>, 
+    index: usize,
+) -> Option<Command> {
+    let open = palette.get_untracked()?;
+    palette.set_untracked(None);
+    let command = open.entries.get(index)?.command;
+    remember_command(recent, command);
+    Some(command)
+}
+
+/// clicking an entry picks it. the click mustn't take the focus from the 
+/// input, or the palette would close before the click lands
+pub fn process_palette_mousedown(event: MouseEvent, palette: RwSignal<Option<Palette>>, 
+    recent: RwSignal<Vec<Command>>,
+) -> Option<Command> {
+    let index = event.target()
+        .and_then(|t| t.dyn_into::<Element>().ok())
+        .and_then(|e| e.closest("[data-index]").ok().flatten())
+        .and_then(|e| e.get_attribute("data-index"))
+        .and_then(|i| i.parse().ok())?;
+    event.prevent_default();
+    choose_palette_entry(palette, recent, index)
+}
+
+/// draw the entries (or hide the palette), at the top middle of the window
+pub fn show_palette(palette_elem: &Element, list_elem: &Element, palette: RwSignal<Option<Palette>>) {
+    let Some(open) = palette.get_untracked() else {
+        palette_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    };
+    list_elem.set_inner_html("");
+    for (i, entry) in open.entries.iter().enumerate() {
+        let row = document().create_element("div").unwrap();
+        row.set_attribute("data-index", &i.to_string()).unwrap();
+        let background = if i == open.selected { "background: #ddd; " } else { "" };
+        row.set_attribute("style", &format!("{}display: flex; \
+            justify-content: space-between; gap: 16px", background)).unwrap();
+        let title = document().create_element("span").unwrap();
+        title.set_text_content(Some(entry.title));
+        let chords = document().create_element("span").unwrap();
+        chords.set_attribute("style", "color: #888").unwrap();
+        chords.set_text_content(Some(&entry.chords.join(", ")));
+        row.append_child(&title).unwrap();
+        row.append_child(&chords).unwrap();
+        list_elem.append_child(&row).unwrap();
+    }
+    if open.entries.is_empty() {
+        list_elem.set_text_content(Some("No matching commands"));
+    }
+    palette_elem.set_attribute("style", "display: block; position: fixed; top: 10%; \
+        left: 50%; transform: translateX(-50%); width: 320px; cursor: default; \
+        background: white; border: 1px solid #ccc").unwrap();
+    if let Some(row) = list_elem.children().item(open.selected as u32) {
+        row.scroll_into_view_with_bool(false);
+    }
+}
+
+/// the palette's state and elems, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct PaletteView {
+    pub palette: RwSignal<Option<Palette>>,
+    pub recent: RwSignal<Vec<Command>>,
+    pub keymap: RwSignal<Keymap>,
+    pub palette_ref: NodeRef<HtmlElement<Div>>,
+    pub list_ref: NodeRef<HtmlElement<Div>>,
+    pub input_ref: NodeRef<HtmlElement<Input>>,
+}
+
+impl PaletteView {
+    pub fn new(cx: Scope, keymap: RwSignal<Keymap>) -> Self {
+        Self {
+            palette: create_rw_signal(cx, None),
+            recent: load_recent_commands(cx),
+            keymap,
+            palette_ref: NodeRef::new(cx),
+            list_ref: NodeRef::new(cx),
+            input_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self) {
+        if let (Some(palette_elem), Some(list_elem)) = (self.palette_ref.get(), self.list_ref.get()) {
+            show_palette(palette_elem.unchecked_ref::<Element>(), list_elem.unchecked_ref::<Element>(), 
+                self.palette);
+        }
+    }
+
+    pub fn open(&self) {
+        filter_palette(self.palette, self.keymap, self.recent, "");
+        self.redraw();
+        if let Some(input) = self.input_ref.get() {
+            input.set_value("");
+            // can only focus it once it's shown
+            _ = input.focus();
+        }
+    }
+
+    // the focus goes back to the page when the palette closes, then the 
+    // command (if one was picked) runs on the page
+    fn close(&self, page: PageView, command: Option<Command>, run: impl Fn(Command)) {
+        if self.palette.get_untracked().is_some() { return }
+        self.redraw();
+        page.refocus();
+        if let Some(command) = command {
+            run(command);
+        }
+    }
+
+    pub fn handle_input(&self, event: Event) {
+        filter_palette(self.palette, self.keymap, self.recent, &event_target_value(&event));
+        self.redraw();
+    }
+
+    pub fn handle_keydown(&self, event: KeyboardEvent, page: PageView, run: impl Fn(Command)) {
+        let command = process_palette_keydown(&event, self.palette, self.recent);
+        self.redraw();
+        self.close(page, command, run);
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, run: impl Fn(Command)) {
+        let command = process_palette_mousedown(event, self.palette, self.recent);
+        self.close(page, command, run);
+    }
+
+    pub fn handle_blur(&self) {
+        self.palette.set_untracked(None);
+        self.redraw();
+    }
+}
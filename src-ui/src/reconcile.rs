@@ -0,0 +1,113 @@
+use leptos::{log, Scope, RwSignal, JsCast, UntrackedGettableSignal};
+use leptos::wasm_bindgen::{JsValue, closure::Closure};
+use web_sys::{Node, Element, MutationObserver, MutationObserverInit, MutationRecord};
+
+use super::{Page, PageNode, PageNodeType, HashToNode, CreateElem, 
+    update_hash_locations, rerender_view, sync_text_from_dom, sync_selection_from_dom, 
+    reshow_selection};
+
+// the page is contenteditable, so the browser can change the DOM w/o going 
+// through `process_beforeinput`: spellcheck/autocorrect, drag-dropped text, 
+// the context menu's paste, the webview's own undo, etc. so the DOM is 
+// watched for changes we didn't make. text changes get copied into the doc, 
+// and anything else is put back the way the doc says it should be
+//
+// our own changes to the DOM are always made in an event handler, and the 
+// observer's callback only runs after that, so each handler throws away the 
+// records of its own changes w/ `discard_own_mutations`
+
+/// start watching the page for DOM changes we didn't make. `page_elem` is 
+/// the scroll window
+pub fn observe_dom(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, 
+    composing: RwSignal<bool>,
+) -> MutationObserver {
+    let scroll_window = page_elem.clone();
+    let on_mutations = Closure::<dyn FnMut(JsValue, MutationObserver)>::new(
+        move |records: JsValue, observer: MutationObserver| {
+            // the browser has to edit the DOM itself while composing. the doc 
+            // catches up in `process_compositionend`
+            if composing.get_untracked() { return }
+            reconcile(cx, page_data, &scroll_window, records.unchecked_into());
+            discard_own_mutations(&observer);
+        },
+    );
+    let observer = MutationObserver::new(on_mutations.as_ref().unchecked_ref()).unwrap();
+    // the page lives as long as the app, so the callback does too
+    on_mutations.forget();
+    // only the page node's elem, bc the padding divs around it change all the 
+    // time and the browser can't edit them
+    let page_node_elem = page_data.get_untracked().nodes.get_untracked().elem_ref.unwrap();
+    let mut options = MutationObserverInit::new();
+    options.child_list(true).character_data(true).subtree(true);
+    observer.observe_with_options(&page_node_elem, &options).unwrap();
+    observer
+}
+
+/// call after changing the DOM ourselves so the observer doesn't think the 
+/// browser did it
+pub fn discard_own_mutations(observer: &MutationObserver) {
+    observer.take_records();
+}
+
+fn reconcile(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, 
+    records: js_sys::Array,
+) {
+    let mut texts: Vec<String> = Vec::new();
+    let mut blocks: Vec<RwSignal<PageNode>> = Vec::new();
+    let mut rerender = false;
+    for record in records.iter() {
+        let record = record.unchecked_into::<MutationRecord>();
+        let Some(target) = record.target() else { continue };
+        let Some(node_sig) = owning_node(page_data, &target) else {
+            rerender = true;
+            continue;
+        };
+        let node = node_sig.get_untracked();
+        if node.kind == PageNodeType::RawText {
+            if !texts.contains(&node.hash) { texts.push(node.hash.clone()) }
+            // e.g. the browser split the text node, or wrapped part of it in 
+            // a `<b>`. the text is kept, but the elems are rebuilt
+            if record.type_() == "childList" {
+                let block = node.parent.unwrap();
+                if !blocks.contains(&block) { blocks.push(block) }
+            }
+        } else if node.is_leaf_block() {
+            // something added/removed between the block's text elems. it 
+            // can't be mapped to the doc, so it gets reverted
+            if !blocks.contains(&node_sig) { blocks.push(node_sig) }
+        } else {
+            // blocks themselves were added/removed/moved around
+            rerender = true;
+        }
+    }
+    if texts.is_empty() && blocks.is_empty() && !rerender { return }
+    log!("UNEXPECTED DOM CHANGE: {} texts, {} blocks, rerender: {}", 
+        texts.len(), blocks.len(), rerender);
+
+    // the browser's caret is where its edit left it
+    sync_selection_from_dom(page_data);
+    for hash in texts {
+        sync_text_from_dom(page_data, &hash);
+    }
+    if rerender {
+        rerender_view(cx, page_data, page_elem);
+    } else {
+        for block in blocks {
+            let Some(old_elem) = block.get_untracked().elem_ref else { continue };
+            let new_elem = block.create_elem();
+            old_elem.replace_with_with_node_1(&new_elem).unwrap();
+        }
+    }
+    update_hash_locations(&page_data);
+    reshow_selection(page_data);
+}
+
+/// the page node whose elem the DOM node is in
+fn owning_node(page_data: RwSignal<Page>, node: &Node) -> Option<RwSignal<PageNode>> {
+    let elem = match node.dyn_ref::<Element>() {
+        Some(elem) => elem.clone(),
+        None => node.parent_element()?,
+    };
+    let hash = elem.closest("[hash]").ok()??.get_attribute("hash")?;
+    page_data.hash_to_node(&hash)
+}
@@ -0,0 +1,217 @@
+use std::path::Path;
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, Input, create_rw_signal, spawn_local, 
+    document, JsCast, UntrackedGettableSignal};
+use serde::Serialize;
+use tauri_sys::tauri;
+use web_sys::{Element, EventTarget, HtmlInputElement, KeyboardEvent, MouseEvent};
+
+use super::{PageView, Page, RenameReport, AmbiguousLink, load_file, workspace_dir};
+
+// renaming/moving the open note (F2). the new path is typed relative to the
+// workspace, so `sub/new name` moves it into `sub`. the backend moves the file
+// and changes every link to it (see `rename.rs` there), then the links it
+// couldn't be sure about are listed here for the user to fix
+
+#[derive(Debug, Clone, Default)]
+pub struct RenamePanel {
+    pub open: bool,
+    /// e.g. how many notes changed, or what went wrong
+    pub status: String,
+    /// the links from the last rename that were left as they were
+    pub ambiguous: Vec<AmbiguousLink>,
+}
+
+/// what a key or button in the panel does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameAction {
+    Rename,
+    Close,
+}
+
+#[derive(Serialize)]
+struct RenameArgs {
+    root: String,
+    from: String,
+    to: String,
+}
+
+/// the open note's path from the workspace folder, w/o `.md`
+fn relative_name(path: RwSignal<Option<String>>) -> Option<String> {
+    let (root, file) = (workspace_dir(path)?, path.get_untracked()?);
+    let name = file.strip_prefix(&root).unwrap_or(&file).trim_start_matches(['/', '\\']);
+    Some(name.strip_suffix(".md").unwrap_or(name).to_string())
+}
+
+pub fn open_rename(panel: RwSignal<RenamePanel>, path: RwSignal<Option<String>>, input: &HtmlInputElement) {
+    let name = relative_name(path);
+    panel.update_untracked(|p| {
+        p.open = true;
+        p.ambiguous.clear();
+        p.status = match name {
+            Some(_) => String::new(),
+            None => "Open a file first".into(),
+        };
+    });
+    input.set_value(&name.unwrap_or_default());
+    _ = input.focus();
+    input.select();
+}
+
+pub fn close_rename(panel: RwSignal<RenamePanel>) {
+    panel.update_untracked(|p| {
+        p.open = false;
+        p.status.clear();
+        p.ambiguous.clear();
+    });
+}
+
+/// Enter renames, Escape closes
+pub fn process_rename_keydown(event: &KeyboardEvent) -> Option<RenameAction> {
+    let action = match event.key().as_str() {
+        "Enter" => RenameAction::Rename,
+        "Escape" => RenameAction::Close,
+        _ => return None,
+    };
+    event.prevent_default();
+    Some(action)
+}
+
+/// the button `target` is in, if it's one of the panel's
+pub fn rename_button_at(target: Option<EventTarget>) -> Option<RenameAction> {
+    let elem = target.and_then(|t| t.dyn_into::<Element>().ok())?;
+    let button = elem.closest("button").ok().flatten()?;
+    match button.get_attribute("action")?.as_str() {
+        "rename" => Some(RenameAction::Rename),
+        "close" => Some(RenameAction::Close),
+        _ => None,
+    }
+}
+
+/// move the open note to `name` (from the workspace folder, `.md` optional)
+/// and change the links to it. the note is loaded again from where it is
+/// now, then `after` runs
+pub fn rename_open_note(cx: Scope, page_data: RwSignal<Page>, page_elem: Element,
+    panel: RwSignal<RenamePanel>, path: RwSignal<Option<String>>, name: String,
+    after: impl Fn() + 'static,
+) {
+    let (Some(root), Some(from)) = (workspace_dir(path), path.get_untracked()) else { return };
+    let name = name.trim().trim_start_matches(['/', '\\']);
+    if name.is_empty() { return }
+    let mut to = Path::new(&root).join(name).to_string_lossy().into_owned();
+    if !to.ends_with(".md") {
+        to += ".md";
+    }
+    if to == from {
+        close_rename(panel);
+        return after();
+    }
+    spawn_local(async move {
+        let args = RenameArgs { root, from, to: to.clone() };
+        match tauri::invoke::<_, RenameReport>("rename_note", &args).await {
+            Ok(report) => {
+                let mut status = format!("Renamed, links changed in {} notes", report.changed.len());
+                if !report.ambiguous.is_empty() {
+                    status += &format!(", {} links could be to another note so were left:",
+                        report.ambiguous.len());
+                }
+                panel.update_untracked(|p| {
+                    p.status = status;
+                    p.ambiguous = report.ambiguous;
+                });
+                load_file(cx, page_data, &page_elem, path, to).await;
+            },
+            Err(err) => {
+                log!("COULDN'T RENAME: {:?}", err);
+                panel.update_untracked(|p| p.status = format!("Not renamed: {:?}", err));
+            },
+        }
+        after();
+    });
+}
+
+/// show/hide the panel, w/ the status and the links that were left
+pub fn show_rename(panel: RwSignal<RenamePanel>, path: RwSignal<Option<String>>, panel_elem: &Element) {
+    let state = panel.get_untracked();
+    if !state.open {
+        panel_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    }
+    panel_elem.set_attribute("style", "display: flex; flex-wrap: wrap; gap: 4px; position: fixed; \
+        top: 8px; left: 50%; transform: translateX(-50%); width: 360px; padding: 6px; \
+        background: white; border: 1px solid #ccc; border-radius: 4px; z-index: 10").unwrap();
+    if let Ok(Some(status_elem)) = panel_elem.query_selector("[type=rename-status]") {
+        status_elem.set_text_content(Some(&state.status));
+    }
+    let Ok(Some(list_elem)) = panel_elem.query_selector("[type=rename-ambiguous]") else { return };
+    list_elem.set_inner_html("");
+    let root = workspace_dir(path).unwrap_or_default();
+    for link in &state.ambiguous {
+        let name = link.path.strip_prefix(&root).unwrap_or(&link.path).trim_start_matches(['/', '\\']);
+        let line = document().create_element("div").unwrap();
+        line.set_attribute("style", "font-family: monospace; white-space: pre-wrap").unwrap();
+        line.set_text_content(Some(&format!("{}:{}  {}", name, link.line, link.link)));
+        list_elem.append_child(&line).unwrap();
+    }
+}
+
+/// the panel's state and elems, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct RenameView {
+    pub panel: RwSignal<RenamePanel>,
+    pub panel_ref: NodeRef<HtmlElement<Div>>,
+    pub input_ref: NodeRef<HtmlElement<Input>>,
+}
+
+impl RenameView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            panel: create_rw_signal(cx, RenamePanel::default()),
+            panel_ref: NodeRef::new(cx),
+            input_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self, page: PageView) {
+        if let Some(panel_elem) = self.panel_ref.get() {
+            show_rename(self.panel, page.file_path, panel_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn open(&self, page: PageView) {
+        let Some(input) = self.input_ref.get() else { return };
+        open_rename(self.panel, page.file_path, &input);
+        self.redraw(page);
+    }
+
+    pub fn run(&self, action: RenameAction, page: PageView, after_open: impl Fn() + 'static) {
+        let (Some(page_elem), Some(input)) = (page.page_elem(), self.input_ref.get()) else { return };
+        match action {
+            RenameAction::Rename => {
+                let view = *self;
+                rename_open_note(page.cx, page.page_data, page_elem, self.panel, page.file_path, 
+                    input.value(), move || {
+                        view.redraw(page);
+                        after_open();
+                    });
+            },
+            RenameAction::Close => {
+                close_rename(self.panel);
+                self.redraw(page);
+                page.refocus();
+            },
+        }
+    }
+
+    pub fn handle_keydown(&self, event: KeyboardEvent, page: PageView, after_open: impl Fn() + 'static) {
+        if let Some(action) = process_rename_keydown(&event) {
+            self.run(action, page, after_open);
+        }
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, after_open: impl Fn() + 'static) {
+        let Some(action) = rename_button_at(event.target()) else { return };
+        // keep the focus in the input
+        event.prevent_default();
+        self.run(action, page, after_open);
+    }
+}
@@ -1,7 +1,8 @@
+use std::collections::HashSet;
 use leptos::{log, Scope, RwSignal, create_rw_signal, UntrackedGettableSignal, UntrackedSettableSignal};
 use web_sys::Element;
 
-use super::{Page, PageNode, CreateElem, HashToLocation, IsFirstChild, IsLastChild, NextChild};
+use super::{Page, PageNode, CreateElem, HashToLocation, HashToNode, show_selection, is_hidden, reveal_block};
 
 trait GetPageElem {
     fn get_page_elem(&self) -> Element;
@@ -172,6 +173,180 @@ pub fn update_dom_nodes_in_view(cx: Scope, page_data: RwSignal<Page>, page_elem:
             e.node_sig = new_bot_node;
         })
     }
+    // blocks the selection was in may have been (un)rendered
+    show_selection(page_data);
+}
+
+/// make sure the block is in the DOM, so the caret can be put in it. the 
+/// blocks just outside the rendered range get added on like when scrolling. 
+/// anything further away (e.g. the end of a huge page) gets the rendered range 
+/// rebuilt around it, rather than rendering every block in between
+pub fn render_block(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, 
+    node_sig: RwSignal<PageNode>,
+) {
+    if node_sig.get_untracked().elem_ref.is_some() { return }
+    let (top_elem, bot_elem) = page_data.update_returning_untracked(|p| {
+        (p.top_elem, p.bot_elem)
+    }).unwrap();
+    let hash = node_sig.get_untracked().hash;
+    if get_prev_block_node(&top_elem.get_untracked().hash, page_data) == Some(node_sig) {
+        let height = insert_new_node_before(cx, node_sig);
+        top_elem.update_untracked(|e| {
+            e.pad = e.pad.saturating_sub(height);
+            e.hash = hash;
+            e.node_sig = node_sig;
+        });
+        update_top_padding(page_elem, top_elem.get_untracked().pad);
+    } else if get_next_block_node(&bot_elem.get_untracked().hash, page_data) == Some(node_sig) {
+        let height = insert_new_node_after(cx, node_sig);
+        bot_elem.update_untracked(|e| {
+            e.pad = e.pad.saturating_sub(height);
+            e.hash = hash;
+            e.node_sig = node_sig;
+        });
+        update_bot_padding(page_elem, bot_elem.get_untracked().pad);
+    } else {
+        render_only_block(cx, page_data, page_elem, node_sig);
+    }
+}
+
+/// render the block and scroll to it. the blocks around it get rendered by 
+/// `update_dom_nodes_in_view` as usual
+pub fn jump_to_block(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, 
+    hash: &String, align_to_top: bool,
+) {
+    let Some(node_sig) = page_data.hash_to_node(hash) else { return };
+    // it can't be shown while it's folded away
+    if reveal_block(page_data, hash) {
+        rerender_view(cx, page_data, page_elem);
+    }
+    render_block(cx, page_data, page_elem, node_sig);
+    node_sig.get_untracked().elem_ref.unwrap().scroll_into_view_with_bool(align_to_top);
+    update_dom_nodes_in_view(cx, page_data, page_elem);
+}
+
+/// throw away whatever is in the DOM and render the view again from the doc, 
+/// starting at the top elem. for when the DOM can't be trusted anymore (see 
+/// `reconcile.rs`)
+pub fn rerender_view(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element) {
+    let page = page_data.get_untracked();
+    // this also gets rid of anything the browser put in the page
+    if let Some(elem) = page.nodes.get_untracked().elem_ref {
+        elem.set_inner_html("");
+    }
+    let mut start = page.top_elem.get_untracked().node_sig;
+    // e.g. a fold just hid it
+    let start_hash = start.get_untracked().hash;
+    if is_hidden(page_data, &start_hash) {
+        if let Some(node) = get_prev_block_node(&start_hash, page_data)
+            .or_else(|| get_next_block_node(&start_hash, page_data)) {
+            start = node;
+        }
+    }
+    render_only_block(cx, page_data, page_elem, start);
+    update_dom_nodes_in_view(cx, page_data, page_elem);
+}
+
+/// unrender everything, then render just this block (and the branch blocks 
+/// it's in), w/ the padding made up from the heights of all the blocks 
+/// above/below it
+fn render_only_block(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element, 
+    node_sig: RwSignal<PageNode>,
+) {
+    let page = page_data.get_untracked();
+    for child in page.nodes.get_untracked().children {
+        if let Some(elem) = child.get_untracked().elem_ref {
+            elem.remove();
+        }
+        child.update_untracked(|n| n.elem_ref = None);
+        remove_all_children_elems(&child);
+    }
+    let mut child_node = node_sig;
+    let mut child_elem: Element = child_node.create_elem();
+    loop {
+        child_node.update(|n| n.elem_ref = Some(child_elem.clone()));
+        let parent_node = child_node.get_untracked().parent.unwrap();
+        // the page elem itself is never removed
+        if let Some(parent_elem) = parent_node.get_untracked().elem_ref {
+            parent_elem.append_child(&child_elem).unwrap();
+            break;
+        }
+        let castrated_parent_elem = {
+            let mut parent = parent_node.get_untracked();
+            parent.children = Vec::new();
+            create_rw_signal(cx, parent).create_elem()
+        };
+        castrated_parent_elem.append_child(&child_elem).unwrap();
+        child_elem = castrated_parent_elem;
+        child_node = parent_node;
+    }
+    let height = node_sig.get_untracked().elem_ref.unwrap()
+        .get_bounding_client_rect().height() as u32;
+    node_sig.update_untracked(|n| n.height = height);
+
+    let (mut top_pad, mut bot_pad) = (0, 0);
+    let hidden = page.hidden.get_untracked();
+    pad_around(page.nodes, node_sig, &hidden, &mut false, &mut top_pad, &mut bot_pad);
+    let hash = node_sig.get_untracked().hash;
+    for (edge, pad) in [(page.top_elem, top_pad), (page.bot_elem, bot_pad)] {
+        edge.update_untracked(|e| {
+            e.hash = hash.clone();
+            e.node_sig = node_sig;
+            e.pad = pad;
+        });
+    }
+    update_top_padding(page_elem, top_pad);
+    update_bot_padding(page_elem, bot_pad);
+}
+
+/// add up the heights of the unrendered blocks above and below `block`. 
+/// blocks hidden by a fold don't take up any room
+fn pad_around(node: RwSignal<PageNode>, block: RwSignal<PageNode>, hidden: &HashSet<String>,
+    found: &mut bool, top_pad: &mut u32, bot_pad: &mut u32,
+) {
+    for child in node.get_untracked().children {
+        if child == block {
+            *found = true;
+            continue;
+        }
+        let child_node = child.get_untracked();
+        if !child_node.is_block() || hidden.contains(&child_node.hash) { continue }
+        // if it's rendered, it's one of the branch blocks `block` is in
+        if child_node.elem_ref.is_some() {
+            pad_around(child, block, hidden, found, top_pad, bot_pad);
+        } else if *found {
+            *bot_pad += total_height(child, hidden);
+        } else {
+            *top_pad += total_height(child, hidden);
+        }
+    }
+}
+/// a guess at the height of a block that's never been rendered, so hasn't
+/// been measured yet (about a line of text)
+pub const UNMEASURED_HEIGHT: u32 = 24;
+
+/// height of a leaf block, the same as `total_height` gives it, or the guess
+/// if it hasn't been measured. e.g. for moving by a page past blocks that
+/// aren't rendered
+pub fn est_leaf_height(node_sig: RwSignal<PageNode>) -> u32 {
+    match total_height(node_sig, &HashSet::new()) {
+        0 => UNMEASURED_HEIGHT,
+        height => height,
+    }
+}
+/// height of the block incl. all the (not folded away) blocks in it
+fn total_height(node_sig: RwSignal<PageNode>, hidden: &HashSet<String>) -> u32 {
+    let node = node_sig.get_untracked();
+    let children: u32 = node.children.iter()
+        .filter(|c| c.get_untracked().is_block() && !hidden.contains(&c.get_untracked().hash))
+        .map(|c| total_height(*c, hidden)).sum();
+    node.height + node.kind.innate_height() + children
+}
+/// whether none of the node's children are rendered. the siblings of a 
+/// block being (un)rendered aren't always the next/prev blocks, bc the ones 
+/// in between might be folded away
+fn has_no_rendered_children(node_sig: RwSignal<PageNode>) -> bool {
+    node_sig.get_untracked().children.iter().all(|c| c.get_untracked().elem_ref.is_none())
 }
 
 pub fn update_top_padding(page_elem: &Element, pad: u32) {
@@ -205,8 +380,9 @@ fn insert_new_node_before(cx: Scope, new_node: RwSignal<PageNode>) -> u32 {
             n.elem_ref = Some(child_elem.clone()) // cloning refers to the same element since its just a ref (i have confirmed this)
         });
         let parent_node = child_node.get().parent.unwrap();
-        if parent_node.is_last_child(&child_node) {
-            // if child is last child, it means we have to add the parent node too
+        if parent_node.get().elem_ref.is_none() {
+            // if the parent isn't rendered (e.g. child is the last child), 
+            // we have to add the parent node too
             let castrated_parent_elem = { // using parent elem with no children otherwise all of its children would be added to the top instead of only the top element
                 let mut parent = parent_node.get();
                 parent.children = Vec::new();
@@ -217,12 +393,13 @@ fn insert_new_node_before(cx: Scope, new_node: RwSignal<PageNode>) -> u32 {
             child_node = parent_node;
         } else {
             // since we're inserting before the child to the right, we need the 
-            // element of the next child so we know where exactly to insert
-            let next_child = parent_node.next_child(&child_node).unwrap();
-            let next_elem = next_child.get().elem_ref.unwrap();
+            // element of the next rendered child so we know where exactly to 
+            // insert (the ones between might be folded away)
+            let next_elem = parent_node.get().children.iter()
+                .skip_while(|c| **c != child_node).skip(1)
+                .find_map(|c| c.get_untracked().elem_ref);
             let parent_elem = parent_node.get().elem_ref.unwrap();
-            parent_elem.insert_before(&child_elem, 
-                Some(&next_elem)).unwrap();
+            parent_elem.insert_before(&child_elem, next_elem.as_ref()).unwrap();
             return child_elem.get_bounding_client_rect().height() as u32;
         }
     }
@@ -254,7 +431,7 @@ fn remove_top_elem_from_dom(elem: RwSignal<PageNode>) -> u32 {
         // we need to remove the parent node too. then we check 
         // the parent node of the parent node, etc
         if let Some(parent_node) = child_node.get().parent {
-            if parent_node.is_last_child(&child_node) {
+            if parent_node.get().parent.is_some() && has_no_rendered_children(parent_node) {
                 // if the child is the last child, it means we have to remove 
                 // the parent node too
                 child_node = parent_node;
@@ -291,8 +468,9 @@ fn insert_new_node_after(cx: Scope, new_node: RwSignal<PageNode>) -> u32 {
             n.elem_ref = Some(child_elem.clone()) // cloning refers to the same element since its just a ref (i have confirmed this)
         });
         let parent_node = child_node.get().parent.unwrap();
-        if parent_node.is_first_child(&child_node) {
-            // if child is first child, it means we have to add the parent node too
+        if parent_node.get().elem_ref.is_none() {
+            // if the parent isn't rendered (e.g. child is the first child), 
+            // we have to add the parent node too
             let castrated_parent_elem = { // using parent elem with no children otherwise all of its children would be added to the bot instead of only the bot element
                 let mut parent = parent_node.get();
                 parent.children = Vec::new();
@@ -328,7 +506,7 @@ fn remove_bot_elem_from_dom(elem: RwSignal<PageNode>) -> u32 {
         // we need to remove the parent node too. then we check 
         // the parent node of the parent node, etc
         if let Some(parent_node) = child_node.get().parent {
-            if parent_node.is_first_child(&child_node) {
+            if parent_node.get().parent.is_some() && has_no_rendered_children(parent_node) {
                 // if the child is the first child, it means we have to remove 
                 // the parent node too
                 child_node = parent_node;
@@ -467,7 +645,16 @@ pub fn get_nodes_in_view(cx: Scope, nodes: Vec<RwSignal<PageNode>>,
     vec
 }
 
+/// the next block to render below this one. blocks folded away are skipped
 pub fn get_next_block_node(hash: &String, page_data: RwSignal<Page>
+) -> Option<RwSignal<PageNode>> {
+    let mut next = next_block_node(hash, page_data)?;
+    while is_hidden(page_data, &next.get_untracked().hash) {
+        next = next_block_node(&next.get_untracked().hash, page_data)?;
+    }
+    Some(next)
+}
+fn next_block_node(hash: &String, page_data: RwSignal<Page>
 ) -> Option<RwSignal<PageNode>> {
     let mut location = page_data.hash_to_location(hash);
     // console_log(&format!("location: {:?}", location));
@@ -504,7 +691,16 @@ pub fn get_next_block_node(hash: &String, page_data: RwSignal<Page>
 // HAVE TO CONFIRM THE RWSIGNAL DATATYPE IS VERY SMALL THOUGH TO JUSTIFY ADDING 
 // THEIR REFERENCES TO EACH NODE
 
+/// the block to render above this one. blocks folded away are skipped
 pub fn get_prev_block_node(hash: &String, page_data: RwSignal<Page>
+) -> Option<RwSignal<PageNode>> {
+    let mut prev = prev_block_node(hash, page_data)?;
+    while is_hidden(page_data, &prev.get_untracked().hash) {
+        prev = prev_block_node(&prev.get_untracked().hash, page_data)?;
+    }
+    Some(prev)
+}
+fn prev_block_node(hash: &String, page_data: RwSignal<Page>
 ) -> Option<RwSignal<PageNode>> {
     let mut location = page_data.hash_to_location(hash);
     // log!("location: {:?}", location);
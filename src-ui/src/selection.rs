@@ -0,0 +1,229 @@
+use leptos::{Scope, RwSignal, document, JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use leptos::wasm_bindgen::closure::Closure;
+use web_sys::{Node, Element};
+
+use super::{Page, Pos, Selection, PageNodeType, HashToNode, ReadDoc, 
+    utf16_to_byte, byte_to_utf16, floor_grapheme_boundary, jump_to_block};
+
+// the DOM selection breaks when the blocks it's in get unrendered (see 
+// `render_in_view.rs`), so the real selection is kept in doc positions in 
+// `Page.selection`. the DOM selection is just a view of it: it's copied to 
+// the doc selection when the user changes it, and put back (w/ any 
+// unrendered ends moved to the edge of the rendered blocks) when blocks get 
+// rendered/unrendered
+
+/// DOM position -> doc position. the DOM position can be anywhere in the 
+/// page: in a text node, between the children of a block (e.g. after a click 
+/// on a block's padding), or in one of the padding divs around the rendered 
+/// blocks. this is the one place DOM positions get turned into doc ones, so 
+/// clicks, drags and the browser's own selection all go through it
+pub fn dom_to_pos(page_data: RwSignal<Page>, node: &Node, offset: u32) -> Option<Pos> {
+    if node.node_type() == Node::TEXT_NODE {
+        let hash = node.parent_element()?.get_attribute("hash")?;
+        let text = page_data.read_doc(|d| {
+            let is_text = d.contains(&hash) && *d.kind(&hash) == PageNodeType::RawText;
+            is_text.then(|| d.text(&hash).to_string())
+        })?;
+        // the caret can't go inside a grapheme (e.g. between the chars of an 
+        // emoji), so snap it to the start
+        let offset = floor_grapheme_boundary(&text, utf16_to_byte(&text, offset));
+        return Some(Pos::new(&hash, offset));
+    }
+    let elem = node.dyn_ref::<Element>()?;
+    if elem.has_attribute("hash") {
+        // between the elem's children. only rendered children are in the 
+        // DOM, so this goes by the child elems rather than the doc's children
+        let children = elem.child_nodes();
+        let offset = offset.min(children.length());
+        if let Some(pos) = children.get(offset).and_then(|c| edge_pos(page_data, &c, true)) {
+            return Some(pos);
+        }
+        if offset > 0 {
+            if let Some(pos) = children.get(offset - 1).and_then(|c| edge_pos(page_data, &c, false)) {
+                return Some(pos);
+            }
+        }
+        return edge_pos(page_data, node, true);
+    }
+    // not part of the page, e.g. the padding divs around the rendered blocks, 
+    // so go to whichever end of the rendered blocks is nearest
+    let before_page = match elem.get_attribute("type").as_deref() {
+        Some("top-pad") => true,
+        // the top padding and the page are the first two children
+        Some("scroll-window") => offset <= 1,
+        _ => false,
+    };
+    rendered_edge_pos(page_data, before_page)
+}
+/// the start (or end) of the first (or last) rendered text in the node
+fn edge_pos(page_data: RwSignal<Page>, node: &Node, start: bool) -> Option<Pos> {
+    if node.node_type() == Node::TEXT_NODE {
+        let text_len = node.text_content().map_or(0, |t| t.encode_utf16().count() as u32);
+        return dom_to_pos(page_data, node, if start { 0 } else { text_len });
+    }
+    let elem = node.dyn_ref::<Element>()?;
+    let hash = elem.get_attribute("hash")?;
+    let is_text = page_data.read_doc(|d| {
+        d.contains(&hash) && *d.kind(&hash) == PageNodeType::RawText
+    });
+    if is_text {
+        return page_data.read_doc(|d| match start {
+            true => Some(Pos::new(&hash, 0)),
+            false => Some(Pos::new(&hash, d.text(&hash).len())),
+        });
+    }
+    let child = if start { elem.first_element_child() } else { elem.last_element_child() };
+    edge_pos(page_data, &child?, start)
+}
+/// the start of the first rendered block, or the end of the last one
+fn rendered_edge_pos(page_data: RwSignal<Page>, start: bool) -> Option<Pos> {
+    let (top, bot) = page_data.update_returning_untracked(|p| {
+        (p.top_elem.get_untracked().hash, p.bot_elem.get_untracked().hash)
+    }).unwrap();
+    page_data.read_doc(|d| match start {
+        true => Some(Pos::new(&d.first_text(&top)?, 0)),
+        false => {
+            let last = d.last_text(&bot)?;
+            Some(Pos::new(&last, d.text(&last).len()))
+        },
+    })
+}
+/// doc position -> DOM position. `None` if the text node isn't rendered
+pub fn pos_to_dom(page_data: RwSignal<Page>, pos: &Pos) -> Option<(Node, u32)> {
+    let elem = page_data.hash_to_node(&pos.hash)?.get_untracked().elem_ref?;
+    let text_node = elem.first_child()?;
+    let text = page_data.read_doc(|d| d.text(&pos.hash).to_string());
+    Some((text_node, byte_to_utf16(&text, pos.offset)))
+}
+
+/// the DOM selection in doc positions. `None` if either end isn't in a text 
+/// node (e.g. its node was just unrendered)
+fn read_dom_selection(page_data: RwSignal<Page>) -> Option<Selection> {
+    let selection = document().get_selection().ok()??;
+    let anchor = dom_to_pos(page_data, &selection.anchor_node()?, selection.anchor_offset())?;
+    let head = dom_to_pos(page_data, &selection.focus_node()?, selection.focus_offset())?;
+    Some(Selection::new(anchor, head))
+}
+
+fn selection_signals(page_data: RwSignal<Page>
+) -> (RwSignal<Option<Selection>>, RwSignal<Option<Selection>>) {
+    page_data.update_returning_untracked(|p| (p.selection, p.shown_selection)).unwrap()
+}
+
+/// copy a selection the user made in the DOM to the doc selection. does 
+/// nothing if the DOM selection is just the one we put there, bc if an end 
+/// wasn't rendered it's only a stand-in for the real one
+pub fn sync_selection_from_dom(page_data: RwSignal<Page>) {
+    let Some(dom_sel) = read_dom_selection(page_data) else { return };
+    let (selection, shown) = selection_signals(page_data);
+    let shown_sel = shown.get_untracked();
+    if shown_sel.as_ref() == Some(&dom_sel) { return }
+    let new_sel = match (selection.get_untracked(), shown_sel) {
+        // e.g. shift+click while the anchor isn't rendered. keep the real 
+        // anchor rather than the stand-in
+        (Some(sel), Some(shown_sel)) if shown_sel.anchor == dom_sel.anchor => {
+            Selection::new(sel.anchor, dom_sel.head.clone())
+        },
+        _ => dom_sel.clone(),
+    };
+    selection.set_untracked(Some(new_sel));
+    shown.set_untracked(Some(dom_sel));
+}
+
+/// keep the doc selection up to date as the user moves the caret/selects
+pub fn track_selection(page_data: RwSignal<Page>) {
+    let on_selectionchange = Closure::<dyn FnMut(_)>::new(move |_: web_sys::Event| {
+        sync_selection_from_dom(page_data);
+    });
+    document().add_event_listener_with_callback("selectionchange", 
+        on_selectionchange.as_ref().unchecked_ref()).unwrap();
+    // the page lives as long as the app, so the listener does too
+    on_selectionchange.forget();
+}
+
+/// the start and end of the doc selection, in doc order (the same pos if 
+/// it's just a caret)
+pub fn selected_range(page_data: RwSignal<Page>) -> Option<(Pos, Pos)> {
+    sync_selection_from_dom(page_data);
+    let (selection, _) = selection_signals(page_data);
+    let sel = selection.get_untracked()?;
+    page_data.read_doc(|d| d.selection_is_valid(&sel).then(|| d.selection_range(&sel)))
+}
+
+/// set the doc selection and show it in the DOM
+pub fn set_selection(page_data: RwSignal<Page>, sel: Selection) {
+    let (selection, _) = selection_signals(page_data);
+    selection.set_untracked(Some(sel));
+    show_selection(page_data);
+}
+/// put the caret at a position in the doc
+pub fn set_caret(page_data: RwSignal<Page>, pos: &Pos) {
+    set_selection(page_data, Selection::caret(pos.clone()));
+}
+
+/// put the doc selection back after the DOM under it was rebuilt. whatever 
+/// the DOM selection ended up as isn't something the user did, so it's 
+/// ignored
+pub fn reshow_selection(page_data: RwSignal<Page>) {
+    let (_, shown) = selection_signals(page_data);
+    shown.set_untracked(read_dom_selection(page_data));
+    show_selection(page_data);
+}
+
+/// if the doc position isn't rendered, move it to the start/end of the 
+/// rendered blocks (whichever side it's on)
+fn clamp_to_rendered(page_data: RwSignal<Page>, pos: &Pos) -> Option<Pos> {
+    if pos_to_dom(page_data, pos).is_some() { return Some(pos.clone()) }
+    let first = rendered_edge_pos(page_data, true)?;
+    match page_data.read_doc(|d| d.cmp_pos(pos, &first)).is_lt() {
+        true => Some(first),
+        false => rendered_edge_pos(page_data, false),
+    }
+}
+
+/// put the doc selection in the DOM. ends that aren't rendered are moved to 
+/// the edge of the rendered blocks, so the rendered part of the selection is 
+/// still highlighted
+pub fn show_selection(page_data: RwSignal<Page>) {
+    // whole blocks are selected instead (see `block_selection.rs`)
+    if page_data.update_returning_untracked(|p| p.block_selection.get_untracked().is_some()).unwrap() {
+        return
+    }
+    // pick up any change the user made first, so it isn't overwritten
+    sync_selection_from_dom(page_data);
+    let (selection, shown) = selection_signals(page_data);
+    let Some(sel) = selection.get_untracked() else { return };
+    if !page_data.read_doc(|d| d.selection_is_valid(&sel)) { return }
+    let (Some(anchor), Some(head)) = (
+        clamp_to_rendered(page_data, &sel.anchor),
+        clamp_to_rendered(page_data, &sel.head),
+    ) else { return };
+    let shown_sel = Selection::new(anchor, head);
+    // rewriting the DOM selection when it's already right would interrupt 
+    // e.g. a mouse drag
+    if read_dom_selection(page_data).as_ref() == Some(&shown_sel) {
+        shown.set_untracked(Some(shown_sel));
+        return;
+    }
+    let (Some((anchor_node, anchor_offset)), Some((head_node, head_offset))) = (
+        pos_to_dom(page_data, &shown_sel.anchor),
+        pos_to_dom(page_data, &shown_sel.head),
+    ) else { return };
+    let dom_selection = document().get_selection().unwrap().unwrap();
+    dom_selection.set_base_and_extent(&anchor_node, anchor_offset, 
+        &head_node, head_offset).unwrap();
+    shown.set_untracked(Some(shown_sel));
+}
+
+/// if the caret isn't rendered (e.g. after editing a selection that went 
+/// off screen), scroll to it
+pub fn scroll_to_selection(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element) {
+    let (selection, _) = selection_signals(page_data);
+    let Some(sel) = selection.get_untracked() else { return };
+    if pos_to_dom(page_data, &sel.head).is_some() { return }
+    let Some(block) = page_data.read_doc(|d| {
+        d.contains(&sel.head.hash).then(|| d.block_of(&sel.head.hash)).flatten()
+    }) else { return };
+    jump_to_block(cx, page_data, page_elem, &block, true);
+    show_selection(page_data);
+}
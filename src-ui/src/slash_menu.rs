@@ -0,0 +1,221 @@
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, create_rw_signal, spawn_local, document, 
+    JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use tauri_sys::tauri;
+use web_sys::{Element, KeyboardEvent, MouseEvent};
+
+use super::{PageView, Page, Pos, SlashItem, Template, SyntaxProfile, ReadDoc, slash_items, 
+    filter_slash_items, selected_range, apply_edit};
+
+// typing `/` at the start of a block (or after a space) opens a menu of block 
+// kinds and things to insert. what's typed after the `/` filters it, and 
+// picking an item replaces the `/...` (see `Doc::apply_slash_item`). the 
+// caret stays in the page the whole time, so the menu is driven from the 
+// page's own key/input events
+
+/// the slash menu while it's open
+#[derive(Debug, Clone)]
+pub struct SlashMenu {
+    /// where the `/` is
+    pub start: Pos,
+    /// the items that match what's been typed after the `/`
+    pub items: Vec<SlashItem>,
+    pub selected: usize,
+}
+
+/// the user's templates, once they've been read
+pub fn load_templates(cx: Scope) -> RwSignal<Vec<Template>> {
+    let templates = create_rw_signal(cx, Vec::new());
+    spawn_local(async move {
+        match tauri::invoke::<_, Vec<Template>>("load_templates", &()).await {
+            Ok(loaded) => templates.set_untracked(loaded),
+            Err(err) => log!("COULDN'T LOAD TEMPLATES: {:?}", err),
+        }
+    });
+    templates
+}
+
+fn today() -> String {
+    let now = js_sys::Date::new_0();
+    format!("{:04}-{:02}-{:02}", now.get_full_year(), now.get_month() + 1, now.get_date())
+}
+
+/// after an input event, open the menu if a `/` was just typed, or filter it 
+/// by what's been typed since. it closes once the caret leaves the `/...` or 
+/// nothing matches
+pub fn update_slash_menu(page_data: RwSignal<Page>, menu: RwSignal<Option<SlashMenu>>, 
+    templates: RwSignal<Vec<Template>>, typed: Option<&str>,
+) {
+    let Some((caret, end)) = selected_range(page_data) else { return menu.set_untracked(None) };
+    let start = match menu.get_untracked() {
+        Some(open) => open.start,
+        None if typed == Some("/") && caret == end && caret.offset > 0 => {
+            let start = Pos::new(&caret.hash, caret.offset - 1);
+            let opens = page_data.read_doc(|d| {
+                let before = &d.text(&start.hash)[..start.offset];
+                match before.is_empty() {
+                    // only the first text in the block is at the start of it
+                    true => d.block_of(&start.hash).and_then(|b| d.first_text(&b)) 
+                        == Some(start.hash.clone()),
+                    false => before.ends_with(char::is_whitespace),
+                }
+            });
+            if !opens { return }
+            start
+        },
+        None => return,
+    };
+    let query = page_data.read_doc(|d| {
+        let in_query = caret == end && caret.hash == start.hash && d.contains(&start.hash)
+            && caret.offset > start.offset;
+        let text = d.text(&start.hash);
+        (in_query && text[start.offset..].starts_with('/'))
+            .then(|| text.get(start.offset + 1..caret.offset).map(String::from))
+            .flatten()
+    });
+    let Some(query) = query else { return menu.set_untracked(None) };
+    let items = filter_slash_items(&slash_items(&today(), &templates.get_untracked()), &query);
+    match items.is_empty() {
+        true => menu.set_untracked(None),
+        false => menu.set_untracked(Some(SlashMenu { start, items, selected: 0 })),
+    }
+}
+
+/// keys for the menu while it's open. returns whether the key was used
+pub fn process_slash_keydown(event: &KeyboardEvent, page_data: RwSignal<Page>, 
+    menu: RwSignal<Option<SlashMenu>>, syntax: RwSignal<SyntaxProfile>,
+) -> bool {
+    let Some(mut open) = menu.get_untracked() else { return false };
+    let len = open.items.len();
+    match event.key().as_str() {
+        "ArrowDown" => open.selected = (open.selected + 1) % len,
+        "ArrowUp" => open.selected = (open.selected + len - 1) % len,
+        "Enter" | "Tab" => {
+            event.prevent_default();
+            choose_slash_item(page_data, menu, open.selected, syntax);
+            return true;
+        },
+        "Escape" => {
+            event.prevent_default();
+            menu.set_untracked(None);
+            return true;
+        },
+        // the caret is leaving the `/...`
+        "ArrowLeft" | "ArrowRight" | "Home" | "End" => {
+            menu.set_untracked(None);
+            return false;
+        },
+        _ => return false,
+    }
+    event.prevent_default();
+    menu.set_untracked(Some(open));
+    true
+}
+
+/// replace the `/...` w/ the item
+pub fn choose_slash_item(page_data: RwSignal<Page>, menu: RwSignal<Option<SlashMenu>>, 
+    index: usize, syntax: RwSignal<SyntaxProfile>,
+) {
+    let Some(open) = menu.get_untracked() else { return };
+    menu.set_untracked(None);
+    let Some(item) = open.items.get(index) else { return };
+    let Some((caret, _)) = selected_range(page_data) else { return };
+    let edit = page_data.read_doc(|d| {
+        d.apply_slash_item(&open.start, &caret, &item.action, &syntax.get_untracked())
+    });
+    match edit {
+        Some((tx, caret)) => { apply_edit(page_data, &tx, caret); },
+        None => log!("COULDN'T APPLY SLASH ITEM: {:?}", item.label),
+    }
+}
+
+/// clicking an item picks it. the click mustn't take the focus from the page, 
+/// or the caret would go
+pub fn process_slash_mousedown(event: MouseEvent, page_data: RwSignal<Page>, 
+    menu: RwSignal<Option<SlashMenu>>, syntax: RwSignal<SyntaxProfile>,
+) {
+    event.prevent_default();
+    let index = event.target()
+        .and_then(|t| t.dyn_into::<Element>().ok())
+        .and_then(|e| e.closest("[data-index]").ok().flatten())
+        .and_then(|e| e.get_attribute("data-index"))
+        .and_then(|i| i.parse().ok());
+    if let Some(index) = index {
+        choose_slash_item(page_data, menu, index, syntax);
+    }
+}
+
+/// draw the menu (or hide it), just under the caret
+pub fn show_slash_menu(menu_elem: &Element, menu: RwSignal<Option<SlashMenu>>) {
+    let Some(open) = menu.get_untracked() else {
+        menu_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    };
+    menu_elem.set_inner_html("");
+    for (i, item) in open.items.iter().enumerate() {
+        let row = document().create_element("div").unwrap();
+        row.set_attribute("data-index", &i.to_string()).unwrap();
+        if i == open.selected {
+            row.set_attribute("style", "background: #ddd").unwrap();
+        }
+        row.set_text_content(Some(&item.label));
+        menu_elem.append_child(&row).unwrap();
+    }
+    let Some(rect) = document().get_selection().ok().flatten()
+        .and_then(|s| s.get_range_at(0).ok())
+        .map(|r| r.get_bounding_client_rect()) else { return };
+    menu_elem.set_attribute("style", &format!("display: block; position: fixed; \
+        left: {}px; top: {}px; max-height: 200px; overflow-y: auto; cursor: default; \
+        background: white; border: 1px solid #ccc", rect.left(), rect.bottom())).unwrap();
+    if let Some(row) = menu_elem.children().item(open.selected as u32) {
+        row.scroll_into_view_with_bool(false);
+    }
+}
+
+/// the menu's state and elem, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct SlashMenuView {
+    pub menu: RwSignal<Option<SlashMenu>>,
+    pub templates: RwSignal<Vec<Template>>,
+    pub syntax: RwSignal<SyntaxProfile>,
+    pub menu_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl SlashMenuView {
+    pub fn new(cx: Scope, syntax: RwSignal<SyntaxProfile>) -> Self {
+        Self {
+            menu: create_rw_signal(cx, None),
+            templates: load_templates(cx),
+            syntax,
+            menu_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self) {
+        if let Some(menu_elem) = self.menu_ref.get() {
+            show_slash_menu(menu_elem.unchecked_ref::<Element>(), self.menu);
+        }
+    }
+
+    pub fn close(&self) {
+        self.menu.set_untracked(None);
+        self.redraw();
+    }
+
+    /// after something was typed in the page
+    pub fn update(&self, page: PageView, typed: Option<&str>) {
+        update_slash_menu(page.page_data, self.menu, self.templates, typed);
+    }
+
+    /// returns whether the menu took the key
+    pub fn handle_keydown(&self, event: &KeyboardEvent, page: PageView) -> bool {
+        let handled = process_slash_keydown(event, page.page_data, self.menu, self.syntax);
+        self.redraw();
+        handled
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn()) {
+        process_slash_mousedown(event, page.page_data, self.menu, self.syntax);
+        refresh_view();
+        self.redraw();
+    }
+}
@@ -0,0 +1,17 @@
+use leptos::{log, Scope, RwSignal, create_rw_signal, spawn_local, UntrackedSettableSignal};
+use tauri_sys::tauri;
+
+use super::SyntaxProfile;
+
+/// starts as standard markdown, then the user's syntax gets added once their 
+/// config file is read
+pub fn load_syntax_profile(cx: Scope) -> RwSignal<SyntaxProfile> {
+    let profile = create_rw_signal(cx, SyntaxProfile::standard());
+    spawn_local(async move {
+        match tauri::invoke::<_, SyntaxProfile>("load_syntax", &()).await {
+            Ok(user) => profile.set_untracked(SyntaxProfile::with_user(&user)),
+            Err(err) => log!("COULDN'T LOAD SYNTAX: {:?}", err),
+        }
+    });
+    profile
+}
@@ -0,0 +1,81 @@
+use leptos::{Scope, RwSignal, create_rw_signal, window, UntrackedGettableSignal, 
+    UntrackedSettableSignal};
+use web_sys::{Element, KeyboardEvent};
+
+use super::{Page, Vim, EditDoc, ReadDoc, HashToNode, sync_selection_from_dom, set_selection, 
+    jump_to_block};
+
+// the UI side of vim mode (see `Vim` in the model). keys go to vim before the 
+// keymap, except in insert mode where only Escape does. vim edits the doc 
+// itself, so all this does is show the selection it comes back w/
+
+/// where whether vim mode is on is kept between sessions
+const VIM_KEY: &str = "vim";
+
+/// `None` while vim mode is off
+pub fn load_vim(cx: Scope) -> RwSignal<Option<Vim>> {
+    let on = window().local_storage().ok().flatten()
+        .and_then(|s| s.get_item(VIM_KEY).ok().flatten())
+        .is_some_and(|on| on == "true");
+    create_rw_signal(cx, on.then(Vim::new))
+}
+
+pub fn toggle_vim(vim: RwSignal<Option<Vim>>, page_elem: &Element) {
+    let on = vim.get_untracked().is_none();
+    vim.set_untracked(on.then(Vim::new));
+    if let Some(storage) = window().local_storage().ok().flatten() {
+        _ = storage.set_item(VIM_KEY, &on.to_string());
+    }
+    show_vim_mode(vim, page_elem);
+}
+
+/// the mode goes on the page as a `vim` attribute, e.g. to style the caret
+pub fn show_vim_mode(vim: RwSignal<Option<Vim>>, page_elem: &Element) {
+    match vim.get_untracked() {
+        Some(vim) => page_elem.set_attribute("vim", vim.mode.name()).unwrap(),
+        None => page_elem.remove_attribute("vim").unwrap(),
+    }
+}
+
+/// returns whether vim used the key. keys w/ Ctrl/Cmd/Alt are left for the 
+/// keymap
+pub fn process_vim_keydown(cx: Scope, event: &KeyboardEvent, page_data: RwSignal<Page>, 
+    page_elem: &Element, vim: RwSignal<Option<Vim>>,
+) -> bool {
+    if event.ctrl_key() || event.meta_key() || event.alt_key() || event.is_composing() { 
+        return false 
+    }
+    let key = event.key();
+    let wants = vim.update_returning_untracked(|v| v.as_ref().is_some_and(|v| v.wants(&key)));
+    if wants != Some(true) { return false }
+    event.prevent_default();
+    sync_selection_from_dom(page_data);
+    let sel = page_data.update_returning_untracked(|p| p.selection.get_untracked()).flatten();
+    let Some(sel) = sel else { return true };
+    let new_sel = page_data.edit_doc(|d| {
+        vim.update_returning_untracked(|v| v.as_mut().and_then(|v| v.key(d, &sel, &key)))
+    }).flatten();
+    if let Some(new_sel) = new_sel {
+        // e.g. `G` to a block that isn't rendered
+        let block = page_data.read_doc(|d| d.block_of(&new_sel.head.hash));
+        if let Some(block) = block {
+            let rendered = page_data.hash_to_node(&block)
+                .is_some_and(|n| n.get_untracked().elem_ref.is_some());
+            if !rendered {
+                jump_to_block(cx, page_data, page_elem, &block, true);
+            }
+        }
+        set_selection(page_data, new_sel);
+    }
+    show_vim_mode(vim, page_elem);
+    true
+}
+
+/// text typed in insert mode, for `.` to repeat
+pub fn vim_typed(vim: RwSignal<Option<Vim>>, text: &str) {
+    vim.update_untracked(|v| {
+        if let Some(v) = v {
+            v.typed(text);
+        }
+    });
+}
@@ -0,0 +1,305 @@
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, create_rw_signal, spawn_local, document, 
+    JsCast, UntrackedGettableSignal, UntrackedSettableSignal};
+use serde::Serialize;
+use tauri_sys::{tauri, dialog::MessageDialogBuilder};
+use web_sys::{Element, KeyboardEvent, MouseEvent};
+
+use super::{PageView, Page, PageNodeType, Pos, NoteInfo, LinkCompletion, LinkTarget, ReadDoc, link_completions,
+    resolve_link, selected_range, apply_edit, set_caret, jump_to_block, load_file, workspace_dir};
+
+// `[[note]]` links. typing `[[` opens a menu of the notes in the workspace
+// (and their headings, once there's a `#`), filtered by what's typed after
+// it, like the slash menu. Cmd/Ctrl+click on a link opens the note it goes
+// to, and if there's no such note it offers to make it. the notes are listed
+// by the backend (see `notes.rs` there), again each time the menu opens or a
+// link is followed, so they're never far out of date
+
+/// the `[[` menu while it's open
+#[derive(Debug, Clone)]
+pub struct LinkMenu {
+    /// where the `[[` is
+    pub start: Pos,
+    /// empty while the notes are still being listed, or if none match
+    pub items: Vec<LinkCompletion>,
+    pub selected: usize,
+}
+
+#[derive(Serialize)]
+struct RootArgs {
+    root: String,
+}
+#[derive(Serialize)]
+struct CreateNoteArgs {
+    root: String,
+    link: String,
+}
+
+/// list the notes again. `after` runs once they're in
+pub fn refresh_notes(notes: RwSignal<Vec<NoteInfo>>, path: RwSignal<Option<String>>,
+    after: impl Fn() + 'static,
+) {
+    let Some(root) = workspace_dir(path) else { return };
+    spawn_local(async move {
+        match tauri::invoke::<_, Vec<NoteInfo>>("list_notes", &RootArgs { root }).await {
+            Ok(listed) => notes.set_untracked(listed),
+            Err(err) => log!("COULDN'T LIST NOTES: {:?}", err),
+        }
+        after();
+    });
+}
+
+/// after an input event, open the menu if `[[` was just typed, or filter it
+/// by what's been typed since. it closes once the caret leaves the `[[...`,
+/// or the link is closed w/ a `]`. returns whether it just opened
+pub fn update_link_menu(page_data: RwSignal<Page>, menu: RwSignal<Option<LinkMenu>>,
+    notes: RwSignal<Vec<NoteInfo>>, typed: Option<&str>,
+) -> bool {
+    let Some((caret, end)) = selected_range(page_data) else {
+        menu.set_untracked(None);
+        return false;
+    };
+    let (start, opened) = match menu.get_untracked() {
+        Some(open) => (open.start, false),
+        None if typed == Some("[") && caret == end && caret.offset >= 2 => {
+            let start = Pos::new(&caret.hash, caret.offset - 2);
+            let opens = page_data.read_doc(|d| {
+                d.text(&caret.hash)[..caret.offset].ends_with("[[")
+                    && d.parent(&caret.hash).is_some_and(|p| {
+                        !matches!(d.kind(p), PageNodeType::CodeInline | PageNodeType::FileLink)
+                    })
+            });
+            if !opens { return false }
+            (start, true)
+        },
+        None => return false,
+    };
+    let query = page_data.read_doc(|d| {
+        let in_query = caret == end && caret.hash == start.hash && d.contains(&start.hash)
+            && caret.offset >= start.offset + 2;
+        let text = d.text(&start.hash);
+        (in_query && text[start.offset..].starts_with("[["))
+            .then(|| text.get(start.offset + 2..caret.offset).map(String::from))
+            .flatten()
+    });
+    match query.filter(|q| !q.contains(']')) {
+        Some(query) => {
+            let items = link_completions(&notes.get_untracked(), &query);
+            menu.set_untracked(Some(LinkMenu { start, items, selected: 0 }));
+        },
+        None => menu.set_untracked(None),
+    }
+    opened
+}
+
+/// keys for the menu while it has items. returns whether the key was used
+pub fn process_link_keydown(event: &KeyboardEvent, page_data: RwSignal<Page>,
+    menu: RwSignal<Option<LinkMenu>>,
+) -> bool {
+    let Some(mut open) = menu.get_untracked() else { return false };
+    let len = open.items.len();
+    match event.key().as_str() {
+        "Escape" => {
+            event.prevent_default();
+            menu.set_untracked(None);
+            return true;
+        },
+        // the caret is leaving the `[[...`
+        "ArrowLeft" | "ArrowRight" | "Home" | "End" => {
+            menu.set_untracked(None);
+            return false;
+        },
+        _ if len == 0 => return false,
+        "ArrowDown" => open.selected = (open.selected + 1) % len,
+        "ArrowUp" => open.selected = (open.selected + len - 1) % len,
+        "Enter" | "Tab" => {
+            event.prevent_default();
+            choose_link_item(page_data, menu, open.selected);
+            return true;
+        },
+        _ => return false,
+    }
+    event.prevent_default();
+    menu.set_untracked(Some(open));
+    true
+}
+
+/// replace the `[[...` w/ a link to the item
+pub fn choose_link_item(page_data: RwSignal<Page>, menu: RwSignal<Option<LinkMenu>>, index: usize) {
+    let Some(open) = menu.get_untracked() else { return };
+    menu.set_untracked(None);
+    let Some(item) = open.items.get(index) else { return };
+    let Some((caret, _)) = selected_range(page_data) else { return };
+    match page_data.read_doc(|d| d.insert_file_link(&open.start, &caret, &item.link)) {
+        Some((tx, caret)) => { apply_edit(page_data, &tx, caret); },
+        None => log!("COULDN'T INSERT LINK: {:?}", item.link),
+    }
+}
+
+/// clicking an item picks it, w/o taking the focus from the page
+pub fn process_link_menu_mousedown(event: MouseEvent, page_data: RwSignal<Page>,
+    menu: RwSignal<Option<LinkMenu>>,
+) {
+    event.prevent_default();
+    let index = event.target()
+        .and_then(|t| t.dyn_into::<Element>().ok())
+        .and_then(|e| e.closest("[data-index]").ok().flatten())
+        .and_then(|e| e.get_attribute("data-index"))
+        .and_then(|i| i.parse().ok());
+    if let Some(index) = index {
+        choose_link_item(page_data, menu, index);
+    }
+}
+
+/// draw the menu (or hide it), just under the caret
+pub fn show_link_menu(menu_elem: &Element, menu: RwSignal<Option<LinkMenu>>) {
+    let Some(open) = menu.get_untracked().filter(|m| !m.items.is_empty()) else {
+        menu_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    };
+    menu_elem.set_inner_html("");
+    for (i, item) in open.items.iter().enumerate() {
+        let row = document().create_element("div").unwrap();
+        row.set_attribute("data-index", &i.to_string()).unwrap();
+        if i == open.selected {
+            row.set_attribute("style", "background: #ddd").unwrap();
+        }
+        row.set_text_content(Some(&item.label));
+        menu_elem.append_child(&row).unwrap();
+    }
+    let Some(rect) = document().get_selection().ok().flatten()
+        .and_then(|s| s.get_range_at(0).ok())
+        .map(|r| r.get_bounding_client_rect()) else { return };
+    menu_elem.set_attribute("style", &format!("display: block; position: fixed; \
+        left: {}px; top: {}px; max-height: 200px; overflow-y: auto; cursor: default; \
+        background: white; border: 1px solid #ccc", rect.left(), rect.bottom())).unwrap();
+    if let Some(row) = menu_elem.children().item(open.selected as u32) {
+        row.scroll_into_view_with_bool(false);
+    }
+}
+
+/// the `link` of the link that was Cmd/Ctrl+clicked, if one was. a link w/
+/// no `link` goes to whatever its label says
+pub fn link_at(event: &MouseEvent, page_data: RwSignal<Page>) -> Option<String> {
+    if !(event.ctrl_key() || event.meta_key()) { return None }
+    let hash = event.target()
+        .and_then(|t| t.dyn_into::<Element>().ok())
+        .and_then(|e| e.closest("[type=fl]").ok().flatten())
+        .and_then(|e| e.get_attribute("hash"))?;
+    page_data.read_doc(|d| {
+        if !d.contains(&hash) { return None }
+        let link = d.content(&hash, "link").filter(|l| !l.is_empty())
+            .map(String::from).unwrap_or_else(|| d.plain_text(&hash));
+        Some(link)
+    })
+}
+
+/// open the note `link` goes to, and go to the heading in it if it has one.
+/// if there's no such note, ask to make it. `after` runs once it's open
+pub fn follow_link(cx: Scope, page_data: RwSignal<Page>, page_elem: Element,
+    path: RwSignal<Option<String>>, notes: RwSignal<Vec<NoteInfo>>, link: String,
+    after: impl Fn() + 'static,
+) {
+    let Some(root) = workspace_dir(path) else {
+        return log!("OPEN A FILE FIRST, ITS FOLDER IS THE WORKSPACE");
+    };
+    spawn_local(async move {
+        let args = RootArgs { root: root.clone() };
+        match tauri::invoke::<_, Vec<NoteInfo>>("list_notes", &args).await {
+            Ok(listed) => notes.set_untracked(listed),
+            Err(err) => return log!("COULDN'T LIST NOTES: {:?}", err),
+        }
+        let target = LinkTarget::parse(&link);
+        let found = resolve_link(&notes.get_untracked(), &link).map(|n| n.path.clone());
+        let file = match found {
+            Some(file) => file,
+            None => {
+                let ask = format!("There's no note called \u{201c}{}\u{201d}. Make it?", target.note);
+                if !MessageDialogBuilder::new().ask(&ask).await.unwrap_or(false) { return }
+                let args = CreateNoteArgs { root, link: link.clone() };
+                match tauri::invoke::<_, String>("create_note", &args).await {
+                    Ok(file) => file,
+                    Err(err) => return log!("COULDN'T MAKE {}: {:?}", target.note, err),
+                }
+            },
+        };
+        load_file(cx, page_data, &page_elem, path, file).await;
+        if let Some(heading) = target.heading {
+            let hash = page_data.read_doc(|d| {
+                d.outline().into_iter().find(|e| e.text.eq_ignore_ascii_case(&heading)).map(|e| e.hash)
+            });
+            if let Some(hash) = hash {
+                jump_to_block(cx, page_data, &page_elem, &hash, true);
+                if let Some(text) = page_data.read_doc(|d| d.first_text(&hash)) {
+                    set_caret(page_data, &Pos::new(&text, 0));
+                }
+            }
+        }
+        after();
+    });
+}
+
+/// the menu's state and elem, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct LinkMenuView {
+    pub menu: RwSignal<Option<LinkMenu>>,
+    /// the notes it lists
+    pub notes: RwSignal<Vec<NoteInfo>>,
+    pub menu_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl LinkMenuView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            menu: create_rw_signal(cx, None),
+            notes: create_rw_signal(cx, Vec::new()),
+            menu_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self) {
+        if let Some(menu_elem) = self.menu_ref.get() {
+            show_link_menu(menu_elem.unchecked_ref::<Element>(), self.menu);
+        }
+    }
+
+    pub fn close(&self) {
+        self.menu.set_untracked(None);
+        self.redraw();
+    }
+
+    /// after something was typed in the page
+    pub fn update(&self, page: PageView, typed: Option<&str>) {
+        if update_link_menu(page.page_data, self.menu, self.notes, typed) {
+            let view = *self;
+            // filter it again once the notes are in
+            refresh_notes(self.notes, page.file_path, move || {
+                update_link_menu(page.page_data, view.menu, view.notes, None);
+                view.redraw();
+            });
+        }
+    }
+
+    /// returns whether the menu took the key
+    pub fn handle_keydown(&self, event: &KeyboardEvent, page: PageView) -> bool {
+        let handled = process_link_keydown(event, page.page_data, self.menu);
+        self.redraw();
+        handled
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn()) {
+        process_link_menu_mousedown(event, page.page_data, self.menu);
+        refresh_view();
+        self.redraw();
+    }
+
+    /// a click on a link in the page. returns whether it opened one
+    pub fn follow(&self, event: &MouseEvent, page: PageView, after_open: impl Fn() + 'static) -> bool {
+        let (Some(link), Some(page_elem)) = (link_at(event, page.page_data), page.page_elem()) else {
+            return false
+        };
+        // don't put the caret in the link
+        event.prevent_default();
+        follow_link(page.cx, page.page_data, page_elem, page.file_path, self.notes, link, after_open);
+        true
+    }
+}
@@ -0,0 +1,331 @@
+use leptos::{log, Scope, RwSignal, NodeRef, HtmlElement, Div, Input, create_rw_signal, spawn_local, 
+    document, JsCast, UntrackedGettableSignal};
+use serde::{Serialize, Deserialize};
+use tauri_sys::tauri;
+use web_sys::{Element, HtmlInputElement, KeyboardEvent, MouseEvent};
+
+use super::{PageView, Page, WorkspaceReplace, FileChanges, FindOptions, load_file, workspace_dir,
+    show_find_options, find_button_at, toggle_find_option};
+
+// find & replace across the workspace (Mod-Shift-F). the files are done by the
+// backend (see `workspace.rs` there). Preview lists the lines that would
+// change in each file, w/ a checkbox per file. Replace does the ticked files
+// all together, and Undo puts back the last replace. if the open file is one
+// that changed, it's loaded again
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceFind {
+    pub open: bool,
+    pub options: FindOptions,
+    /// the last preview
+    pub files: Vec<FileChanges>,
+    /// what it was for. Replace does what was previewed, even if the inputs
+    /// have changed since
+    pub previewed: WorkspaceReplace,
+    /// e.g. how many files changed, or what went wrong
+    pub status: String,
+}
+
+/// what a key or button in the panel does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceAction {
+    Preview,
+    Replace,
+    Undo,
+    Close,
+}
+impl WorkspaceAction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "preview" => Self::Preview,
+            "replace" => Self::Replace,
+            "undo" => Self::Undo,
+            "close" => Self::Close,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct PreviewArgs {
+    root: String,
+    replace: WorkspaceReplace,
+}
+#[derive(Serialize)]
+struct ApplyArgs {
+    root: String,
+    replace: WorkspaceReplace,
+    paths: Vec<String>,
+}
+#[derive(Deserialize)]
+struct UndoneReplace {
+    restored: Vec<String>,
+    skipped: Vec<String>,
+}
+
+pub fn open_workspace_find(find: RwSignal<WorkspaceFind>, query_input: &HtmlInputElement) {
+    find.update_untracked(|f| f.open = true);
+    _ = query_input.focus();
+    query_input.select();
+}
+
+pub fn close_workspace_find(find: RwSignal<WorkspaceFind>) {
+    find.update_untracked(|f| {
+        f.open = false;
+        f.files.clear();
+        f.status.clear();
+    });
+}
+
+/// Enter previews, Escape closes
+pub fn process_workspace_find_keydown(event: &KeyboardEvent) -> Option<WorkspaceAction> {
+    let action = match event.key().as_str() {
+        "Enter" => WorkspaceAction::Preview,
+        "Escape" => WorkspaceAction::Close,
+        _ => return None,
+    };
+    event.prevent_default();
+    Some(action)
+}
+
+fn set_status(find: RwSignal<WorkspaceFind>, status: String) {
+    find.update_untracked(|f| f.status = status);
+}
+
+/// look for the matches in every file. `after` runs once the preview is in
+pub fn preview_workspace_replace(find: RwSignal<WorkspaceFind>, path: RwSignal<Option<String>>,
+    replace: WorkspaceReplace, after: impl Fn() + 'static,
+) {
+    let Some(root) = workspace_dir(path) else {
+        set_status(find, "Open a file first, its folder is the workspace".into());
+        return after();
+    };
+    spawn_local(async move {
+        let args = PreviewArgs { root, replace: replace.clone() };
+        match tauri::invoke::<_, Vec<FileChanges>>("preview_workspace_replace", &args).await {
+            Ok(files) => {
+                let lines: usize = files.iter().map(|f| f.lines.len()).sum();
+                let status = match files.is_empty() {
+                    true => "No results".to_string(),
+                    false => format!("{} lines in {} files", lines, files.len()),
+                };
+                find.update_untracked(|f| {
+                    f.files = files;
+                    f.previewed = replace;
+                    f.status = status;
+                });
+            },
+            Err(err) => {
+                find.update_untracked(|f| f.files.clear());
+                set_status(find, format!("{:?}", err));
+            },
+        }
+        after();
+    });
+}
+
+/// the files ticked in the preview
+fn ticked_files(list_elem: &Element) -> Vec<String> {
+    let boxes = list_elem.query_selector_all("input[path]").unwrap();
+    (0..boxes.length()).filter_map(|i| {
+        let input = boxes.get(i)?.dyn_into::<HtmlInputElement>().ok()?;
+        input.checked().then(|| input.get_attribute("path")).flatten()
+    }).collect()
+}
+
+/// load the open file again if it's one of the `changed` ones
+async fn reload_if_changed(cx: Scope, page_data: RwSignal<Page>, page_elem: &Element,
+    path: RwSignal<Option<String>>, changed: &[String],
+) {
+    let Some(open) = path.get_untracked() else { return };
+    if changed.contains(&open) {
+        load_file(cx, page_data, page_elem, path, open).await;
+    }
+}
+
+/// do the previewed replace in the ticked files. `after` runs once they're
+/// written
+pub fn apply_workspace_replace(cx: Scope, page_data: RwSignal<Page>, page_elem: Element,
+    find: RwSignal<WorkspaceFind>, path: RwSignal<Option<String>>, list_elem: &Element,
+    after: impl Fn() + 'static,
+) {
+    let Some(root) = workspace_dir(path) else { return };
+    let paths = ticked_files(list_elem);
+    if paths.is_empty() { return }
+    let replace = find.update_returning_untracked(|f| f.previewed.clone()).unwrap();
+    spawn_local(async move {
+        let args = ApplyArgs { root, replace, paths };
+        match tauri::invoke::<_, Vec<String>>("apply_workspace_replace", &args).await {
+            Ok(changed) => {
+                find.update_untracked(|f| f.files.clear());
+                set_status(find, format!("Replaced in {} files", changed.len()));
+                reload_if_changed(cx, page_data, &page_elem, path, &changed).await;
+            },
+            Err(err) => set_status(find, format!("Nothing was replaced: {:?}", err)),
+        }
+        after();
+    });
+}
+
+/// put back the files from the last replace
+pub fn undo_workspace_replace(cx: Scope, page_data: RwSignal<Page>, page_elem: Element,
+    find: RwSignal<WorkspaceFind>, path: RwSignal<Option<String>>, after: impl Fn() + 'static,
+) {
+    spawn_local(async move {
+        match tauri::invoke::<_, UndoneReplace>("undo_workspace_replace", &()).await {
+            Ok(undone) => {
+                let mut status = format!("Put back {} files", undone.restored.len());
+                if !undone.skipped.is_empty() {
+                    status += &format!(", {} had changed since so were left", undone.skipped.len());
+                    log!("NOT UNDONE, CHANGED SINCE: {:?}", undone.skipped);
+                }
+                find.update_untracked(|f| f.files.clear());
+                set_status(find, status);
+                reload_if_changed(cx, page_data, &page_elem, path, &undone.restored).await;
+            },
+            Err(err) => set_status(find, format!("{:?}", err)),
+        }
+        after();
+    });
+}
+
+/// show/hide the panel and update its status and option buttons
+pub fn show_workspace_find(find: RwSignal<WorkspaceFind>, panel_elem: &Element) {
+    let state = find.get_untracked();
+    if !state.open {
+        panel_elem.set_attribute("style", "display: none").unwrap();
+        return;
+    }
+    panel_elem.set_attribute("style", "display: flex; flex-wrap: wrap; gap: 4px; position: fixed; \
+        top: 8px; right: 8px; width: 360px; padding: 6px; background: white; \
+        border: 1px solid #ccc; border-radius: 4px; z-index: 10").unwrap();
+    show_find_options(&state.options, panel_elem);
+    if let Ok(Some(status_elem)) = panel_elem.query_selector("[type=workspace-status]") {
+        status_elem.set_text_content(Some(&state.status));
+    }
+}
+
+/// list the lines that would change, under a checkbox for each file. call
+/// once a preview is in (not on every redraw, so the ticks stay)
+pub fn show_workspace_preview(find: RwSignal<WorkspaceFind>, path: RwSignal<Option<String>>,
+    list_elem: &Element,
+) {
+    list_elem.set_inner_html("");
+    let root = workspace_dir(path).unwrap_or_default();
+    for file in find.get_untracked().files {
+        let label = document().create_element("label").unwrap();
+        label.set_attribute("style", "display: block; font-weight: bold").unwrap();
+        let checkbox = document().create_element("input").unwrap();
+        checkbox.set_attribute("type", "checkbox").unwrap();
+        checkbox.set_attribute("checked", "").unwrap();
+        checkbox.set_attribute("path", &file.path).unwrap();
+        label.append_child(&checkbox).unwrap();
+        let name = file.path.strip_prefix(&root).unwrap_or(&file.path).trim_start_matches(['/', '\\']);
+        label.append_with_str_1(name).unwrap();
+        list_elem.append_child(&label).unwrap();
+        for change in &file.lines {
+            let line = document().create_element("div").unwrap();
+            line.set_attribute("style", "font-family: monospace; white-space: pre-wrap").unwrap();
+            line.append_with_str_1(&format!("{}: ", change.line)).unwrap();
+            let before = document().create_element("del").unwrap();
+            before.set_text_content(Some(&change.before));
+            let after = document().create_element("ins").unwrap();
+            after.set_text_content(Some(&change.after));
+            line.append_with_node_1(&before).unwrap();
+            line.append_with_str_1(" → ").unwrap();
+            line.append_with_node_1(&after).unwrap();
+            list_elem.append_child(&line).unwrap();
+        }
+    }
+}
+
+/// the panel's state and elems, for the page to wire up
+#[derive(Clone, Copy)]
+pub struct WorkspaceFindView {
+    pub find: RwSignal<WorkspaceFind>,
+    pub panel_ref: NodeRef<HtmlElement<Div>>,
+    pub query_ref: NodeRef<HtmlElement<Input>>,
+    pub replacement_ref: NodeRef<HtmlElement<Input>>,
+    pub preview_ref: NodeRef<HtmlElement<Div>>,
+}
+
+impl WorkspaceFindView {
+    pub fn new(cx: Scope) -> Self {
+        Self {
+            find: create_rw_signal(cx, WorkspaceFind::default()),
+            panel_ref: NodeRef::new(cx),
+            query_ref: NodeRef::new(cx),
+            replacement_ref: NodeRef::new(cx),
+            preview_ref: NodeRef::new(cx),
+        }
+    }
+
+    pub fn redraw(&self) {
+        if let Some(panel_elem) = self.panel_ref.get() {
+            show_workspace_find(self.find, panel_elem.unchecked_ref::<Element>());
+        }
+    }
+
+    pub fn open(&self) {
+        let Some(input) = self.query_ref.get() else { return };
+        open_workspace_find(self.find, &input);
+        self.redraw();
+    }
+
+    pub fn run(&self, action: WorkspaceAction, page: PageView, refresh_view: impl Fn() + 'static) {
+        let (Some(page_elem), Some(query_input), Some(replacement_input), Some(list_elem)) = (
+            page.page_elem(), self.query_ref.get(), self.replacement_ref.get(), self.preview_ref.get(),
+        ) else { return };
+        let list_elem = list_elem.unchecked_ref::<Element>().clone();
+        // the backend answers later
+        let after = {
+            let view = *self;
+            let list_elem = list_elem.clone();
+            move || {
+                show_workspace_preview(view.find, page.file_path, &list_elem);
+                view.redraw();
+                refresh_view();
+            }
+        };
+        match action {
+            WorkspaceAction::Preview => {
+                let replace = WorkspaceReplace {
+                    query: query_input.value(),
+                    replacement: replacement_input.value(),
+                    options: self.find.get_untracked().options,
+                };
+                preview_workspace_replace(self.find, page.file_path, replace, after);
+            },
+            WorkspaceAction::Replace => apply_workspace_replace(page.cx, page.page_data, page_elem, 
+                self.find, page.file_path, &list_elem, after),
+            WorkspaceAction::Undo => undo_workspace_replace(page.cx, page.page_data, page_elem, 
+                self.find, page.file_path, after),
+            WorkspaceAction::Close => {
+                close_workspace_find(self.find);
+                show_workspace_preview(self.find, page.file_path, &list_elem);
+                self.redraw();
+                page.refocus();
+            },
+        }
+    }
+
+    pub fn handle_keydown(&self, event: KeyboardEvent, page: PageView, refresh_view: impl Fn() + 'static) {
+        if let Some(action) = process_workspace_find_keydown(&event) {
+            self.run(action, page, refresh_view);
+        }
+    }
+
+    pub fn handle_mousedown(&self, event: MouseEvent, page: PageView, refresh_view: impl Fn() + 'static) {
+        let (action, option) = find_button_at(event.target());
+        if action.is_none() && option.is_none() { return }
+        // keep the focus in the input
+        event.prevent_default();
+        if let Some(action) = action.and_then(|a| WorkspaceAction::from_name(&a)) {
+            self.run(action, page, refresh_view);
+        }
+        if let Some(option) = option {
+            self.find.update_untracked(|f| toggle_find_option(&mut f.options, &option));
+            self.redraw();
+        }
+    }
+}